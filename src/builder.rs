@@ -117,7 +117,7 @@ macro_rules! HG {
                 $a: $v,
             )*
             ..Default::default()
-        }.run(&mut Basic { })
+        }.run(&mut Basic::default())
       }}
 }
 
@@ -149,6 +149,34 @@ macro_rules! runcommand {
     }};
 }
 
+#[macro_export]
+macro_rules! runcommand_stream {
+    ( $client: expr, $name: expr, $args: expr $(, $o: expr, $x: expr )* ) => {{
+        let mut tmp = Vec::new();
+        tmp.push($name);
+        $(
+            let v = if let Some(s) = $x.mk($o, &mut tmp) {
+                s
+            } else {
+                String::new()
+            };
+            if !v.is_empty() {
+                tmp.push($o);
+                tmp.push(&v);
+            }
+        )*
+        if !$args.is_empty() {
+            tmp.push("--");
+            for arg in $args {
+                if !arg.is_empty() {
+                    tmp.push(arg);
+                }
+            }
+        }
+        $client.runcommand_stream(&tmp)
+    }};
+}
+
 #[macro_export]
 macro_rules! runcommand_with_prompt {
     ( $client: expr, $name: expr, $prompt: expr, $args: expr $(, $o: expr, $x: expr )* ) => {{
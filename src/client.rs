@@ -6,13 +6,20 @@ extern crate byteorder;
 extern crate subprocess;
 
 use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+use std::collections::HashMap;
 use std::env;
-use std::ffi::OsString;
+use std::ffi::{OsStr, OsString};
 use std::fs::File;
-use std::io::{Cursor, Read, Write};
+use std::io::{self, Cursor, ErrorKind, Read, Write};
 use std::path::{Path, PathBuf};
 use std::result::Result;
-use subprocess::{Popen, PopenConfig, Redirection};
+use subprocess::{ExitStatus, Popen, PopenConfig, Redirection};
+
+use crate::encoding::Encoding;
+pub use crate::error::{CommandError, HglibError};
+use crate::json;
+use crate::replay::{Frame, Session};
+use crate::version;
 
 pub trait Runner {
     /// Run a command
@@ -21,58 +28,466 @@ pub trait Runner {
         args: &'a [&str],
         prompt: Option<Box<dyn Prompt + 'a>>,
     ) -> Result<(Vec<u8>, i32), HglibError>;
+
+    /// Get the information sent by the server (if any)
+    fn server_info(&self) -> Option<&ServerInfo> {
+        None
+    }
+
+    /// Get the encoding used to send the arguments and to decode the output
+    fn server_encoding(&self) -> Encoding {
+        self.server_info()
+            .map_or(Encoding::Utf8, |info| Encoding::from_name(&info.encoding))
+    }
+
+    /// Get the data written on the error channel by the last command which succeeded
+    fn warnings(&self) -> &[u8] {
+        &[]
+    }
+
+    /// Check if the read commands must be run with `-T json`
+    fn use_json(&mut self) -> Result<bool, HglibError> {
+        Ok(false)
+    }
+
+    /// Run a command and read its output chunk by chunk
+    ///
+    /// By default the whole output is read before returning.
+    fn runcommand_stream<'r>(
+        &'r mut self,
+        args: &[&str],
+    ) -> Result<Box<dyn Output + 'r>, HglibError> {
+        let (out, _) = self.runcommand(args, None)?;
+        Ok(Box::new(Buffered(Some(out))))
+    }
 }
 
 pub trait Prompt {
     fn call(&mut self, size: usize) -> &[u8];
+
+    /// Answer a request on the input knowing the output written so far by the command
+    fn answer(&mut self, size: usize, _output: &[u8]) -> &[u8] {
+        self.call(size)
+    }
+
+    /// Get all the data to write on the input when the runner has no channel to ask
+    /// for them (e.g. Basic): None when the prompt only answers some questions
+    fn input(&mut self) -> Option<Vec<u8>> {
+        None
+    }
+}
+
+/// The output of a command read as it's sent by the server
+pub trait Output {
+    /// Get the next chunk of output (None when the command is done)
+    ///
+    /// If the command failed, the error is returned once all the output has been read.
+    fn next_chunk(&mut self) -> Result<Option<Vec<u8>>, HglibError>;
+
+    /// Write all the remaining output and get its size
+    fn copy_to(&mut self, writer: &mut dyn Write) -> Result<u64, HglibError> {
+        let mut size = 0;
+        while let Some(chunk) = self.next_chunk()? {
+            writer.write_all(&chunk)?;
+            size += chunk.len() as u64;
+        }
+        writer.flush()?;
+        Ok(size)
+    }
+}
+
+/// Read the output of a command as it's sent by the server
+///
+/// The errors of the command are returned as `io::Error` wrapping an `HglibError`.
+pub struct Reader<'r> {
+    output: Box<dyn Output + 'r>,
+    chunk: Vec<u8>,
+    pos: usize,
+}
+
+impl<'r> Reader<'r> {
+    pub fn new(output: Box<dyn Output + 'r>) -> Self {
+        Self {
+            output,
+            chunk: Vec::new(),
+            pos: 0,
+        }
+    }
+}
+
+impl<'r> Read for Reader<'r> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.pos == self.chunk.len() {
+            match self.output.next_chunk() {
+                Ok(Some(chunk)) => {
+                    self.chunk = chunk;
+                    self.pos = 0;
+                }
+                Ok(None) => return Ok(0),
+                Err(err) => return Err(io::Error::other(err)),
+            }
+        }
+        let len = buf.len().min(self.chunk.len() - self.pos);
+        buf[..len].copy_from_slice(&self.chunk[self.pos..self.pos + len]);
+        self.pos += len;
+        Ok(len)
+    }
+}
+
+/// The whole output of a command
+struct Buffered(Option<Vec<u8>>);
+
+impl Output for Buffered {
+    fn next_chunk(&mut self) -> Result<Option<Vec<u8>>, HglibError> {
+        Ok(self.0.take())
+    }
+}
+
+/// The information sent by the server in its hello message
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ServerInfo {
+    /// the commands supported by the server (e.g. runcommand, getencoding)
+    pub capabilities: Vec<String>,
+    /// the encoding used by the server
+    pub encoding: String,
+    /// the server process id
+    pub pid: Option<u32>,
+    /// the server process group id
+    pub pgid: Option<u32>,
+    /// the other fields
+    pub others: HashMap<String, String>,
+}
+
+impl ServerInfo {
+    /// Parse the hello message
+    pub fn parse(data: &[u8]) -> Result<ServerInfo, HglibError> {
+        let mut info = ServerInfo::default();
+        let mut has_capabilities = false;
+        let mut has_encoding = false;
+        for line in std::str::from_utf8(data)?.lines() {
+            let mut iter = line.splitn(2, ": ");
+            let name = iter.next().unwrap();
+            let value = iter.next().ok_or_else(|| {
+                HglibError::protocol(format!("Cannot read hello: invalid line: {}", line))
+            })?;
+            match name {
+                "capabilities" => {
+                    info.capabilities = value.split_whitespace().map(|c| c.to_string()).collect();
+                    has_capabilities = true;
+                }
+                "encoding" => {
+                    info.encoding = value.to_string();
+                    has_encoding = true;
+                }
+                "pid" => {
+                    info.pid = value.parse().ok();
+                }
+                "pgid" => {
+                    info.pgid = value.parse().ok();
+                }
+                _ => {
+                    info.others.insert(name.to_string(), value.to_string());
+                }
+            }
+        }
+
+        if !has_capabilities {
+            return Err(HglibError::protocol("Cannot read hello: no capabilities"));
+        }
+
+        if !has_encoding {
+            return Err(HglibError::protocol("Cannot read hello: no encoding"));
+        }
+
+        Ok(info)
+    }
+
+    /// Check if the server has the given capability
+    pub fn has_capability(&self, capability: &str) -> bool {
+        self.capabilities.iter().any(|c| c == capability)
+    }
 }
 
 #[derive(Debug)]
 pub struct Client {
     /// the server process
     server: Popen,
-    /// the encoding used for this process
-    encoding: String,
+    /// the information sent by the server
+    info: ServerInfo,
     /// the canonicalized path
     path: PathBuf,
+    /// the data written on the error channel by the last successful command
+    warnings: Vec<u8>,
+    /// the recorded sessions when recording is on
+    sessions: Option<Vec<Session>>,
+    /// the parsing backend for the read commands
+    json: Json,
 }
 
-pub struct Basic {}
+/// Use `-T json` for the read commands
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Json {
+    Off,
+    /// On if the hg version supports it (checked on the first use)
+    Auto,
+    On,
+}
 
+/// Build a Client with some custom options for the server
+/// # Example
+/// ```no_run
+/// extern crate hglib;
+///
+/// use hglib::ClientBuilder;
+///
+/// fn main() {
+///     let client = ClientBuilder::new("my_hg_repo")
+///         .hg("python3")
+///         .hg_arg("-m")
+///         .hg_arg("mercurial")
+///         .env("HGRCPATH", "")
+///         .plain_except("i18n")
+///         .config("ui.username=foo@bar.com")
+///         .traceback(true)
+///         .open()
+///         .unwrap();
+/// }
+/// ```
 #[derive(Debug)]
-pub struct HglibError {
-    pub code: i32,
-    pub out: Option<Vec<u8>>,
-    msg: String,
+pub struct ClientBuilder {
+    /// the repository path
+    path: PathBuf,
+    /// the hg executable and its first arguments
+    hg: Vec<OsString>,
+    /// the variables to set (or to unset when None)
+    env: Vec<(OsString, Option<OsString>)>,
+    /// inherit the environment of the current process
+    inherit_env: bool,
+    /// set HGPLAIN
+    plain: bool,
+    /// the features to put in HGPLAINEXCEPT
+    plain_except: Vec<String>,
+    /// the encoding (HGENCODING) used by the server
+    encoding: String,
+    /// the --config values
+    configs: Vec<String>,
+    /// the global options (e.g. --traceback)
+    options: Vec<String>,
+    /// the working directory (the repository path if None)
+    cwd: Option<PathBuf>,
+    /// use `-T json` for the read commands when hg supports it
+    json: bool,
 }
 
-impl HglibError {
-    pub(crate) fn handle_err(x: Result<(Vec<u8>, i32), HglibError>) -> Result<bool, HglibError> {
-        match x {
-            Ok((_, code)) => Ok(code == 0),
-            Err(err) => {
-                if err.code == 0 {
-                    Ok(true)
-                } else if err.code == 1 {
-                    Ok(false)
-                } else {
-                    Err(err)
-                }
+impl ClientBuilder {
+    pub fn new<P: AsRef<Path>>(path: P) -> Self {
+        Self {
+            path: path.as_ref().to_path_buf(),
+            hg: vec![OsString::from("hg")],
+            env: Vec::new(),
+            inherit_env: true,
+            plain: true,
+            plain_except: Vec::new(),
+            encoding: String::new(),
+            configs: Vec::new(),
+            options: Vec::new(),
+            cwd: None,
+            json: false,
+        }
+    }
+
+    /// Set the hg executable to use (default is hg from PATH)
+    pub fn hg<S: Into<OsString>>(mut self, hg: S) -> Self {
+        self.hg = vec![hg.into()];
+        self
+    }
+
+    /// Add an argument after the executable (e.g. `-m mercurial` for python)
+    pub fn hg_arg<S: Into<OsString>>(mut self, arg: S) -> Self {
+        self.hg.push(arg.into());
+        self
+    }
+
+    /// Set an environment variable for the server
+    pub fn env<K: Into<OsString>, V: Into<OsString>>(mut self, key: K, value: V) -> Self {
+        self.env.push((key.into(), Some(value.into())));
+        self
+    }
+
+    /// Unset an environment variable for the server
+    pub fn env_remove<K: Into<OsString>>(mut self, key: K) -> Self {
+        self.env.push((key.into(), None));
+        self
+    }
+
+    /// Don't inherit the environment of the current process
+    /// (the variables set with env are kept)
+    pub fn env_clear(mut self) -> Self {
+        self.inherit_env = false;
+        self
+    }
+
+    /// Set HGPLAIN (default is true)
+    pub fn plain(mut self, plain: bool) -> Self {
+        self.plain = plain;
+        self
+    }
+
+    /// Add a feature to HGPLAINEXCEPT
+    pub fn plain_except(mut self, feature: &str) -> Self {
+        self.plain_except.push(feature.to_string());
+        self
+    }
+
+    /// Set the encoding (HGENCODING) used by the server
+    pub fn encoding(mut self, encoding: &str) -> Self {
+        self.encoding = encoding.to_string();
+        self
+    }
+
+    /// Add a --config value
+    pub fn config(mut self, config: &str) -> Self {
+        self.configs.push(config.to_string());
+        self
+    }
+
+    /// Add a global option (e.g. --verbose)
+    pub fn option(mut self, option: &str) -> Self {
+        self.options.push(option.to_string());
+        self
+    }
+
+    /// Add --traceback to the server options
+    pub fn traceback(self, traceback: bool) -> Self {
+        if traceback {
+            self.option("--traceback")
+        } else {
+            self
+        }
+    }
+
+    /// Add --profile to the server options
+    pub fn profile(self, profile: bool) -> Self {
+        if profile {
+            self.option("--profile")
+        } else {
+            self
+        }
+    }
+
+    /// Set the working directory of the server
+    pub fn cwd<P: AsRef<Path>>(mut self, cwd: P) -> Self {
+        self.cwd = Some(cwd.as_ref().to_path_buf());
+        self
+    }
+
+    /// Parse the output of the read commands (branches, tags, ...) from `-T json`
+    /// when the hg version supports it, else the text output is used (default is false)
+    pub fn json(mut self, json: bool) -> Self {
+        self.json = json;
+        self
+    }
+
+    fn get_env(&self) -> Vec<(OsString, OsString)> {
+        let mut env: Vec<(OsString, OsString)> = if self.inherit_env {
+            env::vars_os().collect()
+        } else {
+            Vec::new()
+        };
+
+        for (key, value) in self.env.iter() {
+            set_env(&mut env, key, value.as_deref());
+        }
+
+        if self.plain {
+            set_env(&mut env, "HGPLAIN".as_ref(), Some("1".as_ref()));
+            if !self.plain_except.is_empty() {
+                let except = self.plain_except.join(",");
+                set_env(&mut env, "HGPLAINEXCEPT".as_ref(), Some(except.as_ref()));
             }
+        } else {
+            set_env(&mut env, "HGPLAIN".as_ref(), None);
+            set_env(&mut env, "HGPLAINEXCEPT".as_ref(), None);
         }
+        if !self.encoding.is_empty() {
+            set_env(
+                &mut env,
+                "HGENCODING".as_ref(),
+                Some(self.encoding.as_ref()),
+            );
+        }
+
+        env
     }
-}
 
-impl<T: std::string::ToString> From<T> for HglibError {
-    fn from(err: T) -> HglibError {
-        HglibError {
-            code: -1,
-            out: None,
-            msg: err.to_string(),
+    /// Spawn the server and open the client
+    pub fn open(self) -> Result<Client, HglibError> {
+        let path = self.path.canonicalize()?;
+        let cwd = if let Some(cwd) = self.cwd.as_ref() {
+            cwd.clone()
+        } else {
+            path.clone()
+        };
+
+        let mut args = self.hg.clone();
+        for arg in &["serve", "--cmdserver", "pipe", "-R"] {
+            args.push(OsString::from(arg));
+        }
+        args.push(path.clone().into_os_string());
+        for c in self.configs.iter() {
+            args.push(OsString::from("--config"));
+            args.push(OsString::from(c));
         }
+        args.extend(self.options.iter().map(OsString::from));
+
+        let mut server = Popen::create(
+            &args,
+            PopenConfig {
+                stdout: Redirection::Pipe,
+                stdin: Redirection::Pipe,
+                stderr: Redirection::Pipe,
+                env: Some(self.get_env()),
+                cwd: Some(cwd.into_os_string()),
+                ..Default::default()
+            },
+        )
+        .map_err(HglibError::spawn)?;
+        let info = Client::read_hello(&mut server)?;
+        let client = Client {
+            server,
+            info,
+            path,
+            warnings: Vec::new(),
+            sessions: None,
+            json: if self.json { Json::Auto } else { Json::Off },
+        };
+        Ok(client)
     }
 }
 
+/// Set (or unset with None) a variable in an environment: an inherited value is replaced
+fn set_env(env: &mut Vec<(OsString, OsString)>, key: &OsStr, value: Option<&OsStr>) {
+    env.retain(|(k, _)| k != key);
+    if let Some(value) = value {
+        env.push((key.to_os_string(), value.to_os_string()));
+    }
+}
+
+/// A runner spawning a new hg process for each command
+#[derive(Debug)]
+pub struct Basic {
+    /// the hg executable
+    hg: OsString,
+    /// the working directory (the current one if None)
+    cwd: Option<PathBuf>,
+    /// the encoding used for this process
+    encoding: String,
+    /// the --config values
+    configs: Vec<String>,
+    /// the data written on stderr by the last command which succeeded
+    warnings: Vec<u8>,
+}
+
 impl Drop for Client {
     fn drop(&mut self) {
         self.close().unwrap();
@@ -118,38 +533,11 @@ impl Client {
         encoding: &str,
         configs: &[&str],
     ) -> Result<Client, HglibError> {
-        let mut env: Vec<(OsString, OsString)> = env::vars_os().collect();
-        env.push((OsString::from("HGPLAIN"), OsString::from("1")));
-        if !encoding.is_empty() {
-            env.push((OsString::from("HGENCODING"), OsString::from(encoding)));
-        }
-
-        let path = path.as_ref().to_path_buf().canonicalize()?;
-        let path_str = path.to_str().unwrap();
-
-        let mut args = vec!["hg", "serve", "--cmdserver", "pipe", "-R", path_str];
+        let mut builder = ClientBuilder::new(path).encoding(encoding);
         for c in configs.iter() {
-            args.push("--config");
-            args.push(c);
+            builder = builder.config(c);
         }
-        let mut server = Popen::create(
-            &args,
-            PopenConfig {
-                stdout: Redirection::Pipe,
-                stdin: Redirection::Pipe,
-                stderr: Redirection::Pipe,
-                env: Some(env),
-                cwd: Some(OsString::from(path_str)),
-                ..Default::default()
-            },
-        )?;
-        let encoding = Client::read_hello(&mut server)?;
-        let client = Client {
-            server,
-            encoding,
-            path,
-        };
-        Ok(client)
+        builder.open()
     }
 
     /// Close the client
@@ -164,81 +552,159 @@ impl Client {
         &self.path
     }
 
-    fn read_hello(server: &mut Popen) -> Result<String, HglibError> {
+    fn read_hello(server: &mut Popen) -> Result<ServerInfo, HglibError> {
         let stdout = server.stdout.as_mut().unwrap();
-        let mut chan: Vec<u8> = vec![0; 1];
-        let n = stdout.read(&mut chan)?;
-        if n != 1 || chan[0] != b'o' {
-            return Err("Cannot read hello".into());
-        }
-
-        let len = stdout.read_u32::<BigEndian>()? as usize;
-        let mut data: Vec<u8> = vec![0; len];
-
-        let n = stdout.read(&mut data)?;
-        if n != len {
-            return Err("Cannot read hello (invalid length)".into());
+        let (chan, len) = Client::read_header(stdout)?;
+        if chan != b'o' {
+            return Err(HglibError::protocol("Cannot read hello"));
         }
 
-        let out = std::str::from_utf8(&data)?;
-        let out: Vec<&str> = out.split('\n').collect();
-
-        if !out[0].contains("capabilities: ") {
-            return Err("Cannot read hello: no capabilities ".into());
-        }
+        let mut data = Vec::new();
+        Client::read_data(len, &mut data, stdout)?;
 
-        if !out[1].contains("encoding: ") {
-            return Err("Cannot read hello: no encoding ".into());
-        }
+        ServerInfo::parse(&data)
+    }
 
-        Ok(out[1]["encoding: ".len()..].to_string())
+    fn read_header(stdout: &mut File) -> Result<(u8, usize), HglibError> {
+        let mut chan = [0; 1];
+        Client::read_exact(stdout, &mut chan)?;
+        let mut len = [0; 4];
+        Client::read_exact(stdout, &mut len)?;
+        let len = Cursor::new(&len).read_u32::<BigEndian>()? as usize;
+        Ok((chan[0], len))
     }
 
     fn read_data(
-        mut to_read: usize,
+        to_read: usize,
         output: &mut Vec<u8>,
         stdout: &mut File,
     ) -> Result<(), HglibError> {
-        let mut pos = output.len();
+        let pos = output.len();
         output.resize(pos + to_read, 0);
-        loop {
-            let n = stdout.read(&mut output[pos..])?;
-            if n == to_read {
-                break;
+        Client::read_exact(stdout, &mut output[pos..])
+    }
+
+    fn read_exact(stdout: &mut File, buf: &mut [u8]) -> Result<(), HglibError> {
+        // read_exact handles the partial reads
+        stdout.read_exact(buf).map_err(|err| {
+            if err.kind() == ErrorKind::UnexpectedEof {
+                HglibError::protocol("Unexpected end of the server output")
+            } else {
+                HglibError::Io(err)
             }
-            to_read -= n;
-            pos += n;
+        })
+    }
+
+    pub fn encoding(&self) -> &str {
+        &self.info.encoding
+    }
+
+    /// Get the information sent by the server in its hello message
+    pub fn server_info(&self) -> &ServerInfo {
+        &self.info
+    }
+
+    /// Check if the server has the given capability
+    pub fn has_capability(&self, capability: &str) -> bool {
+        self.info.has_capability(capability)
+    }
+
+    /// Ask the server for its encoding (getencoding command)
+    ///
+    /// The returned encoding is then used to transcode the arguments and the output.
+    pub fn getencoding(&mut self) -> Result<String, HglibError> {
+        if !self.has_capability("getencoding") {
+            return Err(HglibError::protocol(
+                "The server doesn't have the getencoding capability",
+            ));
         }
+
+        let stdin = self.server.stdin.as_mut().unwrap();
+        stdin.write_all(b"getencoding\n")?;
+        stdin.flush()?;
+
+        let stdout = self.server.stdout.as_mut().unwrap();
+        let (chan, len) = Client::read_header(stdout)?;
+        if chan != b'r' {
+            return Err(HglibError::protocol(format!(
+                "Unexpected channel {} for getencoding",
+                chan as char
+            )));
+        }
+        let mut data = Vec::new();
+        Client::read_data(len, &mut data, stdout)?;
+        self.info.encoding = String::from_utf8(data)?;
+
+        Ok(self.info.encoding.clone())
+    }
+
+    fn write_command(&mut self, args: &[&str]) -> Result<(), HglibError> {
+        /* Write the data on stdin:
+        runcommand\n
+        len(arg0\0arg1\0arg2...)
+        arg0\0arg1\0arg2... */
+        let encoding = self.server_encoding();
+        let mut data = Vec::new();
+        for (i, arg) in args.iter().enumerate() {
+            if i != 0 {
+                data.push(b'\0');
+            }
+            data.extend_from_slice(&encoding.encode(arg)?);
+        }
+        let mut stdin = self.server.stdin.as_mut().unwrap();
+        writeln!(&mut stdin, "runcommand")?;
+        stdin.write_u32::<BigEndian>(data.len() as u32)?;
+        stdin.write_all(&data)?;
+        stdin.flush()?;
         Ok(())
     }
 
-    pub fn encoding(&self) -> &str {
-        &self.encoding
+    /// Get the data written on the error channel by the last command
+    /// which succeeded (e.g. warnings)
+    pub fn warnings(&self) -> &[u8] {
+        &self.warnings
+    }
+
+    /// Start to record the frames sent by the server for each command
+    pub fn start_recording(&mut self) {
+        self.sessions = Some(Vec::new());
+    }
+
+    /// Stop the recording and get the recorded sessions
+    pub fn stop_recording(&mut self) -> Vec<Session> {
+        self.sessions.take().unwrap_or_default()
     }
 }
 
 impl Runner for Client {
+    fn warnings(&self) -> &[u8] {
+        Client::warnings(self)
+    }
+
+    fn server_info(&self) -> Option<&ServerInfo> {
+        Some(&self.info)
+    }
+
+    fn use_json(&mut self) -> Result<bool, HglibError> {
+        if self.json == Json::Auto {
+            let version = version::Arg {}.run(self)?;
+            let (major, minor) = json::MIN_VERSION;
+            self.json = if version >= (major, minor, None) {
+                Json::On
+            } else {
+                Json::Off
+            };
+        }
+        Ok(self.json == Json::On)
+    }
+
     fn runcommand<'a>(
         &mut self,
         args: &'a [&str],
         mut prompt: Option<Box<dyn Prompt + 'a>>,
     ) -> Result<(Vec<u8>, i32), HglibError> {
-        /* Write the data on stdin:
-        runcommand\n
-        len(arg0\0arg1\0arg2...)
-        arg0\0arg1\0arg2... */
-        let mut stdin = self.server.stdin.as_mut().unwrap();
-        let args_size: usize = args.iter().map(|arg| -> usize { arg.len() }).sum();
-        let size = args_size + args.len() - 1;
-        writeln!(&mut stdin, "runcommand")?;
-        stdin.write_u32::<BigEndian>(size as u32)?;
-        if let Some((first, args)) = args.split_first() {
-            write!(&mut stdin, "{}", first)?;
-            for arg in args {
-                write!(&mut stdin, "\0{}", arg)?;
-            }
-        }
-        stdin.flush()?;
+        self.write_command(args)?;
+        let stdin = self.server.stdin.as_mut().unwrap();
 
         /* Read the data on stdout:
         o{u32 = len}{data}
@@ -246,91 +712,338 @@ impl Runner for Client {
         r{u32} */
         let stdout = self.server.stdout.as_mut().unwrap();
         let mut out = Vec::<u8>::with_capacity(4096);
-        let mut chan: Vec<u8> = vec![0; 1];
-        //let mut returned_err: Option<String> = None;
+        let mut err = Vec::<u8>::new();
+        let mut frames = self.sessions.as_ref().map(|_| Vec::new());
+        // an error to return when the command is done (e.g. a prompt was required)
+        let mut error = None;
+        self.warnings.clear();
         loop {
-            let n = stdout.read(&mut chan)?;
-            if n != 1 {
-                return Err("Empty stdout".into());
-            }
-            let len = stdout.read_u32::<BigEndian>()? as usize;
-            match chan[0] {
+            let (chan, len) = Client::read_header(stdout)?;
+            match chan {
                 b'e' => {
-                    // We've an error
-                    let mut err = Vec::<u8>::with_capacity(512);
+                    let pos = err.len();
                     Client::read_data(len, &mut err, stdout)?;
-                    //let err = String::from_utf8(err)?;
-                    //returned_err = Some(err);
+                    if let Some(frames) = frames.as_mut() {
+                        frames.push(Frame::Error(err[pos..].to_vec()));
+                    }
                 }
                 b'o' => {
+                    let pos = out.len();
                     Client::read_data(len, &mut out, stdout)?;
+                    if let Some(frames) = frames.as_mut() {
+                        frames.push(Frame::Output(out[pos..].to_vec()));
+                    }
                 }
                 b'r' => {
-                    let mut code: Vec<u8> = vec![0; len];
-                    stdout.read_exact(&mut code)?;
-                    let mut cur = Cursor::new(&code);
-                    let code = cur.read_i32::<BigEndian>()?;
-                    // TODO: error may have been set and code == 0 when we've a warning
-                    // so handle that with an error handler
-                    return /* if let Some(msg) = returned_err {
-                        Err(HglibError {
-                            code,
-                            out: Some(out),
-                            msg,
-                        })
-                    } else */ if code != 0 {
-                        Err(HglibError {
-                            code,
-                            out: Some(out.clone()),
-                            msg: String::from_utf8(out).unwrap(),
-                        })
+                    let mut code = Vec::new();
+                    Client::read_data(len, &mut code, stdout)?;
+                    let code = Cursor::new(&code).read_i32::<BigEndian>()?;
+                    if let (Some(sessions), Some(mut frames)) = (self.sessions.as_mut(), frames) {
+                        frames.push(Frame::Result(code));
+                        let mut session = Session::new(args);
+                        session.frames = frames;
+                        sessions.push(session);
+                    }
+                    return if let Some(error) = error {
+                        Err(error)
+                    } else if code != 0 {
+                        Err(HglibError::command(args, code, out, err))
                     } else {
+                        // Something on the error channel with a null code is a warning
+                        self.warnings = err;
                         Ok((out, code))
                     };
                 }
-                b'L' => {
+                b'L' | b'I' => {
+                    if let Some(frames) = frames.as_mut() {
+                        frames.push(if chan == b'L' {
+                            Frame::Line(len)
+                        } else {
+                            Frame::Input(len)
+                        });
+                    }
                     if let Some(prompt) = prompt.as_mut() {
-                        let buf = prompt.call(len);
+                        let buf = prompt.answer(len, &out);
                         stdin.write_u32::<BigEndian>(buf.len() as u32)?;
                         stdin.write_all(buf)?;
                         stdin.flush()?;
                     } else {
                         stdin.write_u32::<BigEndian>(0)?;
                         stdin.flush()?;
-                        return Err("Hglib error: something is expected on stdin, please implement a Prompt".into());
+                        error = Some(HglibError::PromptRequired(
+                            args.iter().map(|arg| arg.to_string()).collect(),
+                        ));
                     }
                 }
                 _ => {
-                    return Err(format!("Hglib error: invalid channel {}", chan[0] as char).into());
+                    return Err(HglibError::protocol(format!(
+                        "Invalid channel {}",
+                        chan as char
+                    )));
                 }
             }
         }
     }
+
+    fn runcommand_stream<'r>(
+        &'r mut self,
+        args: &[&str],
+    ) -> Result<Box<dyn Output + 'r>, HglibError> {
+        self.write_command(args)?;
+        self.warnings.clear();
+        let frames = self.sessions.as_ref().map(|_| Vec::new());
+        Ok(Box::new(ClientOutput {
+            client: self,
+            args: args.iter().map(|arg| arg.to_string()).collect(),
+            err: Vec::new(),
+            frames,
+            error: None,
+            done: false,
+        }))
+    }
+}
+
+/// The output of a command run by a Client: the frames are read on demand
+///
+/// When it's dropped before the end of the command, the remaining frames are
+/// read (and discarded) to put the server back in a usable state.
+struct ClientOutput<'c> {
+    client: &'c mut Client,
+    args: Vec<String>,
+    /// the data written on the error channel
+    err: Vec<u8>,
+    /// the recorded frames when recording is on
+    frames: Option<Vec<Frame>>,
+    /// an error to return when the command is done (e.g. a prompt was required)
+    error: Option<HglibError>,
+    done: bool,
+}
+
+impl<'c> ClientOutput<'c> {
+    fn read(&mut self) -> Result<Option<Vec<u8>>, HglibError> {
+        let stdin = self.client.server.stdin.as_mut().unwrap();
+        let stdout = self.client.server.stdout.as_mut().unwrap();
+        while !self.done {
+            let (chan, len) = Client::read_header(stdout)?;
+            match chan {
+                b'o' => {
+                    let mut out = Vec::with_capacity(len);
+                    Client::read_data(len, &mut out, stdout)?;
+                    if let Some(frames) = self.frames.as_mut() {
+                        frames.push(Frame::Output(out.clone()));
+                    }
+                    return Ok(Some(out));
+                }
+                b'e' => {
+                    let pos = self.err.len();
+                    Client::read_data(len, &mut self.err, stdout)?;
+                    if let Some(frames) = self.frames.as_mut() {
+                        frames.push(Frame::Error(self.err[pos..].to_vec()));
+                    }
+                }
+                b'r' => {
+                    let mut code = Vec::new();
+                    Client::read_data(len, &mut code, stdout)?;
+                    let code = Cursor::new(&code).read_i32::<BigEndian>()?;
+                    self.done = true;
+                    let args: Vec<&str> = self.args.iter().map(String::as_str).collect();
+                    if let (Some(sessions), Some(mut frames)) =
+                        (self.client.sessions.as_mut(), self.frames.take())
+                    {
+                        frames.push(Frame::Result(code));
+                        let mut session = Session::new(&args);
+                        session.frames = frames;
+                        sessions.push(session);
+                    }
+                    let err = std::mem::take(&mut self.err);
+                    if let Some(error) = self.error.take() {
+                        return Err(error);
+                    } else if code != 0 {
+                        return Err(HglibError::command(&args, code, Vec::new(), err));
+                    }
+                    self.client.warnings = err;
+                }
+                b'L' | b'I' => {
+                    if let Some(frames) = self.frames.as_mut() {
+                        frames.push(if chan == b'L' {
+                            Frame::Line(len)
+                        } else {
+                            Frame::Input(len)
+                        });
+                    }
+                    stdin.write_u32::<BigEndian>(0)?;
+                    stdin.flush()?;
+                    self.error = Some(HglibError::PromptRequired(self.args.clone()));
+                }
+                _ => {
+                    return Err(HglibError::protocol(format!(
+                        "Invalid channel {}",
+                        chan as char
+                    )));
+                }
+            }
+        }
+        Ok(None)
+    }
+}
+
+impl<'c> Output for ClientOutput<'c> {
+    fn next_chunk(&mut self) -> Result<Option<Vec<u8>>, HglibError> {
+        let res = self.read();
+        if res.is_err() {
+            self.done = true;
+        }
+        res
+    }
+}
+
+impl<'c> Drop for ClientOutput<'c> {
+    fn drop(&mut self) {
+        while !self.done {
+            let _ = self.next_chunk();
+        }
+    }
+}
+
+impl Default for Basic {
+    fn default() -> Self {
+        Self {
+            hg: OsString::from("hg"),
+            cwd: None,
+            encoding: String::new(),
+            configs: Vec::new(),
+            warnings: Vec::new(),
+        }
+    }
+}
+
+impl Basic {
+    /// Set the hg executable to use (default is hg from PATH)
+    pub fn hg<S: Into<OsString>>(mut self, hg: S) -> Self {
+        self.hg = hg.into();
+        self
+    }
+
+    /// Set the working directory where the commands are run
+    pub fn cwd<P: AsRef<Path>>(mut self, cwd: P) -> Self {
+        self.cwd = Some(cwd.as_ref().to_path_buf());
+        self
+    }
+
+    /// Set the encoding (HGENCODING) used by hg
+    pub fn encoding(mut self, encoding: &str) -> Self {
+        self.encoding = encoding.to_string();
+        self
+    }
+
+    /// Add a --config value
+    pub fn config(mut self, config: &str) -> Self {
+        self.configs.push(config.to_string());
+        self
+    }
+
+    /// Get the data written on stderr by the last command which succeeded
+    /// (e.g. warnings)
+    pub fn warnings(&self) -> &[u8] {
+        &self.warnings
+    }
+
+    #[cfg(unix)]
+    fn to_os_string(arg: Vec<u8>) -> OsString {
+        use std::os::unix::ffi::OsStringExt;
+        OsString::from_vec(arg)
+    }
+
+    #[cfg(not(unix))]
+    fn to_os_string(arg: Vec<u8>) -> OsString {
+        // The arguments are passed as unicode to the process
+        OsString::from(String::from_utf8_lossy(&arg).into_owned())
+    }
 }
 
 impl Runner for Basic {
+    fn warnings(&self) -> &[u8] {
+        Basic::warnings(self)
+    }
+
+    fn server_encoding(&self) -> Encoding {
+        if self.encoding.is_empty() {
+            Encoding::Utf8
+        } else {
+            Encoding::from_name(&self.encoding)
+        }
+    }
+
     fn runcommand<'a>(
         &mut self,
         args: &'a [&str],
-        _: Option<Box<dyn Prompt + 'a>>,
+        prompt: Option<Box<dyn Prompt + 'a>>,
     ) -> Result<(Vec<u8>, i32), HglibError> {
-        let env: Vec<(OsString, OsString)> = env::vars_os().collect();
-        let mut command = Vec::with_capacity(args.len() + 1);
-        command.push("hg");
-        command.extend(args);
+        let mut env: Vec<(OsString, OsString)> = env::vars_os().collect();
+        set_env(&mut env, "HGPLAIN".as_ref(), Some("1".as_ref()));
+        if !self.encoding.is_empty() {
+            set_env(
+                &mut env,
+                "HGENCODING".as_ref(),
+                Some(self.encoding.as_ref()),
+            );
+        }
+
+        let mut command = Vec::with_capacity(args.len() + 2 * self.configs.len() + 1);
+        command.push(self.hg.clone());
+        for c in self.configs.iter() {
+            command.push(OsString::from("--config"));
+            command.push(OsString::from(c));
+        }
+        let encoding = self.server_encoding();
+        for arg in args {
+            command.push(Basic::to_os_string(encoding.encode(arg)?.into_owned()));
+        }
+
+        // There is no line channel here so all the prompt data is sent on stdin at once:
+        // a prompt which only answers some questions gets an empty input
+        let input = prompt.map(|mut prompt| prompt.input().unwrap_or_default());
+
+        let cwd = if let Some(cwd) = self.cwd.as_ref() {
+            cwd.clone()
+        } else {
+            env::current_dir()?
+        };
 
         let mut process = Popen::create(
             &command,
             PopenConfig {
+                stdin: if input.is_some() {
+                    Redirection::Pipe
+                } else {
+                    Redirection::None
+                },
                 stdout: Redirection::Pipe,
-                cwd: Some(OsString::from(std::env::current_dir().unwrap())),
+                stderr: Redirection::Pipe,
+                cwd: Some(cwd.into_os_string()),
                 env: Some(env),
                 ..Default::default()
             },
-        )?;
+        )
+        .map_err(HglibError::spawn)?;
+
+        let (out, err) = process.communicate_bytes(input.as_deref())?;
+        let out = out.unwrap_or_default();
+        let err = err.unwrap_or_default();
 
-        process.wait()?;
+        let code = match process.wait()? {
+            ExitStatus::Exited(code) => code as i32,
+            ExitStatus::Signaled(signal) => -i32::from(signal),
+            ExitStatus::Other(code) => code,
+            ExitStatus::Undetermined => -1,
+        };
 
-        Ok((Vec::new(), 0))
+        self.warnings.clear();
+        if code != 0 {
+            Err(HglibError::command(args, code, out, err))
+        } else {
+            self.warnings = err;
+            Ok((out, code))
+        }
     }
 }
@@ -26,7 +26,7 @@ impl<'a> Default for Arg<'a> {
 }
 
 impl<'a> Arg<'a> {
-    fn run(&self, client: &mut Client) -> Result<(Vec<u8>, i32), HglibError> {
+    fn runcommand<T: Runner>(&self, client: &mut T) -> Result<(Vec<u8>, i32), HglibError> {
         runcommand!(
             client,
             "add",
@@ -41,11 +41,15 @@ impl<'a> Arg<'a> {
             self.exclude
         )
     }
+
+    pub fn run<T: Runner>(&self, client: &mut T) -> Result<bool, HglibError> {
+        let (_, code) = self.runcommand(client)?;
+        Ok(code == 0)
+    }
 }
 
 impl Client {
     pub fn add(&mut self, x: Arg) -> Result<bool, HglibError> {
-        let (_, code) = x.run(self)?;
-        Ok(code == 0)
+        x.run(self)
     }
 }
@@ -28,10 +28,13 @@ impl<'a> Default for Arg<'a> {
 }
 
 impl<'a> Arg<'a> {
-    fn run(&self, client: &mut Client) -> Result<(Vec<u8>, i32), HglibError> {
+    fn runcommand<T: Runner>(&self, client: &mut T) -> Result<(Vec<u8>, i32), HglibError> {
         if let Some(x) = self.similarity {
             if x > 100 {
-                return Err(HglibError::from(format!("Invalid similarity: {}", x)));
+                return Err(HglibError::invalid_arg(format!(
+                    "Invalid similarity: {}",
+                    x
+                )));
             }
         }
         runcommand!(
@@ -50,10 +53,14 @@ impl<'a> Arg<'a> {
             self.exclude
         )
     }
+
+    pub fn run<T: Runner>(&self, client: &mut T) -> Result<bool, HglibError> {
+        HglibError::handle_err(self.runcommand(client))
+    }
 }
 
 impl Client {
     pub fn addremove(&mut self, x: Arg) -> Result<bool, HglibError> {
-        HglibError::handle_err(x.run(self))
+        x.run(self)
     }
 }
@@ -2,7 +2,10 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this file,
 // You can obtain one at http://mozilla.org/MPL/2.0/.
 
+use std::borrow::Cow;
+
 use crate::client::{Client, HglibError, Runner};
+use crate::encoding::Encoding;
 use crate::{runcommand, MkArg};
 
 pub struct Arg<'a> {
@@ -42,7 +45,7 @@ impl<'a> Default for Arg<'a> {
 }
 
 impl<'a> Arg<'a> {
-    fn run(&self, client: &mut Client) -> Result<(Vec<u8>, i32), HglibError> {
+    fn runcommand<T: Runner>(&self, client: &mut T) -> Result<(Vec<u8>, i32), HglibError> {
         runcommand!(
             client,
             "annotate",
@@ -73,14 +76,27 @@ impl<'a> Arg<'a> {
             self.exclude
         )
     }
+
+    pub fn run<T: Runner>(&self, client: &mut T) -> Result<Lines, HglibError> {
+        let (data, _) = self.runcommand(client)?;
+        Ok(Lines::new(data))
+    }
 }
 
 #[derive(Debug, PartialEq)]
 pub struct Line<'a> {
-    pub info: &'a str,
+    /// the annotation (e.g. the user and the revision): the bytes are kept as is
+    pub info: &'a [u8],
     pub content: &'a [u8],
 }
 
+impl<'a> Line<'a> {
+    /// Decode the annotation with the server encoding
+    pub fn info_lossy(&self, encoding: &Encoding) -> Cow<'a, str> {
+        encoding.decode_lossy(self.info)
+    }
+}
+
 pub struct Lines {
     buf: Vec<u8>,
     pos: usize,
@@ -97,15 +113,14 @@ impl Lines {
         }
 
         let mut info_end = 0;
-        let mut info = "";
+        let mut info: &[u8] = &[];
         for (n, c) in self.buf[self.pos..].iter().enumerate() {
             if *c == b':' {
                 if info_end == 0 {
                     if let Some(c) = self.buf.get(self.pos + n + 1) {
                         if *c == b' ' {
                             info_end = self.pos + n;
-                            let _info = unsafe { self.buf.get_unchecked(self.pos..info_end) };
-                            info = std::str::from_utf8(_info)?;
+                            info = unsafe { self.buf.get_unchecked(self.pos..info_end) };
                         }
                     }
                 }
@@ -121,7 +136,6 @@ impl Lines {
 
 impl Client {
     pub fn annotate(&mut self, x: Arg) -> Result<Lines, HglibError> {
-        let (data, _) = x.run(self)?;
-        Ok(Lines::new(data))
+        x.run(self)
     }
 }
@@ -2,8 +2,10 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this file,
 // You can obtain one at http://mozilla.org/MPL/2.0/.
 
+use std::io::Write;
+
 use crate::client::{Client, HglibError, Runner};
-use crate::{runcommand, MkArg};
+use crate::{runcommand, runcommand_stream, MkArg};
 
 pub struct Arg<'a> {
     pub dest: &'a str,
@@ -31,33 +33,64 @@ impl<'a> Default for Arg<'a> {
     }
 }
 
-impl<'a> Arg<'a> {
-    fn run(&self, client: &mut Client) -> Result<(Vec<u8>, i32), HglibError> {
-        runcommand!(
-            client,
+/// Run archive with the given runcommand macro and destination
+macro_rules! archive {
+    ( $runcommand: ident, $client: expr, $arg: expr, $dest: expr ) => {
+        $runcommand!(
+            $client,
             "archive",
-            &[self.dest],
+            &[$dest],
             "-r",
-            self.rev,
+            $arg.rev,
             "--no-decode",
-            self.nodecode,
+            $arg.nodecode,
             "-p",
-            self.prefix,
+            $arg.prefix,
             "-t",
-            self.typ,
+            $arg.typ,
             "-S",
-            self.subrepos,
+            $arg.subrepos,
             "-I",
-            self.include,
+            $arg.include,
             "-X",
-            self.exclude
+            $arg.exclude
         )
+    };
+}
+
+impl<'a> Arg<'a> {
+    fn runcommand<T: Runner>(&self, client: &mut T) -> Result<(Vec<u8>, i32), HglibError> {
+        archive!(runcommand, client, self, self.dest)
+    }
+
+    pub fn run<T: Runner>(&self, client: &mut T) -> Result<(), HglibError> {
+        self.runcommand(client)?;
+        Ok(())
+    }
+
+    /// Write the archive in the writer as it's sent by the server and get its size
+    ///
+    /// The destination is ignored and the type (e.g. tgz, zip) is required.
+    pub fn run_to<T: Runner, W: Write>(
+        &self,
+        client: &mut T,
+        writer: &mut W,
+    ) -> Result<u64, HglibError> {
+        if self.typ.is_empty() || self.typ == "files" {
+            return Err(HglibError::invalid_arg(
+                "An archive type is required to stream the archive",
+            ));
+        }
+        archive!(runcommand_stream, client, self, "-")?.copy_to(writer)
     }
 }
 
 impl Client {
     pub fn archive(&mut self, x: Arg) -> Result<(), HglibError> {
-        x.run(self)?;
-        Ok(())
+        x.run(self)
+    }
+
+    pub fn archive_to<W: Write>(&mut self, x: Arg, writer: &mut W) -> Result<u64, HglibError> {
+        x.run_to(self, writer)
     }
 }
@@ -32,7 +32,7 @@ impl<'a> Default for Arg<'a> {
 }
 
 impl<'a> Arg<'a> {
-    fn run(&self, client: &mut Client) -> Result<(Vec<u8>, i32), HglibError> {
+    fn runcommand<T: Runner>(&self, client: &mut T) -> Result<(Vec<u8>, i32), HglibError> {
         runcommand!(
             client,
             "backout",
@@ -53,11 +53,15 @@ impl<'a> Arg<'a> {
             self.user
         )
     }
+
+    pub fn run<T: Runner>(&self, client: &mut T) -> Result<(), HglibError> {
+        self.runcommand(client)?;
+        Ok(())
+    }
 }
 
 impl Client {
     pub fn backout(&mut self, x: Arg) -> Result<(), HglibError> {
-        x.run(self)?;
-        Ok(())
+        x.run(self)
     }
 }
@@ -3,7 +3,8 @@
 // You can obtain one at http://mozilla.org/MPL/2.0/.
 
 use crate::client::{Client, HglibError, Runner};
-use crate::runcommand;
+use crate::json;
+use crate::{runcommand, MkArg};
 
 pub struct Arg {}
 
@@ -14,27 +15,44 @@ impl Default for Arg {
 }
 
 impl Arg {
-    fn run(&self, client: &mut Client) -> Result<(Vec<u8>, i32), HglibError> {
-        runcommand!(client, "bookmarks", &[""])
+    fn runcommand<T: Runner>(
+        &self,
+        client: &mut T,
+        template: &str,
+    ) -> Result<(Vec<u8>, i32), HglibError> {
+        runcommand!(client, "bookmarks", &[""], "-T", template)
     }
-}
 
-#[derive(Debug, PartialEq)]
-pub struct Bookmark {
-    pub name: String,
-    pub rev: u64,
-    pub node: String,
-}
+    pub fn run<T: Runner>(&self, client: &mut T) -> Result<Bookmarks, HglibError> {
+        if client.use_json()? {
+            match self.run_json(client) {
+                Err(HglibError::Parse { .. }) => {}
+                res => return res,
+            }
+        }
+        self.run_text(client)
+    }
 
-#[derive(Debug, PartialEq)]
-pub struct Bookmarks {
-    pub bookmarks: Vec<Bookmark>,
-    pub current: Option<usize>,
-}
+    fn run_json<T: Runner>(&self, client: &mut T) -> Result<Bookmarks, HglibError> {
+        let (data, _) = self.runcommand(client, "json")?;
+        let mut bookmarks = Vec::new();
+        let mut current = None;
+        for obj in json::parse(&data)?.iter() {
+            if json::get_bool(obj, "active") {
+                current = Some(bookmarks.len());
+            }
+            bookmarks.push(Bookmark {
+                name: json::get_str(obj, "bookmark")?,
+                rev: json::get_u64(obj, "rev")?,
+                node: json::get_short_node(obj, "node")?,
+            });
+        }
+        Ok(Bookmarks { bookmarks, current })
+    }
 
-impl Client {
-    pub fn bookmarks(&mut self, x: Arg) -> Result<Bookmarks, HglibError> {
-        let (data, _) = x.run(self)?;
+    fn run_text<T: Runner>(&self, client: &mut T) -> Result<Bookmarks, HglibError> {
+        let encoding = client.server_encoding();
+        let (data, _) = self.runcommand(client, "")?;
         let empty = b"no bookmarks set";
         let mut bookmarks = Vec::new();
         let mut current = None;
@@ -54,17 +72,36 @@ impl Client {
             }
             let line = unsafe { line.get_unchecked(3..) };
             let mut iter = line.split(|x| *x == b' ').filter(|x| !x.is_empty());
-            let name = String::from_utf8(iter.next().unwrap().to_vec())?;
+            let name = encoding.decode_string(iter.next().unwrap())?;
             let rev_node = iter.next().unwrap();
             let iter = &mut rev_node.iter();
             let rev = iter
                 .take_while(|x| **x != b':')
                 .fold(0, |r, x| r * 10 + u64::from(*x - b'0'));
             let node = iter.as_slice();
-            let node = String::from_utf8(node.to_vec())?;
+            let node = encoding.decode_string(node)?;
             bookmarks.push(Bookmark { name, rev, node });
         }
 
         Ok(Bookmarks { bookmarks, current })
     }
 }
+
+#[derive(Debug, PartialEq)]
+pub struct Bookmark {
+    pub name: String,
+    pub rev: u64,
+    pub node: String,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Bookmarks {
+    pub bookmarks: Vec<Bookmark>,
+    pub current: Option<usize>,
+}
+
+impl Client {
+    pub fn bookmarks(&mut self, x: Arg) -> Result<Bookmarks, HglibError> {
+        x.run(self)
+    }
+}
@@ -22,7 +22,7 @@ impl<'a> Default for Arg<'a> {
 }
 
 impl<'a> Arg<'a> {
-    fn run(&self, client: &mut Client) -> Result<(Vec<u8>, i32), HglibError> {
+    fn runcommand<T: Runner>(&self, client: &mut T) -> Result<(Vec<u8>, i32), HglibError> {
         runcommand!(
             client,
             "branch",
@@ -33,16 +33,15 @@ impl<'a> Arg<'a> {
             self.force
         )
     }
-}
 
-impl Client {
-    pub fn branch(&mut self, x: Arg) -> Result<String, HglibError> {
-        if !x.name.is_empty() && x.clean {
-            return Err(HglibError::from("Cannot use both name and clean"));
+    pub fn run<T: Runner>(&self, client: &mut T) -> Result<String, HglibError> {
+        let encoding = client.server_encoding();
+        if !self.name.is_empty() && self.clean {
+            return Err(HglibError::invalid_arg("Cannot use both name and clean"));
         }
-        let (data, _) = x.run(self)?;
-        if !x.name.is_empty() {
-            Ok(x.name.to_string())
+        let (data, _) = self.runcommand(client)?;
+        if !self.name.is_empty() {
+            Ok(self.name.to_string())
         } else {
             let pos = if let Some(pos) = data
                 .iter()
@@ -54,14 +53,20 @@ impl Client {
             };
             let data = &data[..pos];
 
-            if !x.clean {
-                let o = String::from_utf8(data.to_vec())?;
+            if !self.clean {
+                let o = encoding.decode_string(data)?;
                 Ok(o)
             } else {
                 let len = "reset working directory to branch ".len();
-                let o = String::from_utf8(data[len..].to_vec())?;
+                let o = encoding.decode_string(&data[len..])?;
                 Ok(o)
             }
         }
     }
 }
+
+impl Client {
+    pub fn branch(&mut self, x: Arg) -> Result<String, HglibError> {
+        x.run(self)
+    }
+}
@@ -3,6 +3,7 @@
 // You can obtain one at http://mozilla.org/MPL/2.0/.
 
 use crate::client::{Client, HglibError, Runner};
+use crate::json;
 use crate::{runcommand, MkArg};
 
 pub struct Arg {
@@ -20,7 +21,11 @@ impl Default for Arg {
 }
 
 impl Arg {
-    fn run(&self, client: &mut Client) -> Result<(Vec<u8>, i32), HglibError> {
+    fn runcommand<T: Runner>(
+        &self,
+        client: &mut T,
+        template: &str,
+    ) -> Result<(Vec<u8>, i32), HglibError> {
         runcommand!(
             client,
             "branches",
@@ -28,36 +33,67 @@ impl Arg {
             "-a",
             self.active,
             "-c",
-            self.closed
+            self.closed,
+            "-T",
+            template
         )
     }
-}
 
-#[derive(Debug, PartialEq)]
-pub struct Branch {
-    pub name: String,
-    pub rev: u64,
-    pub node: String,
-}
+    pub fn run<T: Runner>(&self, client: &mut T) -> Result<Vec<Branch>, HglibError> {
+        if client.use_json()? {
+            match self.run_json(client) {
+                Err(HglibError::Parse { .. }) => {}
+                res => return res,
+            }
+        }
+        self.run_text(client)
+    }
 
-impl Client {
-    pub fn branches(&mut self, x: Arg) -> Result<Vec<Branch>, HglibError> {
-        let (data, _) = x.run(self)?;
+    fn run_json<T: Runner>(&self, client: &mut T) -> Result<Vec<Branch>, HglibError> {
+        let (data, _) = self.runcommand(client, "json")?;
+        json::parse(&data)?
+            .iter()
+            .map(|obj| {
+                Ok(Branch {
+                    name: json::get_str(obj, "branch")?,
+                    rev: json::get_u64(obj, "rev")?,
+                    node: json::get_short_node(obj, "node")?,
+                })
+            })
+            .collect()
+    }
+
+    fn run_text<T: Runner>(&self, client: &mut T) -> Result<Vec<Branch>, HglibError> {
+        let encoding = client.server_encoding();
+        let (data, _) = self.runcommand(client, "")?;
         let mut branches = Vec::new();
         for line in data.split(|x| *x == b'\n').filter(|x| !x.is_empty()) {
             let mut iter = line.split(|x| *x == b' ').filter(|x| !x.is_empty());
             let name = iter.next().unwrap();
-            let name = String::from_utf8(name.to_vec())?;
+            let name = encoding.decode_string(name)?;
             let rev_node = iter.next().unwrap();
             let iter = &mut rev_node.iter();
             let rev = iter
                 .take_while(|x| **x != b':')
                 .fold(0, |r, x| r * 10 + u64::from(*x - b'0'));
             let node = iter.as_slice();
-            let node = String::from_utf8(node.to_vec())?;
+            let node = encoding.decode_string(node)?;
 
             branches.push(Branch { name, rev, node });
         }
         Ok(branches)
     }
 }
+
+#[derive(Debug, PartialEq)]
+pub struct Branch {
+    pub name: String,
+    pub rev: u64,
+    pub node: String,
+}
+
+impl Client {
+    pub fn branches(&mut self, x: Arg) -> Result<Vec<Branch>, HglibError> {
+        x.run(self)
+    }
+}
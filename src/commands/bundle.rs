@@ -2,6 +2,10 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this file,
 // You can obtain one at http://mozilla.org/MPL/2.0/.
 
+use std::fs::{self, File};
+use std::io::{self, Write};
+
+use super::common;
 use crate::client::{Client, HglibError, Runner};
 use crate::{runcommand, MkArg};
 
@@ -37,36 +41,75 @@ impl<'a> Default for Arg<'a> {
     }
 }
 
-impl<'a> Arg<'a> {
-    fn run(&self, client: &mut Client) -> Result<(Vec<u8>, i32), HglibError> {
+/// Run bundle with the given file
+macro_rules! bundle {
+    ( $client: expr, $arg: expr, $file: expr ) => {
         runcommand!(
-            client,
+            $client,
             "bundle",
-            &[self.file, self.destrepo],
+            &[$file, $arg.destrepo],
             "-f",
-            self.force,
+            $arg.force,
             "-r",
-            self.rev,
+            $arg.rev,
             "-b",
-            self.branch,
+            $arg.branch,
             "--base",
-            self.base,
+            $arg.base,
             "-a",
-            self.all,
+            $arg.all,
             "-t",
-            self.typ,
+            $arg.typ,
             "-e",
-            self.ssh,
+            $arg.ssh,
             "--remotecmd",
-            self.remotecmd,
+            $arg.remotecmd,
             "-insecure",
-            self.insecure
+            $arg.insecure
         )
+    };
+}
+
+impl<'a> Arg<'a> {
+    fn runcommand<T: Runner>(&self, client: &mut T) -> Result<(Vec<u8>, i32), HglibError> {
+        bundle!(client, self, self.file)
+    }
+
+    pub fn run<T: Runner>(&self, client: &mut T) -> Result<bool, HglibError> {
+        HglibError::handle_err(self.runcommand(client))
+    }
+
+    /// Write the bundle in the writer (false is returned if there are no changes)
+    ///
+    /// hg can't write a bundle on its output: it's written in a temporary file
+    /// which is copied in the writer and then removed. The file is ignored.
+    pub fn run_to<T: Runner, W: Write>(
+        &self,
+        client: &mut T,
+        writer: &mut W,
+    ) -> Result<bool, HglibError> {
+        let path = common::temp_path("bundle.hg");
+        let file = path
+            .to_str()
+            .ok_or_else(|| HglibError::invalid_arg("Invalid temporary directory"))?;
+        let res = HglibError::handle_err(bundle!(client, self, file)).and_then(|changes| {
+            if changes {
+                io::copy(&mut File::open(&path)?, writer)?;
+                writer.flush()?;
+            }
+            Ok(changes)
+        });
+        let _ = fs::remove_file(&path);
+        res
     }
 }
 
 impl Client {
     pub fn bundle(&mut self, x: Arg) -> Result<bool, HglibError> {
-        HglibError::handle_err(x.run(self))
+        x.run(self)
+    }
+
+    pub fn bundle_to<W: Write>(&mut self, x: Arg, writer: &mut W) -> Result<bool, HglibError> {
+        x.run_to(self, writer)
     }
 }
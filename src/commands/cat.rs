@@ -2,8 +2,10 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this file,
 // You can obtain one at http://mozilla.org/MPL/2.0/.
 
-use crate::client::{Client, HglibError, Runner};
-use crate::{runcommand, MkArg};
+use std::io::Write;
+
+use crate::client::{Client, HglibError, Reader, Runner};
+use crate::{runcommand, runcommand_stream, MkArg};
 
 pub struct Arg<'a> {
     pub files: &'a [&'a str],
@@ -27,31 +29,76 @@ impl<'a> Default for Arg<'a> {
     }
 }
 
-impl<'a> Arg<'a> {
-    fn run(&self, client: &mut Client) -> Result<(Vec<u8>, i32), HglibError> {
-        runcommand!(
-            client,
+/// Run cat with the given runcommand macro
+macro_rules! cat {
+    ( $runcommand: ident, $client: expr, $arg: expr ) => {
+        $runcommand!(
+            $client,
             "cat",
-            self.files,
+            $arg.files,
             "-r",
-            self.rev,
+            $arg.rev,
             "-o",
-            self.output,
+            $arg.output,
             "-I",
-            self.include,
+            $arg.include,
             "-X",
-            self.exclude
+            $arg.exclude
         )
-    }
+    };
 }
 
-impl Client {
-    pub fn cat(&mut self, x: Arg) -> Result<Option<Vec<u8>>, HglibError> {
-        let (data, _) = x.run(self)?;
-        Ok(if x.output.is_empty() {
+impl<'a> Arg<'a> {
+    fn runcommand<T: Runner>(&self, client: &mut T) -> Result<(Vec<u8>, i32), HglibError> {
+        cat!(runcommand, client, self)
+    }
+
+    fn check_output(&self) -> Result<(), HglibError> {
+        if self.output.is_empty() {
+            Ok(())
+        } else {
+            Err(HglibError::invalid_arg(
+                "Cannot stream the data written in the output files",
+            ))
+        }
+    }
+
+    pub fn run<T: Runner>(&self, client: &mut T) -> Result<Option<Vec<u8>>, HglibError> {
+        let (data, _) = self.runcommand(client)?;
+        Ok(if self.output.is_empty() {
             Some(data)
         } else {
             None
         })
     }
+
+    /// Write the data in the writer as they're sent by the server and get their size
+    pub fn run_to<T: Runner, W: Write>(
+        &self,
+        client: &mut T,
+        writer: &mut W,
+    ) -> Result<u64, HglibError> {
+        self.check_output()?;
+        cat!(runcommand_stream, client, self)?.copy_to(writer)
+    }
+
+    /// Get a reader on the data sent by the server
+    pub fn reader<'r, T: Runner>(&self, client: &'r mut T) -> Result<Reader<'r>, HglibError> {
+        self.check_output()?;
+        Ok(Reader::new(cat!(runcommand_stream, client, self)?))
+    }
+}
+
+impl Client {
+    pub fn cat(&mut self, x: Arg) -> Result<Option<Vec<u8>>, HglibError> {
+        x.run(self)
+    }
+
+    pub fn cat_to<W: Write>(&mut self, x: Arg, writer: &mut W) -> Result<u64, HglibError> {
+        x.run_to(self, writer)
+    }
+
+    pub fn cat_reader(&mut self, x: Arg) -> Result<Reader<'_>, HglibError> {
+        x.reader(self)
+    }
 }
@@ -38,7 +38,7 @@ impl<'a> Default for Arg<'a> {
 }
 
 impl<'a> Arg<'a> {
-    fn run(&self, client: &mut Client) -> Result<(Vec<u8>, i32), HglibError> {
+    fn runcommand<T: Runner>(&self, client: &mut T) -> Result<(Vec<u8>, i32), HglibError> {
         runcommand!(
             client,
             "commit",
@@ -69,20 +69,12 @@ impl<'a> Arg<'a> {
             self.secret
         )
     }
-}
 
-#[derive(Debug)]
-pub struct Commit {
-    pub rev: u64,
-    pub node: String,
-}
-
-impl Client {
-    pub fn commit(&mut self, x: Arg) -> Result<Commit, HglibError> {
-        let mut x = x;
-        let message = if x.amend && x.message.is_empty() && x.logfile.is_empty() {
+    pub fn run<T: Runner>(&self, client: &mut T) -> Result<Commit, HglibError> {
+        let encoding = client.server_encoding();
+        let message = if self.amend && self.message.is_empty() && self.logfile.is_empty() {
             runcommand!(
-                self,
+                client,
                 "log",
                 &[""],
                 "-r",
@@ -96,21 +88,27 @@ impl Client {
         } else {
             vec![0; 0]
         };
-        if !message.is_empty() {
-            x.message = std::str::from_utf8(&message)?;
-        }
+        let message = encoding.decode(&message)?;
+        let x = Arg {
+            message: if message.is_empty() {
+                self.message
+            } else {
+                &message
+            },
+            ..*self
+        };
 
         if x.message.is_empty() && x.logfile.is_empty() && !x.amend {
-            return Err(HglibError::from(
+            return Err(HglibError::invalid_arg(
                 "Must provide at least a message or a logfile",
             ));
         } else if !x.message.is_empty() && !x.logfile.is_empty() {
-            return Err(HglibError::from(
+            return Err(HglibError::invalid_arg(
                 "Cannot specify both a message and a logfile",
             ));
         }
 
-        let (data, _) = x.run(self)?;
+        let (data, _) = x.runcommand(client)?;
         let committed_changeset = b"committed changeset ";
         for line in data.split(|x| *x == b'\n') {
             if line.starts_with(committed_changeset) {
@@ -120,14 +118,25 @@ impl Client {
                     .take_while(|x| **x != b':')
                     .fold(0, |r, x| r * 10 + u64::from(*x - b'0'));
                 let node = iter.as_slice();
-                let node = String::from_utf8(node.to_vec())?;
+                let node = encoding.decode_string(node)?;
                 return Ok(Commit { rev, node });
             }
         }
-        let s = std::str::from_utf8(&data).unwrap();
-        Err(HglibError::from(format!(
-            "Revision and node not found in hg output: {}",
-            s
-        )))
+        Err(HglibError::parse(
+            "Revision and node not found in hg output",
+            &data,
+        ))
+    }
+}
+
+#[derive(Debug)]
+pub struct Commit {
+    pub rev: u64,
+    pub node: String,
+}
+
+impl Client {
+    pub fn commit(&mut self, x: Arg) -> Result<Commit, HglibError> {
+        x.run(self)
     }
 }
@@ -3,58 +3,258 @@
 // You can obtain one at http://mozilla.org/MPL/2.0/.
 
 use chrono::prelude::*;
-use chrono::NaiveDateTime;
+use std::env;
+use std::path::PathBuf;
+use std::process;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicUsize, Ordering};
 
-use crate::client::HglibError;
+use crate::client::{HglibError, Prompt, Runner};
+use crate::encoding::Encoding;
+use crate::path::{HgPath, HgText};
+use crate::{runcommand, MkArg};
+
+#[derive(Debug, PartialEq)]
+pub struct Parent {
+    pub rev: u64,
+    pub node: String,
+}
 
 #[derive(Debug, PartialEq)]
 pub struct Revision {
     pub rev: u64,
     pub node: String,
-    pub tags: String,
-    pub branch: String,
-    pub author: String,
-    pub desc: String,
-    pub date: DateTime<Utc>,
+    /// the parents (the null revision isn't included)
+    pub parents: Vec<Parent>,
+    /// public, draft or secret
+    pub phase: String,
+    pub tags: Vec<HgText>,
+    pub bookmarks: Vec<HgText>,
+    pub branch: HgText,
+    pub author: HgText,
+    pub desc: HgText,
+    /// the date with the timezone of the committer
+    pub date: DateTime<FixedOffset>,
+    pub obsolete: bool,
+    /// e.g. orphan, phase-divergent or content-divergent
+    pub instabilities: Vec<String>,
+    /// the files touched by the changeset
+    pub files: Vec<HgPath>,
+    /// the added, modified and removed files: only filled with the `filestatus` option
+    pub added: Vec<HgPath>,
+    pub modified: Vec<HgPath>,
+    pub removed: Vec<HgPath>,
+    /// the copied files (destination, source): only filled with `log --copies`
+    pub copies: Vec<(HgPath, HgPath)>,
 }
 
-pub const CHANGESETS_TEMPLATE: &str =
-    "{rev}\\0{node}\\0{tags}\\0{branch}\\0{author}\\0{desc}\\0{date}\\0";
+/// Make a changesets template: the file status fields are given by the caller
+macro_rules! changesets_template {
+    ( $files: expr ) => {
+        concat!(
+            "{rev}\\0{node}\\0{p1rev} {p1node} {p2rev} {p2node}\\0{phase}\\0",
+            "{join(tags, '\\n')}\\0{join(bookmarks, '\\n')}\\0{branch}\\0{author}\\0{desc}\\0",
+            "{date|hgdate}\\0{obsolete}\\0{join(instabilities, '\\n')}\\0{join(files, '\\n')}\\0",
+            $files,
+            "{file_copies_switch % '{name}\\n{source}\\n'}\\0"
+        )
+    };
+}
 
-pub fn parserevs(data: Vec<u8>) -> Result<Vec<Revision>, HglibError> {
-    let mut count = 0;
-    let mut parts: Vec<&[u8]> = vec![&[]; 6];
-    let mut res = Vec::new();
-    let mut rev: u64 = 0;
-
-    for buf in data.split(|x| *x == b'\0') {
-        if count == 0 {
-            rev = buf.iter().fold(0, |r, x| r * 10 + u64::from(*x - b'0'));
-            count += 1;
-        } else if count == 6 {
-            count = 0;
-            let timestamp = buf
-                .iter()
-                .take_while(|x| **x != b'.')
-                .fold(0, |r, x| r * 10 + i64::from(*x - b'0'));
-            res.push(Revision {
-                rev,
-                node: String::from_utf8(parts[1].to_vec())?,
-                tags: String::from_utf8(parts[2].to_vec())?,
-                branch: String::from_utf8(parts[3].to_vec())?,
-                author: String::from_utf8(parts[4].to_vec())?,
-                desc: String::from_utf8(parts[5].to_vec())?,
-                date: DateTime::from_utc(NaiveDateTime::from_timestamp(timestamp, 0), Utc),
+/// The template used to get the changesets: the fields are separated by a \0
+/// and the lists by a \n (which can't be in a filename, a tag or a bookmark).
+///
+/// The added, modified and removed files are left empty: use CHANGESETS_FILES_TEMPLATE
+/// to get them.
+pub const CHANGESETS_TEMPLATE: &str = changesets_template!("\\0\\0\\0");
+
+/// The same as CHANGESETS_TEMPLATE with the added, modified and removed files
+/// (hg has to compare the manifests of each changeset with its parent's)
+pub const CHANGESETS_FILES_TEMPLATE: &str = changesets_template!(
+    "{join(file_adds, '\\n')}\\0{join(file_mods, '\\n')}\\0{join(file_dels, '\\n')}\\0"
+);
+
+/// Get the template used to get the changesets with or without the file status
+pub(crate) fn changesets_template(filestatus: bool) -> &'static str {
+    if filestatus {
+        CHANGESETS_FILES_TEMPLATE
+    } else {
+        CHANGESETS_TEMPLATE
+    }
+}
+
+pub(crate) const CHANGESETS_FIELDS: usize = 17;
+
+pub(crate) fn parse_number<T: FromStr>(data: &[u8]) -> Result<T, HglibError> {
+    std::str::from_utf8(data)
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .ok_or_else(|| HglibError::parse("Invalid number", data))
+}
+
+pub(crate) fn parse_list(data: &[u8], encoding: &Encoding) -> Vec<String> {
+    data.split(|x| *x == b'\n')
+        .filter(|x| !x.is_empty())
+        .map(|x| encoding.decode_lossy(x).into_owned())
+        .collect()
+}
+
+fn parse_paths(data: &[u8]) -> Vec<HgPath> {
+    data.split(|x| *x == b'\n')
+        .filter(|x| !x.is_empty())
+        .map(HgPath::from)
+        .collect()
+}
+
+pub(crate) fn parse_texts(data: &[u8]) -> Vec<HgText> {
+    data.split(|x| *x == b'\n')
+        .filter(|x| !x.is_empty())
+        .map(HgText::from)
+        .collect()
+}
+
+fn parse_parents(data: &[u8]) -> Result<Vec<Parent>, HglibError> {
+    let parts: Vec<&[u8]> = data.split(|x| *x == b' ').collect();
+    if parts.len() != 4 {
+        return Err(HglibError::parse("Invalid parents", data));
+    }
+    let mut parents = Vec::new();
+    for p in parts.chunks(2) {
+        // The null revision is -1
+        let rev: i64 = parse_number(p[0])?;
+        if rev >= 0 {
+            parents.push(Parent {
+                rev: rev as u64,
+                node: String::from_utf8(p[1].to_vec())?,
             });
-            rev = 0;
-        } else {
-            parts[count] = &buf;
-            count += 1;
         }
     }
+    Ok(parents)
+}
+
+/// Parse a date formatted with the hgdate filter: "timestamp offset"
+/// (the offset is the number of seconds west of UTC)
+pub fn parse_hgdate(data: &[u8]) -> Result<DateTime<FixedOffset>, HglibError> {
+    let err = || HglibError::parse("Invalid date", data);
+    let mut iter = data.split(|x| *x == b' ');
+    let timestamp: i64 = parse_number(iter.next().ok_or_else(err)?)?;
+    let offset: i32 = parse_number(iter.next().ok_or_else(err)?)?;
+    FixedOffset::west_opt(offset)
+        .and_then(|tz| tz.timestamp_opt(timestamp, 0).single())
+        .ok_or_else(err)
+}
+
+pub fn parserevs(data: Vec<u8>, encoding: &Encoding) -> Result<Vec<Revision>, HglibError> {
+    let fields: Vec<&[u8]> = data.split(|x| *x == b'\0').collect();
+    let mut res = Vec::new();
+
+    for parts in fields.chunks(CHANGESETS_FIELDS) {
+        if parts.len() != CHANGESETS_FIELDS {
+            if parts.iter().all(|p| p.is_empty()) {
+                break;
+            }
+            return Err(HglibError::parse("Truncated changeset", &parts.concat()));
+        }
+        res.push(parserev(parts, encoding)?);
+    }
     Ok(res)
 }
 
+/// Parse the fields of one changeset written with CHANGESETS_TEMPLATE
+pub fn parserev(parts: &[&[u8]], encoding: &Encoding) -> Result<Revision, HglibError> {
+    let mut copies = Vec::new();
+    let mut lines = parts[16].split(|x| *x == b'\n');
+    while let (Some(name), Some(source)) = (lines.next(), lines.next()) {
+        copies.push((HgPath::from(name), HgPath::from(source)));
+    }
+    // The text fields are kept as bytes: the history may contain some data
+    // which aren't correctly encoded
+    Ok(Revision {
+        rev: parse_number(parts[0])?,
+        node: String::from_utf8(parts[1].to_vec())?,
+        parents: parse_parents(parts[2])?,
+        phase: encoding.decode_lossy(parts[3]).into_owned(),
+        tags: parse_texts(parts[4]),
+        bookmarks: parse_texts(parts[5]),
+        branch: HgText::from(parts[6]),
+        author: HgText::from(parts[7]),
+        desc: HgText::from(parts[8]),
+        date: parse_hgdate(parts[9])?,
+        obsolete: !parts[10].is_empty(),
+        instabilities: parse_list(parts[11], encoding),
+        files: parse_paths(parts[12]),
+        added: parse_paths(parts[13]),
+        modified: parse_paths(parts[14]),
+        removed: parse_paths(parts[15]),
+        copies,
+    })
+}
+
+/// Get the number of the tip revision (-1 for an empty repository)
+pub(crate) fn tiprev<T: Runner>(client: &mut T) -> Result<i64, HglibError> {
+    let (data, _) = runcommand!(client, "log", &[""], "-r", "tip", "--template", "{rev}")?;
+    parse_number(&data)
+}
+
+/// Check if the data contain the given text (e.g. "unresolved conflicts" in the error output)
+pub(crate) fn contains(data: &[u8], text: &[u8]) -> bool {
+    text.is_empty() || data.windows(text.len()).any(|w| w == text)
+}
+
+/// Get the data between the first occurrence of `before` and the next `after`
+pub(crate) fn find_between<'a>(data: &'a [u8], before: &[u8], after: &[u8]) -> Option<&'a [u8]> {
+    let start = data.windows(before.len()).position(|w| w == before)? + before.len();
+    let end = data[start..]
+        .windows(after.len())
+        .position(|w| w == after)?;
+    Some(&data[start..start + end])
+}
+
+/// Send some data on the input of the command (e.g. a patch read by `import -`)
+pub(crate) struct InputPrompt<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> InputPrompt<'a> {
+    pub(crate) fn new(data: &'a [u8]) -> Self {
+        Self { data, pos: 0 }
+    }
+}
+
+impl<'a> Prompt for InputPrompt<'a> {
+    fn call(&mut self, size: usize) -> &[u8] {
+        if self.pos < self.data.len() {
+            let end = self.data.len().min(self.pos + size);
+            let buf = &self.data[self.pos..end];
+            self.pos = end;
+
+            buf
+        } else {
+            &[]
+        }
+    }
+
+    fn input(&mut self) -> Option<Vec<u8>> {
+        let data = self.data[self.pos..].to_vec();
+        self.pos = self.data.len();
+        Some(data)
+    }
+}
+
+/// Used to get a unique name for the temporary files
+static COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+/// Get a unique path in the temporary directory: e.g. hglib-12345-0-bundle.hg
+pub(crate) fn temp_path(name: &str) -> PathBuf {
+    env::temp_dir().join(format!(
+        "hglib-{}-{}-{}",
+        process::id(),
+        COUNTER.fetch_add(1, Ordering::Relaxed),
+        name
+    ))
+}
+
 pub fn eatlines(buf: &[u8], n: u32) -> &[u8] {
     let mut iter = buf.iter();
     let mut count = 0;
@@ -74,9 +274,9 @@ pub fn handle_err(x: Result<(Vec<u8>, i32), HglibError>) -> Result<bool, HglibEr
     match x {
         Ok((_, code)) => Ok(code == 0),
         Err(err) => {
-            if err.code == 0 {
+            if err.code() == 0 {
                 Ok(true)
-            } else if err.code == 1 {
+            } else if err.code() == 1 {
                 Ok(false)
             } else {
                 Err(err)
@@ -108,4 +308,12 @@ language";
         let r = eatlines(s, 4);
         assert!(&r[..2] == b"pr");
     }
+
+    #[test]
+    fn test_contains() {
+        let msg = b"abort: unresolved conflicts (see 'hg resolve')\n";
+        assert!(contains(msg, b"unresolved conflicts"));
+        assert!(!contains(msg, b"nothing to rebase"));
+        assert!(!contains(b"abort", b"abort: "));
+    }
 }
@@ -3,6 +3,7 @@
 // You can obtain one at http://mozilla.org/MPL/2.0/.
 
 use crate::client::{Client, HglibError, Runner};
+use crate::encoding::Encoding;
 use crate::{runcommand, MkArg};
 
 pub struct Arg<'a> {
@@ -22,7 +23,7 @@ impl<'a> Default for Arg<'a> {
 }
 
 impl<'a> Arg<'a> {
-    fn run(&self, client: &mut Client) -> Result<(Vec<u8>, i32), HglibError> {
+    fn runcommand<T: Runner>(&self, client: &mut T) -> Result<(Vec<u8>, i32), HglibError> {
         runcommand!(
             client,
             "showconfig",
@@ -33,6 +34,36 @@ impl<'a> Arg<'a> {
             self.showsource
         )
     }
+
+    pub fn run<T: Runner>(&self, client: &mut T) -> Result<Vec<Config>, HglibError> {
+        let encoding = client.server_encoding();
+        let (data, _) = self.runcommand(client)?;
+        let mut conf = Vec::new();
+        if self.showsource {
+            let mut iter = data.split(|x| *x == b'\n').filter(|x| !x.is_empty());
+            let mut line = iter.next();
+            while let Some(l) = line {
+                if !l.starts_with(b"read config from: ") && !l.starts_with(b"set config by: ") {
+                    break;
+                }
+                line = iter.next();
+            }
+            while let Some(l) = line {
+                if let Some((source, kv)) = get_skv(l) {
+                    let source = encoding.decode(source)?;
+                    conf.push(Config::with_source(&source, kv, &encoding)?);
+                } else {
+                    return Err(HglibError::parse("Invalid line in config", l));
+                }
+                line = iter.next();
+            }
+        } else {
+            for line in data.split(|x| *x == b'\n').filter(|x| !x.is_empty()) {
+                conf.push(Config::no_source(line, &encoding)?);
+            }
+        }
+        Ok(conf)
+    }
 }
 
 #[derive(Debug, PartialEq)]
@@ -44,8 +75,9 @@ pub struct Config {
 }
 
 impl Config {
-    fn no_source(line: &[u8]) -> Result<Self, HglibError> {
-        let (section, key, value) = splitline(line)?;
+    fn no_source(line: &[u8], encoding: &Encoding) -> Result<Self, HglibError> {
+        let line = encoding.decode(line)?;
+        let (section, key, value) = splitline(&line)?;
         Ok(Self {
             source: None,
             section: section.to_string(),
@@ -54,8 +86,9 @@ impl Config {
         })
     }
 
-    fn with_source(source: &str, line: &[u8]) -> Result<Self, HglibError> {
-        let (section, key, value) = splitline(line)?;
+    fn with_source(source: &str, line: &[u8], encoding: &Encoding) -> Result<Self, HglibError> {
+        let line = encoding.decode(line)?;
+        let (section, key, value) = splitline(&line)?;
         Ok(Self {
             source: Some(source.to_string()),
             section: section.to_string(),
@@ -65,9 +98,8 @@ impl Config {
     }
 }
 
-fn splitline(data: &[u8]) -> Result<(&str, &str, &str), HglibError> {
-    let data = std::str::from_utf8(data)?;
-    let err = || HglibError::from(format!("Invalid line in config: {}", data));
+fn splitline(data: &str) -> Result<(&str, &str, &str), HglibError> {
+    let err = || HglibError::parse("Invalid line in config", data.as_bytes());
     let data = data.trim_end();
     let mut iter = data.rsplitn(2, '=');
     let value = iter.next().ok_or_else(err)?;
@@ -91,34 +123,6 @@ fn get_skv(line: &[u8]) -> Option<(&[u8], &[u8])> {
 
 impl Client {
     pub fn config(&mut self, x: Arg) -> Result<Vec<Config>, HglibError> {
-        let (data, _) = x.run(self)?;
-        let mut conf = Vec::new();
-        if x.showsource {
-            let mut iter = data.split(|x| *x == b'\n').filter(|x| !x.is_empty());
-            let mut line = iter.next();
-            while let Some(l) = line {
-                if !l.starts_with(b"read config from: ") && !l.starts_with(b"set config by: ") {
-                    break;
-                }
-                line = iter.next();
-            }
-            while let Some(l) = line {
-                if let Some((source, kv)) = get_skv(l) {
-                    let source = std::str::from_utf8(source)?;
-                    conf.push(Config::with_source(source, kv)?);
-                } else {
-                    return Err(HglibError::from(format!(
-                        "invalid line in config: {}",
-                        std::str::from_utf8(l)?
-                    )));
-                }
-                line = iter.next();
-            }
-        } else {
-            for line in data.split(|x| *x == b'\n').filter(|x| !x.is_empty()) {
-                conf.push(Config::no_source(line)?);
-            }
-        }
-        Ok(conf)
+        x.run(self)
     }
 }
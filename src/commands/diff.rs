@@ -2,8 +2,12 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this file,
 // You can obtain one at http://mozilla.org/MPL/2.0/.
 
+use std::io::Write;
+
 use crate::client::{Client, HglibError, Runner};
-use crate::{runcommand, MkArg};
+use crate::patch::{self, FileDiff, LineKind};
+use crate::path::HgPath;
+use crate::{runcommand, runcommand_stream, MkArg};
 
 pub struct Arg<'a> {
     pub files: &'a [&'a str],
@@ -17,9 +21,13 @@ pub struct Arg<'a> {
     pub ignoreallspace: bool,
     pub ignorespacechange: bool,
     pub ignoreblanklines: bool,
+    pub ignorespaceateol: bool,
     pub unified: Option<u32>,
     pub stat: bool,
     pub subrepos: bool,
+    pub root: &'a str,
+    pub from: &'a str,
+    pub to: &'a str,
     pub include: &'a [&'a str],
     pub exclude: &'a [&'a str],
 }
@@ -38,61 +46,183 @@ impl<'a> Default for Arg<'a> {
             ignoreallspace: false,
             ignorespacechange: false,
             ignoreblanklines: false,
+            ignorespaceateol: false,
             unified: None,
             stat: false,
             subrepos: false,
+            root: "",
+            from: "",
+            to: "",
             include: &[],
             exclude: &[],
         }
     }
 }
 
-impl<'a> Arg<'a> {
-    fn run(&self, client: &mut Client) -> Result<(Vec<u8>, i32), HglibError> {
-        runcommand!(
-            client,
+/// Run diff with the given runcommand macro
+macro_rules! diff {
+    ( $runcommand: ident, $client: expr, $arg: expr ) => {
+        $runcommand!(
+            $client,
             "diff",
-            self.files,
+            $arg.files,
             "-r",
-            self.revs,
+            $arg.revs,
             "-c",
-            self.change,
+            $arg.change,
             "-a",
-            self.text,
+            $arg.text,
             "-g",
-            self.git,
+            $arg.git,
             "--nodates",
-            self.nodates,
+            $arg.nodates,
             "-p",
-            self.showfunction,
+            $arg.showfunction,
             "--reverse",
-            self.reverse,
+            $arg.reverse,
             "-w",
-            self.ignoreallspace,
+            $arg.ignoreallspace,
             "-b",
-            self.ignorespacechange,
+            $arg.ignorespacechange,
             "-B",
-            self.ignoreblanklines,
+            $arg.ignoreblanklines,
+            "-Z",
+            $arg.ignorespaceateol,
             "-U",
-            self.unified,
+            $arg.unified,
             "--stat",
-            self.stat,
+            $arg.stat,
             "-S",
-            self.subrepos,
+            $arg.subrepos,
+            "--root",
+            $arg.root,
+            "--from",
+            $arg.from,
+            "--to",
+            $arg.to,
             "-I",
-            self.include,
+            $arg.include,
             "-X",
-            self.exclude
+            $arg.exclude
         )
+    };
+}
+
+impl<'a> Arg<'a> {
+    fn runcommand<T: Runner>(&self, client: &mut T) -> Result<(Vec<u8>, i32), HglibError> {
+        diff!(runcommand, client, self)
+    }
+
+    fn check(&self) -> Result<(), HglibError> {
+        if !self.change.is_empty() && !self.revs.is_empty() {
+            return Err(HglibError::invalid_arg(
+                "Cannot specify both change and rev",
+            ));
+        }
+        if (!self.from.is_empty() || !self.to.is_empty())
+            && (!self.change.is_empty() || !self.revs.is_empty())
+        {
+            return Err(HglibError::invalid_arg(
+                "Cannot specify from or to with change or rev",
+            ));
+        }
+        Ok(())
+    }
+
+    pub fn run<T: Runner>(&self, client: &mut T) -> Result<Vec<u8>, HglibError> {
+        self.check()?;
+        let (data, _) = self.runcommand(client)?;
+        Ok(data)
+    }
+
+    /// Get the parsed diff of each file
+    pub fn run_parsed<T: Runner>(&self, client: &mut T) -> Result<Vec<FileDiff>, HglibError> {
+        if self.stat {
+            return Err(HglibError::invalid_arg("Cannot parse a diffstat as a diff"));
+        }
+        let encoding = client.server_encoding();
+        let data = self.run(client)?;
+        patch::parse_diff(&data, &encoding)
+    }
+
+    /// Get the number of inserted and deleted lines for each file
+    ///
+    /// The counts are computed from the diff: the output of `--stat` can't be used
+    /// because its graph is scaled to the terminal width.
+    pub fn run_stat<T: Runner>(&self, client: &mut T) -> Result<DiffStat, HglibError> {
+        let arg = Arg {
+            stat: false,
+            ..*self
+        };
+        Ok(DiffStat::new(&arg.run_parsed(client)?))
+    }
+
+    /// Write the diff in the writer as it's sent by the server and get its size
+    pub fn run_to<T: Runner, W: Write>(
+        &self,
+        client: &mut T,
+        writer: &mut W,
+    ) -> Result<u64, HglibError> {
+        self.check()?;
+        diff!(runcommand_stream, client, self)?.copy_to(writer)
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct FileStat {
+    pub path: HgPath,
+    pub insertions: u64,
+    pub deletions: u64,
+    pub binary: bool,
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub struct DiffStat {
+    pub files: Vec<FileStat>,
+    pub insertions: u64,
+    pub deletions: u64,
+}
+
+impl DiffStat {
+    pub fn new(files: &[FileDiff]) -> DiffStat {
+        let mut stat = DiffStat::default();
+        for file in files {
+            let count = |kind| {
+                file.hunks
+                    .iter()
+                    .flat_map(|hunk| hunk.lines.iter())
+                    .filter(|line| line.kind == kind)
+                    .count() as u64
+            };
+            let insertions = count(LineKind::Added);
+            let deletions = count(LineKind::Removed);
+            stat.insertions += insertions;
+            stat.deletions += deletions;
+            stat.files.push(FileStat {
+                path: file.path().clone(),
+                insertions,
+                deletions,
+                binary: file.binary,
+            });
+        }
+        stat
     }
 }
 
 impl Client {
     pub fn diff(&mut self, x: Arg) -> Result<Vec<u8>, HglibError> {
-        if !x.change.is_empty() && !x.revs.is_empty() {
-            return Err(HglibError::from("Cannot specify both change and rev"));
-        }
-        let (data, _) = x.run(self)?;
-        Ok(data)
+        x.run(self)
+    }
+
+    pub fn diff_parsed(&mut self, x: Arg) -> Result<Vec<FileDiff>, HglibError> {
+        x.run_parsed(self)
+    }
+
+    pub fn diff_stat(&mut self, x: Arg) -> Result<DiffStat, HglibError> {
+        x.run_stat(self)
+    }
+
+    pub fn diff_to<W: Write>(&mut self, x: Arg, writer: &mut W) -> Result<u64, HglibError> {
+        x.run_to(self, writer)
     }
 }
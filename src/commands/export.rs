@@ -2,8 +2,11 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this file,
 // You can obtain one at http://mozilla.org/MPL/2.0/.
 
+use std::io::Write;
+
 use crate::client::{Client, HglibError, Runner};
-use crate::{runcommand, MkArg};
+use crate::patch::{self, Patch};
+use crate::{runcommand, runcommand_stream, MkArg};
 
 pub struct Arg<'a> {
     pub revs: &'a [&'a str],
@@ -27,33 +30,80 @@ impl<'a> Default for Arg<'a> {
     }
 }
 
-impl<'a> Arg<'a> {
-    fn run(&self, client: &mut Client) -> Result<(Vec<u8>, i32), HglibError> {
-        runcommand!(
-            client,
+/// Run export with the given runcommand macro
+macro_rules! export {
+    ( $runcommand: ident, $client: expr, $arg: expr ) => {
+        $runcommand!(
+            $client,
             "export",
-            self.revs,
+            $arg.revs,
             "-o",
-            self.output,
+            $arg.output,
             "--switch-parent",
-            self.switchparent,
+            $arg.switchparent,
             "-a",
-            self.text,
+            $arg.text,
             "-g",
-            self.git,
+            $arg.git,
             "--nodates",
-            self.nodates
+            $arg.nodates
         )
-    }
+    };
 }
 
-impl Client {
-    pub fn export(&mut self, x: Arg) -> Result<Option<Vec<u8>>, HglibError> {
-        Ok(if x.output.is_empty() {
-            let (data, _) = x.run(self)?;
+impl<'a> Arg<'a> {
+    fn runcommand<T: Runner>(&self, client: &mut T) -> Result<(Vec<u8>, i32), HglibError> {
+        export!(runcommand, client, self)
+    }
+
+    pub fn run<T: Runner>(&self, client: &mut T) -> Result<Option<Vec<u8>>, HglibError> {
+        Ok(if self.output.is_empty() {
+            let (data, _) = self.runcommand(client)?;
             Some(data)
         } else {
             None
         })
     }
+
+    fn check_output(&self) -> Result<(), HglibError> {
+        if self.output.is_empty() {
+            Ok(())
+        } else {
+            Err(HglibError::invalid_arg(
+                "Cannot get the patches written in the output files",
+            ))
+        }
+    }
+
+    /// Get the parsed patches
+    pub fn run_parsed<T: Runner>(&self, client: &mut T) -> Result<Vec<Patch>, HglibError> {
+        self.check_output()?;
+        let encoding = client.server_encoding();
+        let (data, _) = self.runcommand(client)?;
+        patch::parse_export(&data, &encoding)
+    }
+
+    /// Write the patches in the writer as they're sent by the server and get their size
+    pub fn run_to<T: Runner, W: Write>(
+        &self,
+        client: &mut T,
+        writer: &mut W,
+    ) -> Result<u64, HglibError> {
+        self.check_output()?;
+        export!(runcommand_stream, client, self)?.copy_to(writer)
+    }
+}
+
+impl Client {
+    pub fn export(&mut self, x: Arg) -> Result<Option<Vec<u8>>, HglibError> {
+        x.run(self)
+    }
+
+    pub fn export_parsed(&mut self, x: Arg) -> Result<Vec<Patch>, HglibError> {
+        x.run_parsed(self)
+    }
+
+    pub fn export_to<W: Write>(&mut self, x: Arg, writer: &mut W) -> Result<u64, HglibError> {
+        x.run_to(self, writer)
+    }
 }
@@ -22,7 +22,7 @@ impl<'a> Default for Arg<'a> {
 }
 
 impl<'a> Arg<'a> {
-    fn run(&self, client: &mut Client) -> Result<(Vec<u8>, i32), HglibError> {
+    fn runcommand<T: Runner>(&self, client: &mut T) -> Result<(Vec<u8>, i32), HglibError> {
         runcommand!(
             client,
             "forget",
@@ -33,10 +33,14 @@ impl<'a> Arg<'a> {
             self.exclude
         )
     }
+
+    pub fn run<T: Runner>(&self, client: &mut T) -> Result<bool, HglibError> {
+        HglibError::handle_err(self.runcommand(client))
+    }
 }
 
 impl Client {
     pub fn forget(&mut self, x: Arg) -> Result<bool, HglibError> {
-        HglibError::handle_err(x.run(self))
+        x.run(self)
     }
 }
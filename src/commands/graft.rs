@@ -0,0 +1,192 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this file,
+// You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use super::common::{self, Revision, CHANGESETS_FIELDS};
+use super::resolve;
+use crate::client::{Client, HglibError, Runner};
+use crate::path::HgPath;
+use crate::{runcommand, MkArg};
+
+pub struct Arg<'a> {
+    pub revs: &'a [&'a str],
+    pub base: &'a str,
+    pub force: bool,
+    pub currentdate: bool,
+    pub currentuser: bool,
+    pub date: &'a str,
+    pub user: &'a str,
+    pub log: bool,
+    pub tool: &'a str,
+    pub dryrun: bool,
+    pub cont: bool,
+    pub stop: bool,
+    pub abort: bool,
+}
+
+impl<'a> Default for Arg<'a> {
+    fn default() -> Self {
+        Self {
+            revs: &[],
+            base: "",
+            force: false,
+            currentdate: false,
+            currentuser: false,
+            date: "",
+            user: "",
+            log: false,
+            tool: "",
+            dryrun: false,
+            cont: false,
+            stop: false,
+            abort: false,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Grafted {
+    /// the node of the grafted changeset
+    pub source: String,
+    /// the new changeset
+    pub revision: Revision,
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub struct Graft {
+    /// the created changesets (from the oldest to the newest)
+    pub grafted: Vec<Grafted>,
+    /// the revisions which haven't been grafted (e.g. already grafted or ancestors)
+    pub skipped: Vec<u64>,
+    /// true when the graft stopped on some conflicts: resolve them and use --continue
+    pub interrupted: bool,
+    pub conflicts: Vec<HgPath>,
+}
+
+/// Get the revision from a line like "skipping ancestor revision 2:5d205f8b35b6"
+fn parse_skipped(line: &[u8]) -> Option<u64> {
+    let marker: &[u8] = b"revision ";
+    if !line.starts_with(b"skipping ") {
+        return None;
+    }
+    let pos = line.windows(marker.len()).position(|w| w == marker)? + marker.len();
+    let end = line[pos..]
+        .iter()
+        .position(|c| !c.is_ascii_digit())
+        .map_or(line.len(), |p| pos + p);
+    common::parse_number(&line[pos..end]).ok()
+}
+
+impl<'a> Arg<'a> {
+    fn runcommand<T: Runner>(&self, client: &mut T) -> Result<(Vec<u8>, i32), HglibError> {
+        runcommand!(
+            client,
+            "graft",
+            self.revs,
+            "--base",
+            self.base,
+            "-f",
+            self.force,
+            "-D",
+            self.currentdate,
+            "-U",
+            self.currentuser,
+            "-d",
+            self.date,
+            "-u",
+            self.user,
+            "--log",
+            self.log,
+            "-t",
+            self.tool,
+            "-n",
+            self.dryrun,
+            "--continue",
+            self.cont,
+            "--stop",
+            self.stop,
+            "--abort",
+            self.abort
+        )
+    }
+
+    /// Get the changesets created after `tip` with the node they come from
+    fn grafted<T: Runner>(client: &mut T, tip: i64) -> Result<Vec<Grafted>, HglibError> {
+        let encoding = client.server_encoding();
+        let range = format!("{}:tip", tip + 1);
+        // A graft of a graft has the original node in source
+        let template = format!(
+            "{}{{get(extras, 'intermediate-source')}}\\0{{get(extras, 'source')}}\\0",
+            common::CHANGESETS_TEMPLATE
+        );
+        let (data, _) = runcommand!(
+            client,
+            "log",
+            &[""],
+            "-r",
+            range.as_str(),
+            "--template",
+            template.as_str()
+        )?;
+
+        let fields: Vec<&[u8]> = data.split(|x| *x == b'\0').collect();
+        let mut res = Vec::new();
+        for parts in fields.chunks(CHANGESETS_FIELDS + 2) {
+            if parts.len() != CHANGESETS_FIELDS + 2 {
+                if parts.iter().all(|p| p.is_empty()) {
+                    break;
+                }
+                return Err(HglibError::parse("Truncated changeset", &parts.concat()));
+            }
+            let source = if parts[CHANGESETS_FIELDS].is_empty() {
+                parts[CHANGESETS_FIELDS + 1]
+            } else {
+                parts[CHANGESETS_FIELDS]
+            };
+            res.push(Grafted {
+                source: String::from_utf8(source.to_vec())?,
+                revision: common::parserev(&parts[..CHANGESETS_FIELDS], &encoding)?,
+            });
+        }
+        Ok(res)
+    }
+
+    pub fn run<T: Runner>(&self, client: &mut T) -> Result<Graft, HglibError> {
+        let tip = common::tiprev(client)?;
+        let mut graft = Graft::default();
+        let err = match self.runcommand(client) {
+            Ok(_) => client.warnings().to_vec(),
+            Err(err) => {
+                let msg = err.err().unwrap_or_default().to_vec();
+                if common::contains(&msg, b"unresolved conflicts") {
+                    graft.interrupted = true;
+                } else if !matches!(err, HglibError::Command(_))
+                    || !msg
+                        .split(|x| *x == b'\n')
+                        .any(|l| parse_skipped(l).is_some())
+                {
+                    // When all the revisions are skipped, the command fails without aborting
+                    return Err(err);
+                }
+                msg
+            }
+        };
+        graft.skipped = err
+            .split(|x| *x == b'\n')
+            .filter_map(parse_skipped)
+            .collect();
+        if common::tiprev(client)? > tip {
+            graft.grafted = Arg::grafted(client, tip)?;
+        }
+        if graft.interrupted {
+            graft.conflicts = resolve::unresolved(client)?;
+        }
+        Ok(graft)
+    }
+}
+
+impl Client {
+    pub fn graft(&mut self, x: Arg) -> Result<Graft, HglibError> {
+        x.run(self)
+    }
+}
@@ -3,6 +3,7 @@
 // You can obtain one at http://mozilla.org/MPL/2.0/.
 
 use crate::client::{Client, HglibError, Runner};
+use crate::path::{HgPath, HgText};
 use crate::version;
 use crate::{runcommand, MkArg};
 
@@ -41,7 +42,7 @@ impl<'a> Default for Arg<'a> {
 }
 
 impl<'a> Arg<'a> {
-    fn run(&self, client: &mut Client) -> Result<(Vec<u8>, i32), HglibError> {
+    fn runcommand<T: Runner>(&self, client: &mut T) -> Result<(Vec<u8>, i32), HglibError> {
         let mut args = self.files.to_vec();
         args.insert(0, self.pattern);
         runcommand!(
@@ -72,66 +73,40 @@ impl<'a> Arg<'a> {
             true
         )
     }
-}
-
-#[derive(Debug, PartialEq)]
-#[cfg_attr(test, derive(Clone))]
-pub struct GrepRes {
-    pub filename: String,
-    pub rev: Option<u64>,
-    pub line: Option<u32>,
-    pub match_status: Option<String>,
-    pub user: Option<String>,
-    //TODO: convert string to datetime
-    // pub date: Option<DateTime<Utc>>,
-    pub date: Option<String>,
-    pub matched: Option<String>,
-}
 
-#[derive(Debug, PartialEq)]
-enum FieldType {
-    Filename,
-    Rev,
-    Line,
-    MatchStatus,
-    User,
-    Date,
-    Matched,
-}
-
-impl Client {
-    fn get_field_types(&mut self, x: &Arg) -> Result<Vec<FieldType>, HglibError> {
+    fn get_field_types<T: Runner>(&self, client: &mut T) -> Result<Vec<FieldType>, HglibError> {
         let mut field_types = vec![FieldType::Filename];
 
-        if x.all || self.version(version::Arg {})? < (5, 2, None) {
+        if self.all || (version::Arg {}).run(client)? < (5, 2, None) {
             field_types.push(FieldType::Rev);
         }
-        if x.line {
+        if self.line {
             field_types.push(FieldType::Line);
         }
-        if x.all {
+        if self.all {
             field_types.push(FieldType::MatchStatus);
         }
-        if x.user {
+        if self.user {
             field_types.push(FieldType::User);
         }
-        if x.date {
+        if self.date {
             field_types.push(FieldType::Date);
         }
-        if !x.fileswithmatches {
+        if !self.fileswithmatches {
             field_types.push(FieldType::Matched);
         }
 
         Ok(field_types)
     }
 
-    pub fn grep(&mut self, x: Arg) -> Result<Vec<GrepRes>, HglibError> {
+    pub fn run<T: Runner>(&self, client: &mut T) -> Result<Vec<GrepRes>, HglibError> {
+        let encoding = client.server_encoding();
         let mut res = Vec::new();
 
-        let data = match x.run(self) {
+        let data = match self.runcommand(client) {
             Ok(ret) => ret.0,
             Err(e) => {
-                if e.code == 1 {
+                if e.code() == 1 {
                     return Ok(res);
                 } else {
                     return Err(e);
@@ -139,11 +114,11 @@ impl Client {
             }
         };
 
-        let field_types = self.get_field_types(&x)?;
+        let field_types = self.get_field_types(client)?;
         for (n, element) in data.split(|x| *x == b'\0').enumerate() {
             match field_types[n % field_types.len()] {
                 FieldType::Filename => {
-                    let filename = String::from_utf8(element.to_vec())?;
+                    let filename = HgPath::from(element);
                     res.push(GrepRes {
                         filename,
                         rev: None,
@@ -164,17 +139,18 @@ impl Client {
                 }
                 FieldType::MatchStatus => {
                     res.last_mut().unwrap().match_status =
-                        Some(String::from_utf8(element.to_vec())?);
+                        Some(encoding.decode_lossy(element).into_owned());
                 }
                 FieldType::User => {
-                    res.last_mut().unwrap().user = Some(String::from_utf8(element.to_vec())?);
+                    res.last_mut().unwrap().user =
+                        Some(encoding.decode_lossy(element).into_owned());
                 }
                 FieldType::Date => {
-                    let sdate = std::str::from_utf8(element)?;
+                    let sdate = encoding.decode_lossy(element);
                     res.last_mut().unwrap().date = Some(sdate.to_string());
                 }
                 FieldType::Matched => {
-                    res.last_mut().unwrap().matched = Some(String::from_utf8(element.to_vec())?);
+                    res.last_mut().unwrap().matched = Some(HgText::from(element));
                 }
             }
         }
@@ -182,3 +158,35 @@ impl Client {
         Ok(res)
     }
 }
+
+#[derive(Debug, PartialEq)]
+#[cfg_attr(test, derive(Clone))]
+pub struct GrepRes {
+    pub filename: HgPath,
+    pub rev: Option<u64>,
+    pub line: Option<u32>,
+    pub match_status: Option<String>,
+    pub user: Option<String>,
+    //TODO: convert string to datetime
+    // pub date: Option<DateTime<Utc>>,
+    pub date: Option<String>,
+    /// the matched line (the file content is kept as is)
+    pub matched: Option<HgText>,
+}
+
+#[derive(Debug, PartialEq)]
+enum FieldType {
+    Filename,
+    Rev,
+    Line,
+    MatchStatus,
+    User,
+    Date,
+    Matched,
+}
+
+impl Client {
+    pub fn grep(&mut self, x: Arg) -> Result<Vec<GrepRes>, HglibError> {
+        x.run(self)
+    }
+}
@@ -11,6 +11,7 @@ pub struct Arg<'a> {
     pub startrev: &'a [&'a str],
     pub topological: bool,
     pub closed: bool,
+    pub filestatus: bool,
 }
 
 impl<'a> Default for Arg<'a> {
@@ -20,12 +21,13 @@ impl<'a> Default for Arg<'a> {
             startrev: &[],
             topological: false,
             closed: false,
+            filestatus: false,
         }
     }
 }
 
 impl<'a> Arg<'a> {
-    fn run(&self, client: &mut Client) -> Result<(Vec<u8>, i32), HglibError> {
+    fn runcommand<T: Runner>(&self, client: &mut T) -> Result<(Vec<u8>, i32), HglibError> {
         runcommand!(
             client,
             "heads",
@@ -37,17 +39,16 @@ impl<'a> Arg<'a> {
             "-c",
             self.closed,
             "--template",
-            common::CHANGESETS_TEMPLATE
+            common::changesets_template(self.filestatus)
         )
     }
-}
 
-impl Client {
-    pub fn heads(&mut self, x: Arg) -> Result<Vec<common::Revision>, HglibError> {
-        match x.run(self) {
-            Ok((data, _)) => common::parserevs(data),
+    pub fn run<T: Runner>(&self, client: &mut T) -> Result<Vec<common::Revision>, HglibError> {
+        let encoding = client.server_encoding();
+        match self.runcommand(client) {
+            Ok((data, _)) => common::parserevs(data, &encoding),
             Err(err) => {
-                if err.code == 1 {
+                if err.code() == 1 {
                     Ok(Vec::new())
                 } else {
                     Err(err)
@@ -56,3 +57,9 @@ impl Client {
         }
     }
 }
+
+impl Client {
+    pub fn heads(&mut self, x: Arg) -> Result<Vec<common::Revision>, HglibError> {
+        x.run(self)
+    }
+}
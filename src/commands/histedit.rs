@@ -0,0 +1,181 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this file,
+// You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use std::fmt;
+
+use super::common::{find_between, InputPrompt};
+use super::resolve;
+use crate::client::{Client, HglibError, Runner};
+use crate::path::HgPath;
+use crate::{runcommand, runcommand_with_prompt, MkArg};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Action {
+    Pick,
+    Edit,
+    Fold,
+    Roll,
+    Drop,
+    Mess,
+    Base,
+}
+
+impl Action {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Action::Pick => "pick",
+            Action::Edit => "edit",
+            Action::Fold => "fold",
+            Action::Roll => "roll",
+            Action::Drop => "drop",
+            Action::Mess => "mess",
+            Action::Base => "base",
+        }
+    }
+
+    fn from_bytes(action: &[u8]) -> Option<Action> {
+        Some(match action {
+            b"pick" => Action::Pick,
+            b"edit" => Action::Edit,
+            b"fold" => Action::Fold,
+            b"roll" => Action::Roll,
+            b"drop" => Action::Drop,
+            b"mess" => Action::Mess,
+            b"base" => Action::Base,
+            _ => return None,
+        })
+    }
+}
+
+/// A line of the plan: the action to apply on a changeset
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Rule<'a> {
+    pub action: Action,
+    pub node: &'a str,
+}
+
+impl<'a> fmt::Display for Rule<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {}", self.action.as_str(), self.node)
+    }
+}
+
+/// The editor is disabled: fold and mess keep the proposed message
+pub struct Arg<'a> {
+    /// the plan: if empty all the changesets are picked
+    pub plan: &'a [Rule<'a>],
+    pub rev: &'a str,
+    pub keep: bool,
+    pub force: bool,
+    pub editplan: bool,
+    pub cont: bool,
+    pub abort: bool,
+}
+
+impl<'a> Default for Arg<'a> {
+    fn default() -> Self {
+        Self {
+            plan: &[],
+            rev: "",
+            keep: false,
+            force: false,
+            editplan: false,
+            cont: false,
+            abort: false,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Histedit {
+    Done,
+    /// The edition stopped on an edit rule at the given changeset (short node):
+    /// amend it and use --continue
+    Edit(String),
+    /// The edition stopped on some conflicts when applying a rule: resolve the files
+    /// and use --continue
+    Conflicts {
+        action: Action,
+        node: String,
+        files: Vec<HgPath>,
+    },
+}
+
+macro_rules! histedit {
+    ($runcommand: ident, $client: expr, [$($head: expr),*], $arg: expr $(, $o: expr, $x: expr )*) => {
+        $runcommand!(
+            $client,
+            "histedit",
+            $($head),*,
+            "-k",
+            $arg.keep,
+            "-f",
+            $arg.force,
+            "--edit-plan",
+            $arg.editplan,
+            "-c",
+            $arg.cont,
+            "--abort",
+            $arg.abort,
+            "--config",
+            "extensions.histedit=",
+            "--config",
+            "ui.editor=true"
+            $(, $o, $x)*
+        )
+    };
+}
+
+impl<'a> Arg<'a> {
+    fn runcommand<T: Runner>(&self, client: &mut T) -> Result<(Vec<u8>, i32), HglibError> {
+        if self.plan.is_empty() {
+            histedit!(runcommand, client, [&[self.rev]], self)
+        } else {
+            // The rules are sent on the input of the command
+            let rules: String = self.plan.iter().map(|r| format!("{}\n", r)).collect();
+            let prompt = InputPrompt::new(rules.as_bytes());
+            histedit!(
+                runcommand_with_prompt,
+                client,
+                [prompt, &["--", self.rev]],
+                self,
+                "--commands",
+                "-"
+            )
+        }
+    }
+
+    pub fn run<T: Runner>(&self, client: &mut T) -> Result<Histedit, HglibError> {
+        let err = match self.runcommand(client) {
+            Ok(_) => return Ok(Histedit::Done),
+            Err(err) => err,
+        };
+        let msg = err.err().unwrap_or_default();
+        if let Some(node) = find_between(msg, b"Editing (", b")") {
+            Ok(Histedit::Edit(String::from_utf8(node.to_vec())?))
+        } else if let Some(rule) = find_between(msg, b"Fix up the change (", b")") {
+            let mut iter = rule.split(|c| *c == b' ');
+            let action = iter.next().and_then(Action::from_bytes);
+            let node = iter.next();
+            if let (Some(action), Some(node)) = (action, node) {
+                let node = String::from_utf8(node.to_vec())?;
+                Ok(Histedit::Conflicts {
+                    action,
+                    node,
+                    files: resolve::unresolved(client)?,
+                })
+            } else {
+                Err(err)
+            }
+        } else {
+            Err(err)
+        }
+    }
+}
+
+impl Client {
+    pub fn histedit(&mut self, x: Arg) -> Result<Histedit, HglibError> {
+        x.run(self)
+    }
+}
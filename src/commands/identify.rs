@@ -2,17 +2,24 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this file,
 // You can obtain one at http://mozilla.org/MPL/2.0/.
 
+use super::common;
 use crate::client::{Client, HglibError, Runner};
+use crate::path::HgText;
 use crate::{runcommand, MkArg};
 
+/// The template used for a local repository: the working directory has no rev
+/// and no node so its first parent is used instead
+const LOCAL_TEMPLATE: &str = "{rev}\\0{node}\\0{p1rev}\\0{p1node}\\0{dirty}\\0{branch}\\0\
+     {join(tags, '\\n')}\\0{join(bookmarks, '\\n')}";
+
+/// A remote repository only gives its node and its bookmarks
+const REMOTE_TEMPLATE: &str = "{id}\\0{join(bookmarks, '\\n')}";
+
+const WDIR_REV: &[u8] = b"2147483647";
+
 pub struct Arg<'a> {
     pub rev: &'a str,
     pub source: &'a str,
-    pub num: bool,
-    pub id: bool,
-    pub branch: bool,
-    pub tags: bool,
-    pub bookmarks: bool,
 }
 
 impl<'a> Default for Arg<'a> {
@@ -20,40 +27,92 @@ impl<'a> Default for Arg<'a> {
         Self {
             rev: "",
             source: "",
-            num: false,
-            id: false,
-            branch: false,
-            tags: false,
-            bookmarks: false,
         }
     }
 }
 
+#[derive(Debug, PartialEq)]
+pub struct Identify {
+    /// the full node
+    pub node: String,
+    /// the local revision number (-1 for the null revision): None for a remote source
+    pub rev: Option<i64>,
+    /// true when the working directory has uncommitted changes
+    pub dirty: bool,
+    /// empty for a remote source
+    pub branch: HgText,
+    /// empty for a remote source
+    pub tags: Vec<HgText>,
+    pub bookmarks: Vec<HgText>,
+}
+
+impl Identify {
+    /// The short form of the node (12 hex digits)
+    pub fn short(&self) -> &str {
+        &self.node[..self.node.len().min(12)]
+    }
+}
+
 impl<'a> Arg<'a> {
-    fn run(&self, client: &mut Client) -> Result<(Vec<u8>, i32), HglibError> {
+    fn runcommand<T: Runner>(&self, client: &mut T) -> Result<(Vec<u8>, i32), HglibError> {
+        let remote = !self.source.is_empty();
         runcommand!(
             client,
             "identify",
             &[self.source],
             "-r",
             self.rev,
-            "-n",
-            self.num,
-            "-i",
-            self.id,
-            "-b",
-            self.branch,
-            "-t",
-            self.tags,
             "-B",
-            self.bookmarks
+            remote,
+            "-T",
+            if remote {
+                REMOTE_TEMPLATE
+            } else {
+                LOCAL_TEMPLATE
+            }
         )
     }
+
+    pub fn run<T: Runner>(&self, client: &mut T) -> Result<Identify, HglibError> {
+        let (data, _) = self.runcommand(client)?;
+        let parts: Vec<&[u8]> = data.split(|x| *x == b'\0').collect();
+
+        if !self.source.is_empty() {
+            if parts.len() != 2 {
+                return Err(HglibError::parse("Invalid identify output", &data));
+            }
+            return Ok(Identify {
+                node: String::from_utf8(parts[0].to_vec())?,
+                rev: None,
+                dirty: false,
+                branch: HgText::default(),
+                tags: Vec::new(),
+                bookmarks: common::parse_texts(parts[1]),
+            });
+        }
+
+        if parts.len() != 8 {
+            return Err(HglibError::parse("Invalid identify output", &data));
+        }
+        // Old versions of Mercurial give an empty rev for the working directory
+        let (rev, node) = if parts[0].is_empty() || parts[0] == WDIR_REV {
+            (parts[2], parts[3])
+        } else {
+            (parts[0], parts[1])
+        };
+        Ok(Identify {
+            node: String::from_utf8(node.to_vec())?,
+            rev: Some(common::parse_number(rev)?),
+            dirty: parts[4] == b"+",
+            branch: HgText::from(parts[5]),
+            tags: common::parse_texts(parts[6]),
+            bookmarks: common::parse_texts(parts[7]),
+        })
+    }
 }
 
 impl Client {
-    pub fn identify(&mut self, x: Arg) -> Result<Vec<u8>, HglibError> {
-        let (data, _) = x.run(self)?;
-        Ok(data)
+    pub fn identify(&mut self, x: Arg) -> Result<Identify, HglibError> {
+        x.run(self)
     }
 }
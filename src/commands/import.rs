@@ -2,16 +2,23 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this file,
 // You can obtain one at http://mozilla.org/MPL/2.0/.
 
-use crate::client::{Client, HglibError, Prompt, Runner};
+use super::common::{self, InputPrompt, Revision};
+use super::log;
+use crate::client::{Client, HglibError, Runner};
+use crate::path::HgPath;
 use crate::{runcommand, runcommand_with_prompt, MkArg};
 
 pub struct Arg<'a> {
     pub patches: &'a [&'a str],
+    /// a patch given in memory: it's sent to the server on its input
+    pub input: &'a [u8],
     pub strip: Option<u32>,
     pub force: bool,
     pub nocommit: bool,
     pub bypass: bool,
+    pub partial: bool,
     pub exact: bool,
+    pub prefix: &'a str,
     pub importbranch: bool,
     pub message: &'a str,
     pub date: &'a str,
@@ -23,11 +30,14 @@ impl<'a> Default for Arg<'a> {
     fn default() -> Self {
         Self {
             patches: &[],
+            input: &[],
             strip: None,
             force: false,
             nocommit: false,
             bypass: false,
+            partial: false,
             exact: false,
+            prefix: "",
             importbranch: false,
             message: "",
             date: "",
@@ -37,97 +47,122 @@ impl<'a> Default for Arg<'a> {
     }
 }
 
-struct ImportPrompt<'a> {
-    patch: &'a [u8],
-    pos: usize,
+/// A file where some hunks failed to apply with `--partial`
+#[derive(Debug, PartialEq)]
+pub struct Reject {
+    /// the patched file (the rejected hunks are in the same path with a .rej extension)
+    pub path: HgPath,
+    pub failed: u32,
+    pub hunks: u32,
 }
 
-impl<'a> ImportPrompt<'a> {
-    fn new(patch: &'a str) -> Self {
-        Self {
-            patch: patch.as_bytes(),
-            pos: 0,
-        }
-    }
+#[derive(Debug, PartialEq)]
+pub struct Import {
+    /// the created changesets (from the oldest to the newest)
+    pub changesets: Vec<Revision>,
+    pub rejects: Vec<Reject>,
 }
 
-impl<'a> Prompt for ImportPrompt<'a> {
-    fn call(&mut self, size: usize) -> &[u8] {
-        if self.pos < self.patch.len() {
-            let end = self.patch.len().min(self.pos + size);
-            let buf = &self.patch[self.pos..end];
-            self.pos = end;
+macro_rules! import {
+    ($runcommand: ident, $client: expr, [$($head: expr),*], $arg: expr) => {
+        $runcommand!(
+            $client,
+            "import",
+            $($head),*,
+            "--strip",
+            $arg.strip,
+            "--force",
+            $arg.force,
+            "--no-commit",
+            $arg.nocommit,
+            "--bypass",
+            $arg.bypass,
+            "--partial",
+            $arg.partial,
+            "--exact",
+            $arg.exact,
+            "--prefix",
+            $arg.prefix,
+            "--import-branch",
+            $arg.importbranch,
+            "--message",
+            $arg.message,
+            "--date",
+            $arg.date,
+            "--user",
+            $arg.user,
+            "--similarity",
+            $arg.similarity
+        )
+    };
+}
 
-            buf
-        } else {
-            &[]
-        }
+/// Parse a line like "1 out of 2 hunks FAILED -- saving rejects to file a.rej"
+fn parse_reject(line: &[u8]) -> Option<Reject> {
+    let marker: &[u8] = b" hunks FAILED -- saving rejects to file ";
+    let pos = line.windows(marker.len()).position(|w| w == marker)?;
+    let mut counts = line[..pos].split(|x| *x == b' ');
+    let failed = common::parse_number(counts.next()?).ok()?;
+    if counts.next()? != b"out" || counts.next()? != b"of" {
+        return None;
     }
+    let hunks = common::parse_number(counts.next()?).ok()?;
+    let file = &line[pos + marker.len()..];
+    let path = file.strip_suffix(b".rej").unwrap_or(file);
+    Some(Reject {
+        path: HgPath::from(path),
+        failed,
+        hunks,
+    })
 }
 
 impl<'a> Arg<'a> {
-    fn run(&self, client: &mut Client) -> Result<(Vec<u8>, i32), HglibError> {
-        if self.patches.len() == 1 && self.patches[0].as_bytes().iter().any(|c| *c == b'\n') {
-            let prompt = ImportPrompt::new(self.patches[0]);
-            runcommand_with_prompt!(
-                client,
-                "import",
-                prompt,
-                &["-"],
-                "--strip",
-                self.strip,
-                "--force",
-                self.force,
-                "--no-commit",
-                self.nocommit,
-                "--bypass",
-                self.bypass,
-                "--exact",
-                self.exact,
-                "--import-branch",
-                self.importbranch,
-                "--message",
-                self.message,
-                "--date",
-                self.date,
-                "--user",
-                self.user,
-                "--similarity",
-                self.similarity
-            )
+    fn runcommand<T: Runner>(&self, client: &mut T) -> Result<(Vec<u8>, i32), HglibError> {
+        if !self.input.is_empty() {
+            let prompt = InputPrompt::new(self.input);
+            import!(runcommand_with_prompt, client, [prompt, &["-"]], self)
+        } else if self.patches.len() == 1 && self.patches[0].as_bytes().iter().any(|c| *c == b'\n')
+        {
+            let prompt = InputPrompt::new(self.patches[0].as_bytes());
+            import!(runcommand_with_prompt, client, [prompt, &["-"]], self)
         } else {
-            runcommand!(
-                client,
-                "import",
-                self.patches,
-                "--strip",
-                self.strip,
-                "--force",
-                self.force,
-                "--no-commit",
-                self.nocommit,
-                "--bypass",
-                self.bypass,
-                "--exact",
-                self.exact,
-                "--import-branch",
-                self.importbranch,
-                "--message",
-                self.message,
-                "--date",
-                self.date,
-                "--user",
-                self.user,
-                "--similarity",
-                self.similarity
-            )
+            import!(runcommand, client, [self.patches], self)
         }
     }
+
+    pub fn run<T: Runner>(&self, client: &mut T) -> Result<Import, HglibError> {
+        let tip = common::tiprev(client)?;
+        // With --partial, the command returns 1 when some hunks have been rejected
+        let err = match self.runcommand(client) {
+            Ok(_) => Vec::new(),
+            Err(e) if self.partial && e.code() == 1 => e.err().unwrap_or_default().to_vec(),
+            Err(e) => return Err(e),
+        };
+        let rejects = err
+            .split(|x| *x == b'\n')
+            .filter_map(parse_reject)
+            .collect();
+
+        let changesets = if common::tiprev(client)? > tip {
+            let range = format!("{}:tip", tip + 1);
+            log::Arg {
+                revrange: &[&range],
+                ..Default::default()
+            }
+            .run(client)?
+        } else {
+            Vec::new()
+        };
+
+        Ok(Import {
+            changesets,
+            rejects,
+        })
+    }
 }
 
 impl Client {
-    pub fn import(&mut self, x: Arg) -> Result<Vec<u8>, HglibError> {
-        let (data, _) = x.run(self)?;
-        Ok(data)
+    pub fn import(&mut self, x: Arg) -> Result<Import, HglibError> {
+        x.run(self)
     }
 }
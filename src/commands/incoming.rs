@@ -17,6 +17,7 @@ pub struct Arg<'a> {
     pub limit: Option<u32>,
     pub nomerges: bool,
     pub subrepos: bool,
+    pub filestatus: bool,
 }
 
 impl<'a> Default for Arg<'a> {
@@ -32,18 +33,19 @@ impl<'a> Default for Arg<'a> {
             limit: None,
             nomerges: false,
             subrepos: false,
+            filestatus: false,
         }
     }
 }
 
 impl<'a> Arg<'a> {
-    fn run(&self, client: &mut Client) -> Result<(Vec<u8>, i32), HglibError> {
+    fn runcommand<T: Runner>(&self, client: &mut T) -> Result<(Vec<u8>, i32), HglibError> {
         runcommand!(
             client,
             "incoming",
             &[self.path],
             "--template",
-            common::CHANGESETS_TEMPLATE,
+            common::changesets_template(self.filestatus),
             "-r",
             self.revrange,
             "-f",
@@ -64,31 +66,17 @@ impl<'a> Arg<'a> {
             self.subrepos
         )
     }
-}
 
-#[derive(Debug, PartialEq)]
-pub struct Bookmark {
-    pub bookmark: String,
-    pub revision: String,
-}
-
-#[derive(Debug, PartialEq)]
-pub enum Incoming {
-    Revisions(Vec<common::Revision>),
-    Bookmarks(Vec<Bookmark>),
-    Empty,
-}
-
-impl Client {
-    pub fn incoming(&mut self, x: Arg) -> Result<Incoming, HglibError> {
-        match x.run(self) {
+    pub fn run<T: Runner>(&self, client: &mut T) -> Result<Incoming, HglibError> {
+        let encoding = client.server_encoding();
+        match self.runcommand(client) {
             Ok((data, _)) => {
                 if data.is_empty() {
                     return Ok(Incoming::Empty);
                 }
 
                 let data = common::eatlines(&data, 2);
-                if x.bookmarks {
+                if self.bookmarks {
                     let mut res = Vec::new();
                     let mut tmp: &[u8] = &[];
                     let mut odd = false;
@@ -98,8 +86,8 @@ impl Client {
                     {
                         if odd {
                             res.push(Bookmark {
-                                bookmark: String::from_utf8(tmp.to_vec())?,
-                                revision: String::from_utf8(chunk.to_vec())?,
+                                bookmark: encoding.decode_string(tmp)?,
+                                revision: encoding.decode_string(chunk)?,
                             });
                             odd = false;
                         } else {
@@ -109,11 +97,14 @@ impl Client {
                     }
                     Ok(Incoming::Bookmarks(res))
                 } else {
-                    Ok(Incoming::Revisions(common::parserevs(data.to_vec())?))
+                    Ok(Incoming::Revisions(common::parserevs(
+                        data.to_vec(),
+                        &encoding,
+                    )?))
                 }
             }
             Err(err) => {
-                if err.code == 1 {
+                if err.code() == 1 {
                     Ok(Incoming::Empty)
                 } else {
                     Err(err)
@@ -122,3 +113,22 @@ impl Client {
         }
     }
 }
+
+#[derive(Debug, PartialEq)]
+pub struct Bookmark {
+    pub bookmark: String,
+    pub revision: String,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Incoming {
+    Revisions(Vec<common::Revision>),
+    Bookmarks(Vec<Bookmark>),
+    Empty,
+}
+
+impl Client {
+    pub fn incoming(&mut self, x: Arg) -> Result<Incoming, HglibError> {
+        x.run(self)
+    }
+}
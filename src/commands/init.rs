@@ -24,7 +24,7 @@ impl<'a> Default for Arg<'a> {
 }
 
 impl<'a> Arg<'a> {
-    pub fn run<T: Runner>(&self, client: &mut T) -> Result<(Vec<u8>, i32), HglibError> {
+    fn runcommand<T: Runner>(&self, client: &mut T) -> Result<(Vec<u8>, i32), HglibError> {
         runcommand!(
             client,
             "init",
@@ -37,11 +37,15 @@ impl<'a> Arg<'a> {
             self.insecure
         )
     }
+
+    pub fn run<T: Runner>(&self, client: &mut T) -> Result<(), HglibError> {
+        self.runcommand(client)?;
+        Ok(())
+    }
 }
 
 impl Client {
     pub fn init(&mut self, x: Arg) -> Result<(), HglibError> {
-        x.run(self)?;
-        Ok(())
+        x.run(self)
     }
 }
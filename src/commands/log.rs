@@ -3,8 +3,9 @@
 // You can obtain one at http://mozilla.org/MPL/2.0/.
 
 use super::common;
-use crate::client::{Client, HglibError, Runner};
-use crate::{runcommand, MkArg};
+use crate::client::{Client, HglibError, Output, Runner};
+use crate::encoding::Encoding;
+use crate::{runcommand, runcommand_stream, MkArg};
 
 pub struct Arg<'a> {
     pub revrange: &'a [&'a str],
@@ -24,6 +25,8 @@ pub struct Arg<'a> {
     pub nomerges: bool,
     pub include: &'a [&'a str],
     pub exclude: &'a [&'a str],
+    /// fill the added, modified and removed files (slower on big histories)
+    pub filestatus: bool,
 }
 
 impl<'a> Default for Arg<'a> {
@@ -46,57 +49,148 @@ impl<'a> Default for Arg<'a> {
             nomerges: false,
             include: &[],
             exclude: &[],
+            filestatus: false,
         }
     }
 }
 
-impl<'a> Arg<'a> {
-    fn run(&self, client: &mut Client) -> Result<(Vec<u8>, i32), HglibError> {
-        runcommand!(
-            client,
+/// Run log with the given runcommand macro (the arguments are the same for the streaming version)
+macro_rules! log {
+    ( $runcommand: ident, $client: expr, $arg: expr ) => {
+        $runcommand!(
+            $client,
             "log",
-            self.files,
+            $arg.files,
             "--template",
-            common::CHANGESETS_TEMPLATE,
+            common::changesets_template($arg.filestatus),
             "-r",
-            self.revrange,
+            $arg.revrange,
             "-f",
-            self.follow,
+            $arg.follow,
             "--follow-first",
-            self.followfirst,
+            $arg.followfirst,
             "-d",
-            self.date,
+            $arg.date,
             "-C",
-            self.copies,
+            $arg.copies,
             "-k",
-            self.keyword,
+            $arg.keyword,
             "--removed",
-            self.removed,
+            $arg.removed,
             "-m",
-            self.onlymerges,
+            $arg.onlymerges,
             "-u",
-            self.user,
+            $arg.user,
             "-b",
-            self.branch,
+            $arg.branch,
             "-P",
-            self.prune,
+            $arg.prune,
             "--hidden",
-            self.hidden,
+            $arg.hidden,
             "-l",
-            self.limit,
+            $arg.limit,
             "-M",
-            self.nomerges,
+            $arg.nomerges,
             "-I",
-            self.include,
+            $arg.include,
             "-X",
-            self.exclude
+            $arg.exclude
         )
+    };
+}
+
+impl<'a> Arg<'a> {
+    fn runcommand<T: Runner>(&self, client: &mut T) -> Result<(Vec<u8>, i32), HglibError> {
+        log!(runcommand, client, self)
+    }
+
+    pub fn run<T: Runner>(&self, client: &mut T) -> Result<Vec<common::Revision>, HglibError> {
+        let encoding = client.server_encoding();
+        let (data, _) = self.runcommand(client)?;
+        common::parserevs(data, &encoding)
+    }
+
+    /// Run the command and get an iterator on the revisions parsed as they're written by hg
+    ///
+    /// Only the data of the current revision are kept in memory and the iteration can be
+    /// stopped at any time.
+    pub fn stream<'r, T: Runner>(&self, client: &'r mut T) -> Result<Revisions<'r>, HglibError> {
+        let encoding = client.server_encoding();
+        let output = log!(runcommand_stream, client, self)?;
+        Ok(Revisions {
+            output,
+            encoding,
+            buf: Vec::new(),
+            pos: 0,
+            done: false,
+        })
+    }
+}
+
+/// The revisions written by `log`, parsed lazily
+pub struct Revisions<'r> {
+    output: Box<dyn Output + 'r>,
+    encoding: Encoding,
+    /// the data which haven't been parsed yet start at pos
+    buf: Vec<u8>,
+    pos: usize,
+    done: bool,
+}
+
+impl<'r> Revisions<'r> {
+    /// Get the end of the next changeset in the buffer if it's complete
+    fn changeset_end(&self) -> Option<usize> {
+        self.buf[self.pos..]
+            .iter()
+            .enumerate()
+            .filter(|(_, c)| **c == b'\0')
+            .nth(common::CHANGESETS_FIELDS - 1)
+            .map(|(i, _)| self.pos + i + 1)
+    }
+
+    fn next_revision(&mut self) -> Result<Option<common::Revision>, HglibError> {
+        loop {
+            if let Some(end) = self.changeset_end() {
+                let fields: Vec<&[u8]> =
+                    self.buf[self.pos..end - 1].split(|x| *x == b'\0').collect();
+                let rev = common::parserev(&fields, &self.encoding)?;
+                self.pos = end;
+                return Ok(Some(rev));
+            }
+            self.buf.drain(..self.pos);
+            self.pos = 0;
+            if let Some(chunk) = self.output.next_chunk()? {
+                self.buf.extend_from_slice(&chunk);
+            } else if self.buf.is_empty() {
+                return Ok(None);
+            } else {
+                return Err(HglibError::parse("Truncated changeset", &self.buf));
+            }
+        }
+    }
+}
+
+impl<'r> Iterator for Revisions<'r> {
+    type Item = Result<common::Revision, HglibError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let res = self.next_revision();
+        if !matches!(res, Ok(Some(_))) {
+            self.done = true;
+        }
+        res.transpose()
     }
 }
 
 impl Client {
-    pub fn log<'a>(&mut self, x: Arg<'a>) -> Result<Vec<common::Revision>, HglibError> {
-        let (data, _) = x.run(self)?;
-        common::parserevs(data)
+    pub fn log(&mut self, x: Arg) -> Result<Vec<common::Revision>, HglibError> {
+        x.run(self)
+    }
+
+    pub fn log_stream(&mut self, x: Arg) -> Result<Revisions<'_>, HglibError> {
+        x.stream(self)
     }
 }
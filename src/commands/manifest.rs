@@ -3,6 +3,8 @@
 // You can obtain one at http://mozilla.org/MPL/2.0/.
 
 use crate::client::{Client, HglibError, Runner};
+use crate::json;
+use crate::path::HgPath;
 use crate::{runcommand, MkArg};
 
 pub struct Arg<'a> {
@@ -20,7 +22,11 @@ impl<'a> Default for Arg<'a> {
 }
 
 impl<'a> Arg<'a> {
-    fn run(&self, client: &mut Client) -> Result<(Vec<u8>, i32), HglibError> {
+    fn runcommand<T: Runner>(
+        &self,
+        client: &mut T,
+        template: &str,
+    ) -> Result<(Vec<u8>, i32), HglibError> {
         runcommand!(
             client,
             "manifest",
@@ -30,34 +36,58 @@ impl<'a> Arg<'a> {
             "--all",
             self.all,
             "--debug",
-            true
+            true,
+            "-T",
+            template
         )
     }
-}
 
-#[derive(Debug, PartialEq)]
-pub struct File {
-    pub node: String,
-    pub perm: String,
-    pub symlink: bool,
-    pub executable: bool,
-    pub filename: String,
-}
+    pub fn run<T: Runner>(&self, client: &mut T) -> Result<Manifest, HglibError> {
+        if client.use_json()? {
+            match self.run_json(client) {
+                Err(HglibError::Parse { .. }) => {}
+                res => return res,
+            }
+        }
+        self.run_text(client)
+    }
 
-#[derive(Debug, PartialEq)]
-pub enum Manifest {
-    All(Vec<String>),
-    Info(Vec<File>),
-}
+    fn run_json<T: Runner>(&self, client: &mut T) -> Result<Manifest, HglibError> {
+        let encoding = client.server_encoding();
+        let (data, _) = self.runcommand(client, "json")?;
+        let files = json::parse(&data)?;
+        let get_path = |obj: &json::Object| -> Result<HgPath, HglibError> {
+            let path = json::get_str(obj, "path")?;
+            Ok(HgPath::from(encoding.encode(&path)?.into_owned()))
+        };
+        if self.all {
+            Ok(Manifest::All(
+                files.iter().map(get_path).collect::<Result<_, _>>()?,
+            ))
+        } else {
+            let mut res = Vec::new();
+            for obj in files.iter() {
+                let kind = json::get_str(obj, "type")?;
+                res.push(File {
+                    node: json::get_str(obj, "hash")?,
+                    perm: json::get_str(obj, "mode")?,
+                    symlink: kind == "@",
+                    executable: kind == "*",
+                    filename: get_path(obj)?,
+                });
+            }
+            Ok(Manifest::Info(res))
+        }
+    }
 
-impl Client {
-    pub fn manifest(&mut self, x: Arg) -> Result<Manifest, HglibError> {
-        let (data, _) = x.run(self)?;
-        if x.all {
+    fn run_text<T: Runner>(&self, client: &mut T) -> Result<Manifest, HglibError> {
+        let encoding = client.server_encoding();
+        let (data, _) = self.runcommand(client, "")?;
+        if self.all {
             Ok(Manifest::All(
                 data.split(|c| *c == b'\n')
                     .filter(|l| !l.is_empty())
-                    .map(|s| String::from_utf8(s.to_vec()).unwrap())
+                    .map(HgPath::from)
                     .collect(),
             ))
         } else {
@@ -65,20 +95,41 @@ impl Client {
             for line in data.split(|c| *c == b'\n').filter(|l| !l.is_empty()) {
                 if line.len() >= 48 {
                     res.push(File {
-                        node: String::from_utf8(unsafe { line.get_unchecked(..40).to_vec() })?,
-                        perm: String::from_utf8(unsafe { line.get_unchecked(41..44).to_vec() })?,
+                        node: encoding.decode_string(unsafe { line.get_unchecked(..40) })?,
+                        perm: encoding.decode_string(unsafe { line.get_unchecked(41..44) })?,
                         symlink: unsafe { *line.get_unchecked(45) == b'@' },
                         executable: unsafe { *line.get_unchecked(45) == b'*' },
-                        filename: String::from_utf8(unsafe { line.get_unchecked(47..).to_vec() })?,
+                        filename: HgPath::from(unsafe { line.get_unchecked(47..) }),
                     });
                 } else {
-                    return Err(HglibError::from(format!(
-                        "Hglib error: invalid length for line: {} <= 48",
-                        line.len()
-                    )));
+                    return Err(HglibError::parse(
+                        format!("Invalid length for line: {} < 48", line.len()),
+                        line,
+                    ));
                 }
             }
             Ok(Manifest::Info(res))
         }
     }
 }
+
+#[derive(Debug, PartialEq)]
+pub struct File {
+    pub node: String,
+    pub perm: String,
+    pub symlink: bool,
+    pub executable: bool,
+    pub filename: HgPath,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Manifest {
+    All(Vec<HgPath>),
+    Info(Vec<File>),
+}
+
+impl Client {
+    pub fn manifest(&mut self, x: Arg) -> Result<Manifest, HglibError> {
+        x.run(self)
+    }
+}
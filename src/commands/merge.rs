@@ -2,8 +2,12 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this file,
 // You can obtain one at http://mozilla.org/MPL/2.0/.
 
-use crate::client::{Client, HglibError, Runner};
-use crate::{runcommand, MkArg};
+use super::common::{self, find_between, Revision};
+use super::update::Update;
+use crate::client::{Client, HglibError, Prompt, Runner};
+use crate::encoding::Encoding;
+use crate::path::HgPath;
+use crate::{runcommand, runcommand_with_prompt, MkArg};
 
 pub struct Arg<'a> {
     pub rev: &'a str,
@@ -21,11 +25,172 @@ impl<'a> Default for Arg<'a> {
     }
 }
 
-impl<'a> Arg<'a> {
-    fn run(&self, client: &mut Client) -> Result<(Vec<u8>, i32), HglibError> {
+/// A possible answer to a question: e.g. 'c' for "(c)hanged version"
+#[derive(Debug, PartialEq)]
+pub struct Choice {
+    pub key: char,
+    pub label: String,
+}
+
+/// A question asked by hg during a merge
+#[derive(Debug, PartialEq)]
+pub struct Question {
+    /// the file to resolve (if it has been found in the question)
+    pub path: Option<HgPath>,
+    /// the text written by hg since the previous question
+    pub text: String,
+    pub choices: Vec<Choice>,
+}
+
+/// Choose the answers to the questions asked when a file cannot be merged
+pub trait Resolver {
+    /// Get the key of the chosen answer (None for the default one)
+    fn choose(&mut self, question: &Question) -> Option<char>;
+}
+
+impl<F: FnMut(&Question) -> Option<char>> Resolver for F {
+    fn choose(&mut self, question: &Question) -> Option<char> {
+        self(question)
+    }
+}
+
+impl Question {
+    fn parse(text: String) -> Question {
+        let data = text.as_bytes();
+        let path = find_between(data, b"file '", b"' was deleted")
+            .or_else(|| find_between(data, b" changed ", b" which "))
+            .or_else(|| find_between(data, b"merge of '", b"' successful"))
+            .or_else(|| find_between(data, b"no tool found to merge ", b"\n"))
+            .or_else(|| find_between(data, b"conflicting flags for ", b"\n"))
+            .or_else(|| find_between(data, b"resolved for ", b"?"))
+            .map(HgPath::from);
+
+        let mut choices: Vec<Choice> = Vec::new();
+        if text.contains("(yn)") {
+            for (key, label) in &[('y', "yes"), ('n', "no")] {
+                choices.push(Choice {
+                    key: *key,
+                    label: label.to_string(),
+                });
+            }
+        }
+        let chars: Vec<char> = text.chars().collect();
+        for i in 1..chars.len().saturating_sub(1) {
+            // A key is a single char in parentheses inside a word: (c)hanged, e(x)ec
+            if chars[i - 1] != '(' || chars[i + 1] != ')' || !chars[i].is_alphabetic() {
+                continue;
+            }
+            let start = chars[..i - 1]
+                .iter()
+                .rposition(|c| !c.is_alphanumeric())
+                .map_or(0, |p| p + 1);
+            let end = chars[i + 2..]
+                .iter()
+                .position(|c| !c.is_alphanumeric())
+                .map_or(chars.len(), |p| p + i + 2);
+            let label: String = chars[start..i - 1]
+                .iter()
+                .chain(&chars[i..=i])
+                .chain(&chars[i + 2..end])
+                .collect();
+            if !choices.iter().any(|c| c.key == chars[i]) {
+                choices.push(Choice {
+                    key: chars[i],
+                    label,
+                });
+            }
+        }
+
+        Question {
+            path,
+            text,
+            choices,
+        }
+    }
+}
+
+struct MergePrompt<'a> {
+    resolver: &'a mut dyn Resolver,
+    encoding: Encoding,
+    /// the position in the output after the previous question
+    pos: usize,
+    answer: Vec<u8>,
+}
+
+impl<'a> Prompt for MergePrompt<'a> {
+    /// Without the output there is no question to answer: with Basic, hg gets
+    /// an empty input and aborts if it has to ask something
+    fn call(&mut self, _size: usize) -> &[u8] {
+        &[]
+    }
+
+    fn answer(&mut self, _size: usize, output: &[u8]) -> &[u8] {
+        let text = &output[self.pos.min(output.len())..];
+        self.pos = output.len();
+        let question = Question::parse(self.encoding.decode_lossy(text).into_owned());
+        self.answer = match self.resolver.choose(&question) {
+            Some(key) => format!("{}\n", key).into_bytes(),
+            None => b"\n".to_vec(),
+        };
+        &self.answer
+    }
+}
+
+macro_rules! merge {
+    ( $client: expr, $arg: expr $(, $o: expr, $x: expr )* ) => {
         runcommand!(
+            $client,
+            "merge",
+            &[""],
+            "-r",
+            $arg.rev,
+            "-f",
+            $arg.force,
+            "-t",
+            $arg.tool,
+            "-y",
+            true
+            $(, $o, $x)*
+        )
+    };
+}
+
+impl<'a> Arg<'a> {
+    pub fn run<T: Runner>(&self, client: &mut T) -> Result<Update, HglibError> {
+        let encoding = client.server_encoding();
+        let (out, err) = match merge!(client, self) {
+            Ok((out, _)) => (out, Vec::new()),
+            // Some files are unresolved
+            Err(err) if err.code() == 1 => (
+                err.out().unwrap_or_default().to_vec(),
+                err.err().unwrap_or_default().to_vec(),
+            ),
+            Err(err) => return Err(err),
+        };
+        let mut update = Update::parse(&out, &err, &encoding)?;
+        update.merging = true;
+        Ok(update)
+    }
+
+    /// Merge and ask the resolver what to do when a file cannot be merged
+    /// (e.g. when it has been deleted on one side or when the merge tool failed)
+    pub fn run_with_resolver<T: Runner>(
+        &self,
+        client: &mut T,
+        resolver: &mut dyn Resolver,
+    ) -> Result<Update, HglibError> {
+        let encoding = client.server_encoding();
+        let prompt = MergePrompt {
+            resolver,
+            encoding: encoding.clone(),
+            pos: 0,
+            answer: Vec::new(),
+        };
+        // The server isn't interactive by default so the prompts would be skipped
+        let res = runcommand_with_prompt!(
             client,
             "merge",
+            prompt,
             &[""],
             "-r",
             self.rev,
@@ -33,15 +198,46 @@ impl<'a> Arg<'a> {
             self.force,
             "-t",
             self.tool,
-            "-y",
-            true
-        )
+            "--config",
+            "ui.interactive=True"
+        );
+        let (out, err) = match res {
+            Ok((out, _)) => (out, Vec::new()),
+            Err(err) if err.code() == 1 => (
+                err.out().unwrap_or_default().to_vec(),
+                err.err().unwrap_or_default().to_vec(),
+            ),
+            Err(err) => return Err(err),
+        };
+        let mut update = Update::parse(&out, &err, &encoding)?;
+        update.merging = true;
+        Ok(update)
+    }
+
+    /// Get the changesets which would be merged (the working directory isn't touched)
+    pub fn run_preview<T: Runner>(&self, client: &mut T) -> Result<Vec<Revision>, HglibError> {
+        let encoding = client.server_encoding();
+        // merge has no --template option so the one used by the displayer is set
+        let template = format!("ui.logtemplate={}", common::CHANGESETS_TEMPLATE);
+        let (data, _) = merge!(client, self, "-P", true, "--config", template.as_str())?;
+        common::parserevs(data, &encoding)
     }
 }
 
 impl Client {
-    pub fn merge(&mut self, x: Arg) -> Result<(), HglibError> {
-        x.run(self)?;
-        Ok(())
+    pub fn merge(&mut self, x: Arg) -> Result<Update, HglibError> {
+        x.run(self)
+    }
+
+    pub fn merge_with_resolver(
+        &mut self,
+        x: Arg,
+        resolver: &mut dyn Resolver,
+    ) -> Result<Update, HglibError> {
+        x.run_with_resolver(self, resolver)
+    }
+
+    pub fn merge_preview(&mut self, x: Arg) -> Result<Vec<Revision>, HglibError> {
+        x.run_preview(self)
     }
 }
@@ -25,8 +25,10 @@ pub mod copy;
 pub mod diff;
 pub mod export;
 pub mod forget;
+pub mod graft;
 pub mod grep;
 pub mod heads;
+pub mod histedit;
 pub mod identify;
 pub mod import;
 pub mod incoming;
@@ -39,16 +41,21 @@ pub mod outgoing;
 pub mod parents;
 pub mod paths;
 pub mod phase;
+pub mod prune;
 pub mod pull;
 pub mod push;
+pub mod rebase;
 pub mod remove;
 pub mod resolve;
 pub mod revert;
 pub mod root;
+pub mod shelve;
 pub mod status;
+pub mod strip;
 pub mod summary;
 pub mod tag;
 pub mod tags;
 pub mod tip;
+pub mod unshelve;
 pub mod update;
 pub mod version;
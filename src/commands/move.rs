@@ -30,7 +30,7 @@ impl<'a> Default for Arg<'a> {
 }
 
 impl<'a> Arg<'a> {
-    fn run(&self, client: &mut Client) -> Result<(Vec<u8>, i32), HglibError> {
+    fn runcommand<T: Runner>(&self, client: &mut T) -> Result<(Vec<u8>, i32), HglibError> {
         runcommand!(
             client,
             "move",
@@ -47,10 +47,14 @@ impl<'a> Arg<'a> {
             self.exclude
         )
     }
+
+    pub fn run<T: Runner>(&self, client: &mut T) -> Result<bool, HglibError> {
+        HglibError::handle_err(self.runcommand(client))
+    }
 }
 
 impl Client {
     pub fn r#move(&mut self, x: Arg) -> Result<bool, HglibError> {
-        HglibError::handle_err(x.run(self))
+        x.run(self)
     }
 }
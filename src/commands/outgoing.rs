@@ -16,6 +16,7 @@ pub struct Arg<'a> {
     pub limit: Option<u32>,
     pub nomerges: bool,
     pub subrepos: bool,
+    pub filestatus: bool,
 }
 
 impl<'a> Default for Arg<'a> {
@@ -30,18 +31,19 @@ impl<'a> Default for Arg<'a> {
             limit: None,
             nomerges: false,
             subrepos: false,
+            filestatus: false,
         }
     }
 }
 
 impl<'a> Arg<'a> {
-    fn run(&self, client: &mut Client) -> Result<(Vec<u8>, i32), HglibError> {
+    fn runcommand<T: Runner>(&self, client: &mut T) -> Result<(Vec<u8>, i32), HglibError> {
         runcommand!(
             client,
             "outgoing",
             &[self.path],
             "--template",
-            common::CHANGESETS_TEMPLATE,
+            common::changesets_template(self.filestatus),
             "-r",
             self.revrange,
             "-f",
@@ -60,27 +62,13 @@ impl<'a> Arg<'a> {
             self.subrepos
         )
     }
-}
 
-#[derive(Debug, PartialEq)]
-pub struct Bookmark {
-    pub bookmark: String,
-    pub revision: String,
-}
-
-#[derive(Debug, PartialEq)]
-pub enum Outgoing {
-    Revisions(Vec<common::Revision>),
-    Bookmarks(Vec<Bookmark>),
-    Empty,
-}
-
-impl Client {
-    pub fn outgoing(&mut self, x: Arg) -> Result<Outgoing, HglibError> {
-        let data = match x.run(self) {
+    pub fn run<T: Runner>(&self, client: &mut T) -> Result<Outgoing, HglibError> {
+        let encoding = client.server_encoding();
+        let data = match self.runcommand(client) {
             Ok(ret) => ret.0,
             Err(e) => {
-                if e.code == 1 {
+                if e.code() == 1 {
                     return Ok(Outgoing::Empty);
                 } else {
                     return Err(e);
@@ -89,7 +77,7 @@ impl Client {
         };
 
         let data = common::eatlines(&data, 2);
-        if x.bookmarks {
+        if self.bookmarks {
             let mut res = Vec::new();
             let mut tmp: &[u8] = &[];
             let mut odd = false;
@@ -99,8 +87,8 @@ impl Client {
             {
                 if odd {
                     res.push(Bookmark {
-                        bookmark: String::from_utf8(tmp.to_vec())?,
-                        revision: String::from_utf8(chunk.to_vec())?,
+                        bookmark: encoding.decode_string(tmp)?,
+                        revision: encoding.decode_string(chunk)?,
                     });
                     odd = false;
                 } else {
@@ -110,7 +98,29 @@ impl Client {
             }
             Ok(Outgoing::Bookmarks(res))
         } else {
-            Ok(Outgoing::Revisions(common::parserevs(data.to_vec())?))
+            Ok(Outgoing::Revisions(common::parserevs(
+                data.to_vec(),
+                &encoding,
+            )?))
         }
     }
 }
+
+#[derive(Debug, PartialEq)]
+pub struct Bookmark {
+    pub bookmark: String,
+    pub revision: String,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Outgoing {
+    Revisions(Vec<common::Revision>),
+    Bookmarks(Vec<Bookmark>),
+    Empty,
+}
+
+impl Client {
+    pub fn outgoing(&mut self, x: Arg) -> Result<Outgoing, HglibError> {
+        x.run(self)
+    }
+}
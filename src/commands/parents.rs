@@ -9,16 +9,21 @@ use crate::{runcommand, MkArg};
 pub struct Arg<'a> {
     pub rev: &'a str,
     pub file: &'a str,
+    pub filestatus: bool,
 }
 
 impl<'a> Default for Arg<'a> {
     fn default() -> Self {
-        Self { rev: "", file: "" }
+        Self {
+            rev: "",
+            file: "",
+            filestatus: false,
+        }
     }
 }
 
 impl<'a> Arg<'a> {
-    fn run(&self, client: &mut Client) -> Result<(Vec<u8>, i32), HglibError> {
+    fn runcommand<T: Runner>(&self, client: &mut T) -> Result<(Vec<u8>, i32), HglibError> {
         runcommand!(
             client,
             "parents",
@@ -26,18 +31,23 @@ impl<'a> Arg<'a> {
             "-r",
             self.rev,
             "--template",
-            common::CHANGESETS_TEMPLATE
+            common::changesets_template(self.filestatus)
         )
     }
-}
 
-impl Client {
-    pub fn parents(&mut self, x: Arg) -> Result<Vec<common::Revision>, HglibError> {
-        let (data, _) = x.run(self)?;
+    pub fn run<T: Runner>(&self, client: &mut T) -> Result<Vec<common::Revision>, HglibError> {
+        let encoding = client.server_encoding();
+        let (data, _) = self.runcommand(client)?;
         if data.is_empty() {
             Ok(Vec::new())
         } else {
-            common::parserevs(data)
+            common::parserevs(data, &encoding)
         }
     }
 }
+
+impl Client {
+    pub fn parents(&mut self, x: Arg) -> Result<Vec<common::Revision>, HglibError> {
+        x.run(self)
+    }
+}
@@ -5,7 +5,8 @@
 use std::collections::HashMap;
 
 use crate::client::{Client, HglibError, Runner};
-use crate::runcommand;
+use crate::json;
+use crate::{runcommand, MkArg};
 
 pub struct Arg<'a> {
     pub name: &'a str,
@@ -18,33 +19,53 @@ impl<'a> Default for Arg<'a> {
 }
 
 impl<'a> Arg<'a> {
-    fn run(&self, client: &mut Client) -> Result<(Vec<u8>, i32), HglibError> {
-        runcommand!(client, "paths", &[self.name])
+    fn runcommand<T: Runner>(
+        &self,
+        client: &mut T,
+        template: &str,
+    ) -> Result<(Vec<u8>, i32), HglibError> {
+        runcommand!(client, "paths", &[self.name], "-T", template)
     }
-}
 
-#[derive(Debug)]
-pub enum Paths {
-    Map(HashMap<String, String>),
-    Value(String),
-}
+    pub fn run<T: Runner>(&self, client: &mut T) -> Result<Paths, HglibError> {
+        if client.use_json()? {
+            match self.run_json(client) {
+                Err(HglibError::Parse { .. }) => {}
+                res => return res,
+            }
+        }
+        self.run_text(client)
+    }
 
-impl Client {
-    pub fn paths(&mut self, x: Arg) -> Result<Paths, HglibError> {
-        let (data, _) = x.run(self)?;
-        if x.name.is_empty() {
+    fn run_json<T: Runner>(&self, client: &mut T) -> Result<Paths, HglibError> {
+        let (data, _) = self.runcommand(client, "json")?;
+        let paths = json::parse(&data)?;
+        if self.name.is_empty() {
+            let mut map = HashMap::new();
+            for obj in paths.iter() {
+                map.insert(json::get_str(obj, "name")?, json::get_str(obj, "url")?);
+            }
+            Ok(Paths::Map(map))
+        } else if let Some(obj) = paths.first() {
+            Ok(Paths::Value(json::get_str(obj, "url")?))
+        } else {
+            Err(HglibError::parse("No path in the json output", &data))
+        }
+    }
+
+    fn run_text<T: Runner>(&self, client: &mut T) -> Result<Paths, HglibError> {
+        let encoding = client.server_encoding();
+        let (data, _) = self.runcommand(client, "")?;
+        if self.name.is_empty() {
             let mut map = HashMap::new();
             for line in data.split(|c| *c == b'\n') {
                 if let Some(eq_pos) = line.iter().position(|c| *c == b' ') {
                     if let Some(two) = line.get(eq_pos + 1..eq_pos + 3) {
                         if two == b"= " {
                             map.insert(
-                                String::from_utf8(unsafe {
-                                    line.get_unchecked(..eq_pos).to_vec()
-                                })?,
-                                String::from_utf8(unsafe {
-                                    line.get_unchecked(eq_pos + 3..).to_vec()
-                                })?,
+                                encoding.decode_string(unsafe { line.get_unchecked(..eq_pos) })?,
+                                encoding
+                                    .decode_string(unsafe { line.get_unchecked(eq_pos + 3..) })?,
                             );
                         }
                     }
@@ -52,13 +73,23 @@ impl Client {
             }
             Ok(Paths::Map(map))
         } else {
-            Ok(Paths::Value(String::from_utf8({
-                let pos = data
-                    .iter()
-                    .rposition(|x| *x != b' ' && *x != b'\n')
-                    .map_or(data.len(), |p| p + 1);
-                data[..pos].to_vec()
-            })?))
+            let pos = data
+                .iter()
+                .rposition(|x| *x != b' ' && *x != b'\n')
+                .map_or(data.len(), |p| p + 1);
+            Ok(Paths::Value(encoding.decode_string(&data[..pos])?))
         }
     }
 }
+
+#[derive(Debug)]
+pub enum Paths {
+    Map(HashMap<String, String>),
+    Value(String),
+}
+
+impl Client {
+    pub fn paths(&mut self, x: Arg) -> Result<Paths, HglibError> {
+        x.run(self)
+    }
+}
@@ -3,6 +3,7 @@
 // You can obtain one at http://mozilla.org/MPL/2.0/.
 
 use crate::client::{Client, HglibError, Runner};
+use crate::json;
 use crate::{runcommand, MkArg};
 
 pub struct Arg<'a> {
@@ -26,7 +27,7 @@ impl<'a> Default for Arg<'a> {
 }
 
 impl<'a> Arg<'a> {
-    fn run(&self, client: &mut Client) -> Result<(Vec<u8>, i32), HglibError> {
+    fn runcommand<T: Runner>(&self, client: &mut T) -> Result<(Vec<u8>, i32), HglibError> {
         runcommand!(
             client,
             "phase",
@@ -41,6 +42,64 @@ impl<'a> Arg<'a> {
             self.force
         )
     }
+
+    fn runcommand_json<T: Runner>(&self, client: &mut T) -> Result<(Vec<u8>, i32), HglibError> {
+        // phase has no formatter: the phases are got from log
+        let revs: &[&str] = if self.revs.is_empty() {
+            &["."]
+        } else {
+            self.revs
+        };
+        runcommand!(client, "log", &[""], "-r", revs, "-T", "json")
+    }
+
+    pub fn run<T: Runner>(&self, client: &mut T) -> Result<Option<Vec<Phase>>, HglibError> {
+        if self.draft || self.public || self.secret {
+            self.runcommand(client)?;
+            return Ok(None);
+        }
+        if client.use_json()? {
+            match self.run_json(client) {
+                Err(HglibError::Parse { .. }) => {}
+                res => return res.map(Some),
+            }
+        }
+        self.run_text(client).map(Some)
+    }
+
+    fn run_json<T: Runner>(&self, client: &mut T) -> Result<Vec<Phase>, HglibError> {
+        let (data, _) = self.runcommand_json(client)?;
+        json::parse(&data)?
+            .iter()
+            .map(|obj| {
+                Ok(Phase {
+                    num: json::get_u64(obj, "rev")?,
+                    phase: json::get_str(obj, "phase")?,
+                })
+            })
+            .collect()
+    }
+
+    fn run_text<T: Runner>(&self, client: &mut T) -> Result<Vec<Phase>, HglibError> {
+        let encoding = client.server_encoding();
+        let (data, _) = self.runcommand(client)?;
+        let mut phases = Vec::new();
+        let mut num: Option<u64> = None;
+        for elem in data
+            .split(|x| *x == b'\n' || *x == b' ' || *x == b':')
+            .filter(|x| !x.is_empty())
+        {
+            num = if let Some(num) = num {
+                let phase = encoding.decode_string(elem)?;
+                phases.push(Phase { num, phase });
+                None
+            } else {
+                let num = elem.iter().fold(0, |r, x| r * 10 + u64::from(*x - b'0'));
+                Some(num)
+            }
+        }
+        Ok(phases)
+    }
 }
 
 #[derive(Debug, PartialEq)]
@@ -51,26 +110,6 @@ pub struct Phase {
 
 impl Client {
     pub fn phase(&mut self, x: Arg) -> Result<Option<Vec<Phase>>, HglibError> {
-        let (data, _) = x.run(self)?;
-        if x.draft || x.public || x.secret {
-            Ok(None)
-        } else {
-            let mut phases = Vec::new();
-            let mut num: Option<u64> = None;
-            for elem in data
-                .split(|x| *x == b'\n' || *x == b' ' || *x == b':')
-                .filter(|x| !x.is_empty())
-            {
-                num = if let Some(num) = num {
-                    let phase = String::from_utf8(elem.to_vec())?;
-                    phases.push(Phase { num, phase });
-                    None
-                } else {
-                    let num = elem.iter().fold(0, |r, x| r * 10 + u64::from(*x - b'0'));
-                    Some(num)
-                }
-            }
-            Ok(Some(phases))
-        }
+        x.run(self)
     }
 }
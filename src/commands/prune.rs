@@ -0,0 +1,110 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this file,
+// You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use std::path::PathBuf;
+
+use super::config;
+use super::strip;
+use crate::client::{Client, HglibError, Runner};
+use crate::{runcommand, MkArg};
+
+/// Prune the changesets with evolve: the history is only stripped without evolve
+/// when it's explicitly allowed
+pub struct Arg<'a> {
+    pub revs: &'a [&'a str],
+    /// the successors of the pruned changesets (requires evolve)
+    pub successors: &'a [&'a str],
+    pub keep: bool,
+    pub bookmark: &'a [&'a str],
+    /// strip the changesets when evolve isn't enabled (they're destroyed)
+    pub fallback_to_strip: bool,
+}
+
+impl<'a> Default for Arg<'a> {
+    fn default() -> Self {
+        Self {
+            revs: &[],
+            successors: &[],
+            keep: false,
+            bookmark: &[],
+            fallback_to_strip: false,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Prune {
+    /// Some obsolescence markers have been recorded
+    Pruned,
+    /// evolve isn't enabled so the changesets have been stripped (with `fallback_to_strip`):
+    /// the path of the backup bundle is given
+    Stripped(Option<PathBuf>),
+}
+
+impl<'a> Arg<'a> {
+    fn runcommand<T: Runner>(&self, client: &mut T) -> Result<(Vec<u8>, i32), HglibError> {
+        runcommand!(
+            client,
+            "prune",
+            &[""],
+            "-r",
+            self.revs,
+            "-s",
+            self.successors,
+            "-k",
+            self.keep,
+            "-B",
+            self.bookmark
+        )
+    }
+
+    /// Check if evolve is enabled (e.g. "evolve =" or "hgext3rd.evolve =")
+    fn has_evolve<T: Runner>(client: &mut T) -> Result<bool, HglibError> {
+        let configs = match (config::Arg {
+            names: &["extensions"],
+            ..Default::default()
+        })
+        .run(client)
+        {
+            Ok(configs) => configs,
+            // no extensions at all
+            Err(err) if err.code() == 1 => return Ok(false),
+            Err(err) => return Err(err),
+        };
+        Ok(configs.iter().any(|c| {
+            (c.key == "evolve" || c.key.ends_with(".evolve")) && !c.value.starts_with('!')
+        }))
+    }
+
+    pub fn run<T: Runner>(&self, client: &mut T) -> Result<Prune, HglibError> {
+        if Arg::has_evolve(client)? {
+            self.runcommand(client)?;
+            return Ok(Prune::Pruned);
+        }
+        if !self.successors.is_empty() {
+            return Err(HglibError::invalid_arg(
+                "The successors cannot be recorded without evolve",
+            ));
+        }
+        if !self.fallback_to_strip {
+            return Err(HglibError::invalid_arg(
+                "The evolve extension isn't enabled: use fallback_to_strip to strip the changesets",
+            ));
+        }
+        let backup = strip::Arg {
+            revs: self.revs,
+            keep: self.keep,
+            bookmark: self.bookmark,
+            ..Default::default()
+        }
+        .run(client)?;
+        Ok(Prune::Stripped(backup))
+    }
+}
+
+impl Client {
+    pub fn prune(&mut self, x: Arg) -> Result<Prune, HglibError> {
+        x.run(self)
+    }
+}
@@ -0,0 +1,108 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this file,
+// You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use super::common::contains;
+use super::resolve;
+use crate::client::{Client, HglibError, Runner};
+use crate::path::HgPath;
+use crate::{runcommand, MkArg};
+
+pub struct Arg<'a> {
+    pub source: &'a [&'a str],
+    pub base: &'a [&'a str],
+    pub rev: &'a [&'a str],
+    pub dest: &'a str,
+    pub collapse: bool,
+    pub keep: bool,
+    pub keepbranches: bool,
+    pub tool: &'a str,
+    pub dryrun: bool,
+    pub cont: bool,
+    pub abort: bool,
+}
+
+impl<'a> Default for Arg<'a> {
+    fn default() -> Self {
+        Self {
+            source: &[],
+            base: &[],
+            rev: &[],
+            dest: "",
+            collapse: false,
+            keep: false,
+            keepbranches: false,
+            tool: "",
+            dryrun: false,
+            cont: false,
+            abort: false,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Rebase {
+    /// The changesets have been rebased (or would be with --dry-run)
+    Rebased,
+    NothingToRebase,
+    /// The rebase stopped on some conflicts: the unresolved files must be resolved
+    /// before continuing (with --dry-run, the rebase is aborted and the list is empty)
+    Conflicts(Vec<HgPath>),
+}
+
+impl<'a> Arg<'a> {
+    fn runcommand<T: Runner>(&self, client: &mut T) -> Result<(Vec<u8>, i32), HglibError> {
+        runcommand!(
+            client,
+            "rebase",
+            &[""],
+            "-s",
+            self.source,
+            "-b",
+            self.base,
+            "-r",
+            self.rev,
+            "-d",
+            self.dest,
+            "--collapse",
+            self.collapse,
+            "--keep",
+            self.keep,
+            "--keepbranches",
+            self.keepbranches,
+            "-t",
+            self.tool,
+            "-n",
+            self.dryrun,
+            "--continue",
+            self.cont,
+            "--abort",
+            self.abort,
+            "--config",
+            "extensions.rebase="
+        )
+    }
+
+    pub fn run<T: Runner>(&self, client: &mut T) -> Result<Rebase, HglibError> {
+        let err = match self.runcommand(client) {
+            Ok(_) => return Ok(Rebase::Rebased),
+            Err(err) => err,
+        };
+        let (out, msg) = (err.out().unwrap_or_default(), err.err().unwrap_or_default());
+        if err.code() == 1
+            && (contains(out, b"nothing to rebase") || contains(msg, b"nothing to rebase"))
+        {
+            Ok(Rebase::NothingToRebase)
+        } else if contains(msg, b"unresolved conflicts") || contains(out, b"hit a merge conflict") {
+            Ok(Rebase::Conflicts(resolve::unresolved(client)?))
+        } else {
+            Err(err)
+        }
+    }
+}
+
+impl Client {
+    pub fn rebase(&mut self, x: Arg) -> Result<Rebase, HglibError> {
+        x.run(self)
+    }
+}
@@ -3,6 +3,8 @@
 // You can obtain one at http://mozilla.org/MPL/2.0/.
 
 use crate::client::{Client, HglibError, Runner};
+use crate::json;
+use crate::path::HgPath;
 use crate::{runcommand, MkArg};
 
 pub struct Arg<'a> {
@@ -32,7 +34,11 @@ impl<'a> Default for Arg<'a> {
 }
 
 impl<'a> Arg<'a> {
-    fn run(&self, client: &mut Client) -> Result<(Vec<u8>, i32), HglibError> {
+    fn runcommand<T: Runner>(
+        &self,
+        client: &mut T,
+        template: &str,
+    ) -> Result<(Vec<u8>, i32), HglibError> {
         runcommand!(
             client,
             "resolve",
@@ -50,9 +56,78 @@ impl<'a> Arg<'a> {
             "-I",
             self.include,
             "-X",
-            self.exclude
+            self.exclude,
+            "-T",
+            template
         )
     }
+
+    pub fn run<T: Runner>(&self, client: &mut T) -> Result<Option<Vec<Resolve>>, HglibError> {
+        if !self.listfiles {
+            self.runcommand(client, "")?;
+            return Ok(None);
+        }
+        if client.use_json()? {
+            match self.run_json(client) {
+                Err(HglibError::Parse { .. }) => {}
+                res => return res.map(Some),
+            }
+        }
+        self.run_text(client).map(Some)
+    }
+
+    fn get_kind(status: &[u8]) -> Result<Kind, HglibError> {
+        match status {
+            b"R" => Ok(Kind::Resolved),
+            b"U" => Ok(Kind::Unresolved),
+            _ => Err(HglibError::parse("Invalid value", status)),
+        }
+    }
+
+    fn run_json<T: Runner>(&self, client: &mut T) -> Result<Vec<Resolve>, HglibError> {
+        let encoding = client.server_encoding();
+        let (data, _) = self.runcommand(client, "json")?;
+        json::parse(&data)?
+            .iter()
+            .map(|obj| {
+                let path = json::get_str(obj, "path")?;
+                Ok(Resolve {
+                    kind: Self::get_kind(json::get_str(obj, "mergestatus")?.as_bytes())?,
+                    filename: HgPath::from(encoding.encode(&path)?.into_owned()),
+                })
+            })
+            .collect()
+    }
+
+    fn run_text<T: Runner>(&self, client: &mut T) -> Result<Vec<Resolve>, HglibError> {
+        let (data, _) = self.runcommand(client, "")?;
+        let mut res = Vec::new();
+        for line in data.split(|c| *c == b'\n') {
+            if line.len() >= 3 {
+                let filename = unsafe { line.get_unchecked(2..) };
+                let filename = HgPath::from(filename);
+                let kind = Self::get_kind(unsafe { line.get_unchecked(..1) })
+                    .map_err(|_| HglibError::parse("Invalid value", line))?;
+                res.push(Resolve { kind, filename });
+            }
+        }
+        Ok(res)
+    }
+}
+
+/// Get the unresolved files of the current merge state
+pub(crate) fn unresolved<T: Runner>(client: &mut T) -> Result<Vec<HgPath>, HglibError> {
+    let files = Arg {
+        listfiles: true,
+        ..Default::default()
+    }
+    .run(client)?
+    .unwrap_or_default();
+    Ok(files
+        .into_iter()
+        .filter(|f| f.kind == Kind::Unresolved)
+        .map(|f| f.filename)
+        .collect())
 }
 
 #[derive(Debug, PartialEq)]
@@ -64,32 +139,11 @@ pub enum Kind {
 #[derive(Debug, PartialEq)]
 pub struct Resolve {
     pub kind: Kind,
-    pub filename: String,
+    pub filename: HgPath,
 }
 
 impl Client {
     pub fn resolve(&mut self, x: Arg) -> Result<Option<Vec<Resolve>>, HglibError> {
-        let (data, _) = x.run(self)?;
-        if x.listfiles {
-            let mut res = Vec::new();
-            for line in data.split(|c| *c == b'\n') {
-                if line.len() >= 3 {
-                    let filename = unsafe { line.get_unchecked(2..) };
-                    let filename = String::from_utf8(filename.to_vec())?;
-                    let c = unsafe { line.get_unchecked(0) };
-                    let kind = match c {
-                        b'R' => Kind::Resolved,
-                        b'U' => Kind::Unresolved,
-                        _ => {
-                            return Err(HglibError::from("Invalid value"));
-                        }
-                    };
-                    res.push(Resolve { kind, filename });
-                }
-            }
-            Ok(Some(res))
-        } else {
-            Ok(None)
-        }
+        x.run(self)
     }
 }
@@ -14,20 +14,25 @@ impl Default for Arg {
 }
 
 impl Arg {
-    fn run(&self, client: &mut Client) -> Result<(Vec<u8>, i32), HglibError> {
+    fn runcommand<T: Runner>(&self, client: &mut T) -> Result<(Vec<u8>, i32), HglibError> {
         runcommand!(client, "root", &[""])
     }
-}
 
-impl Client {
-    pub fn root(&mut self, x: Arg) -> Result<String, HglibError> {
-        let (data, _) = x.run(self)?;
+    pub fn run<T: Runner>(&self, client: &mut T) -> Result<String, HglibError> {
+        let encoding = client.server_encoding();
+        let (data, _) = self.runcommand(client)?;
         let pos = data
             .iter()
             .rposition(|x| *x != b' ' && *x != b'\n')
             .map_or(data.len(), |p| p + 1);
         let data = &data[..pos];
 
-        Ok(String::from_utf8(data.to_vec())?)
+        encoding.decode_string(data)
+    }
+}
+
+impl Client {
+    pub fn root(&mut self, x: Arg) -> Result<String, HglibError> {
+        x.run(self)
     }
 }
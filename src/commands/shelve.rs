@@ -0,0 +1,168 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this file,
+// You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use regex::bytes::Regex;
+
+use super::diff::{DiffStat, FileStat};
+use crate::client::{Client, HglibError, Runner};
+use crate::patch;
+use crate::{runcommand, MkArg};
+
+pub struct Arg<'a> {
+    /// the files to shelve (all the changes if empty)
+    pub files: &'a [&'a str],
+    pub name: &'a str,
+    pub message: &'a str,
+    pub keep: bool,
+    pub unknown: bool,
+    pub addremove: bool,
+    pub include: &'a [&'a str],
+    pub exclude: &'a [&'a str],
+}
+
+impl<'a> Default for Arg<'a> {
+    fn default() -> Self {
+        Self {
+            files: &[],
+            name: "",
+            message: "",
+            keep: false,
+            unknown: false,
+            addremove: false,
+            include: &[],
+            exclude: &[],
+        }
+    }
+}
+
+impl<'a> Arg<'a> {
+    fn runcommand<T: Runner>(&self, client: &mut T) -> Result<(Vec<u8>, i32), HglibError> {
+        runcommand!(
+            client,
+            "shelve",
+            self.files,
+            "-n",
+            self.name,
+            "-m",
+            self.message,
+            "-k",
+            self.keep,
+            "-u",
+            self.unknown,
+            "-A",
+            self.addremove,
+            "-I",
+            self.include,
+            "-X",
+            self.exclude,
+            "--config",
+            "extensions.shelve="
+        )
+    }
+
+    /// Get the name of the shelf (None if there was nothing to shelve)
+    pub fn run<T: Runner>(&self, client: &mut T) -> Result<Option<String>, HglibError> {
+        let encoding = client.server_encoding();
+        let data = match self.runcommand(client) {
+            Ok((data, _)) => data,
+            // Nothing changed
+            Err(err) if err.code() == 1 => return Ok(None),
+            Err(err) => return Err(err),
+        };
+        let prefix = b"shelved as ";
+        for line in data.split(|x| *x == b'\n') {
+            if let Some(name) = line.strip_prefix(prefix) {
+                return Ok(Some(encoding.decode_lossy(name).into_owned()));
+            }
+        }
+        Err(HglibError::parse("Invalid shelve output", &data))
+    }
+}
+
+pub struct ListArg<'a> {
+    /// the shelves to list (all if empty)
+    pub names: &'a [&'a str],
+    /// get the changed files with their stats
+    pub stat: bool,
+}
+
+impl<'a> Default for ListArg<'a> {
+    fn default() -> Self {
+        Self {
+            names: &[],
+            stat: false,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Shelf {
+    pub name: String,
+    /// e.g. "5m ago"
+    pub age: String,
+    pub description: String,
+    /// only filled with `stat`
+    pub files: Vec<FileStat>,
+}
+
+impl<'a> ListArg<'a> {
+    fn runcommand<T: Runner>(
+        &self,
+        client: &mut T,
+        names: &[&str],
+        patch: bool,
+    ) -> Result<(Vec<u8>, i32), HglibError> {
+        runcommand!(
+            client,
+            "shelve",
+            names,
+            "-l",
+            !patch,
+            "-p",
+            patch,
+            "--config",
+            "extensions.shelve="
+        )
+    }
+
+    pub fn run<T: Runner>(&self, client: &mut T) -> Result<Vec<Shelf>, HglibError> {
+        let encoding = client.server_encoding();
+        let (data, _) = self.runcommand(client, self.names, false)?;
+        // The name is padded and followed by the age in parentheses
+        let pat = Regex::new(r"^(.+?) *\(([^)]*)\) *(.*)$").unwrap();
+        let mut shelves = Vec::new();
+        for line in data.split(|x| *x == b'\n').filter(|x| !x.is_empty()) {
+            let caps = pat
+                .captures(line)
+                .ok_or_else(|| HglibError::parse("Invalid shelf", line))?;
+            shelves.push(Shelf {
+                name: encoding.decode_lossy(&caps[1]).into_owned(),
+                age: encoding.decode_lossy(&caps[2]).into_owned(),
+                description: encoding.decode_lossy(&caps[3]).into_owned(),
+                files: Vec::new(),
+            });
+        }
+
+        if self.stat {
+            // The stats written by hg are scaled to the terminal width so they're
+            // computed from the patches
+            for shelf in shelves.iter_mut() {
+                let (data, _) = self.runcommand(client, &[shelf.name.as_str()], true)?;
+                let files = patch::parse_diff(&data, &encoding)?;
+                shelf.files = DiffStat::new(&files).files;
+            }
+        }
+        Ok(shelves)
+    }
+}
+
+impl Client {
+    pub fn shelve(&mut self, x: Arg) -> Result<Option<String>, HglibError> {
+        x.run(self)
+    }
+
+    pub fn shelve_list(&mut self, x: ListArg) -> Result<Vec<Shelf>, HglibError> {
+        x.run(self)
+    }
+}
@@ -3,6 +3,7 @@
 // You can obtain one at http://mozilla.org/MPL/2.0/.
 
 use crate::client::{Client, HglibError, Runner};
+use crate::path::HgPath;
 use crate::{runcommand, MkArg};
 
 pub struct Arg<'a> {
@@ -44,7 +45,7 @@ impl<'a> Default for Arg<'a> {
 }
 
 impl<'a> Arg<'a> {
-    fn run(&self, client: &mut Client) -> Result<(Vec<u8>, i32), HglibError> {
+    fn runcommand<T: Runner>(&self, client: &mut T) -> Result<(Vec<u8>, i32), HglibError> {
         runcommand!(
             client,
             "status",
@@ -81,33 +82,15 @@ impl<'a> Arg<'a> {
             true
         )
     }
-}
 
-#[derive(Debug, PartialEq)]
-pub enum Code {
-    Modified,
-    Added,
-    Removed,
-    Clean,
-    Missing,
-    NotTracked,
-    Ignored,
-    Origin,
-}
-
-#[derive(Debug, PartialEq)]
-pub struct Status {
-    pub code: Code,
-    pub filename: String,
-}
-
-impl Client {
-    pub fn status(&mut self, x: Arg) -> Result<Vec<Status>, HglibError> {
-        if !x.rev.is_empty() && !x.change.is_empty() {
-            return Err(HglibError::from("Cannot specify both rev and change"));
+    pub fn run<T: Runner>(&self, client: &mut T) -> Result<Vec<Status>, HglibError> {
+        if !self.rev.is_empty() && !self.change.is_empty() {
+            return Err(HglibError::invalid_arg(
+                "Cannot specify both rev and change",
+            ));
         }
 
-        let (data, _) = x.run(self)?;
+        let (data, _) = self.runcommand(client)?;
         let mut res = Vec::new();
         for line in data.split(|c| *c == b'\0').filter(|l| l.len() >= 3) {
             let c = unsafe { line.get_unchecked(0) };
@@ -121,13 +104,39 @@ impl Client {
                 b'I' => Code::Ignored,
                 b' ' => Code::Origin,
                 _ => {
-                    return Err(HglibError::from(format!("Invalid code: {}", *c)));
+                    return Err(HglibError::parse(format!("Invalid code: {}", *c), line));
                 }
             };
             let filename = unsafe { line.get_unchecked(2..) };
-            let filename = String::from_utf8(filename.to_vec())?;
-            res.push(Status { code, filename });
+            res.push(Status {
+                code,
+                filename: HgPath::from(filename),
+            });
         }
         Ok(res)
     }
 }
+
+#[derive(Debug, PartialEq)]
+pub enum Code {
+    Modified,
+    Added,
+    Removed,
+    Clean,
+    Missing,
+    NotTracked,
+    Ignored,
+    Origin,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Status {
+    pub code: Code,
+    pub filename: HgPath,
+}
+
+impl Client {
+    pub fn status(&mut self, x: Arg) -> Result<Vec<Status>, HglibError> {
+        x.run(self)
+    }
+}
@@ -0,0 +1,65 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this file,
+// You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use std::path::PathBuf;
+
+use crate::client::{Client, HglibError, Runner};
+use crate::path::HgPath;
+use crate::{runcommand, MkArg};
+
+pub struct Arg<'a> {
+    pub revs: &'a [&'a str],
+    pub keep: bool,
+    pub bookmark: &'a [&'a str],
+    pub nobackup: bool,
+    pub force: bool,
+}
+
+impl<'a> Default for Arg<'a> {
+    fn default() -> Self {
+        Self {
+            revs: &[],
+            keep: false,
+            bookmark: &[],
+            nobackup: false,
+            force: false,
+        }
+    }
+}
+
+impl<'a> Arg<'a> {
+    fn runcommand<T: Runner>(&self, client: &mut T) -> Result<(Vec<u8>, i32), HglibError> {
+        runcommand!(
+            client,
+            "strip",
+            self.revs,
+            "-k",
+            self.keep,
+            "-B",
+            self.bookmark,
+            "--no-backup",
+            self.nobackup,
+            "-f",
+            self.force,
+            "--config",
+            "extensions.strip="
+        )
+    }
+
+    /// Get the path of the backup bundle (None with --no-backup)
+    pub fn run<T: Runner>(&self, client: &mut T) -> Result<Option<PathBuf>, HglibError> {
+        let (data, _) = self.runcommand(client)?;
+        let prefix = b"saved backup bundle to ";
+        Ok(data
+            .split(|x| *x == b'\n')
+            .find_map(|line| line.strip_prefix(prefix))
+            .map(|path| HgPath::from(path).to_path_buf()))
+    }
+}
+
+impl Client {
+    pub fn strip(&mut self, x: Arg) -> Result<Option<PathBuf>, HglibError> {
+        x.run(self)
+    }
+}
@@ -18,54 +18,20 @@ impl Default for Arg {
 }
 
 impl Arg {
-    fn run(&self, client: &mut Client) -> Result<(Vec<u8>, i32), HglibError> {
+    fn runcommand<T: Runner>(&self, client: &mut T) -> Result<(Vec<u8>, i32), HglibError> {
         runcommand!(client, "summary", &[""], "--remote", self.remote)
     }
-}
 
-#[derive(Debug, Default, PartialEq)]
-pub struct Revision {
-    pub rev: i64,
-    pub node: String,
-    pub tags: String,
-    pub message: String,
-}
-
-#[derive(Debug, Default, PartialEq)]
-pub struct Remote {
-    pub outgoing: u64,
-    pub incoming: u64,
-    pub outgoing_bookmarks: u64,
-    pub incoming_bookmarks: u64,
-}
-
-#[derive(Debug, Default, PartialEq)]
-pub struct Mq {
-    pub applied: u64,
-    pub unapplied: u64,
-}
-
-#[derive(Debug, Default, PartialEq)]
-pub struct Summary {
-    pub parent: Vec<Revision>,
-    pub branch: String,
-    pub commit: bool,
-    pub update: u32,
-    pub remote: Option<Remote>,
-    pub mq: Mq,
-    pub others: HashMap<String, String>,
-}
-
-impl Client {
-    pub fn summary(&mut self, x: Arg) -> Result<Summary, HglibError> {
-        let (data, _) = x.run(self)?;
+    pub fn run<T: Runner>(&self, client: &mut T) -> Result<Summary, HglibError> {
+        let encoding = client.server_encoding();
+        let (data, _) = self.runcommand(client)?;
 
         let mut summary = Summary::default();
         let mut wait_message = false;
 
         for line in data.split(|x| *x == b'\n').filter(|x| !x.is_empty()) {
             if wait_message {
-                let message = String::from_utf8(line[1..].to_vec()).unwrap();
+                let message = encoding.decode_lossy(&line[1..]).into_owned();
                 summary.parent.last_mut().unwrap().message = message;
                 wait_message = false;
                 continue;
@@ -113,7 +79,7 @@ impl Client {
                     wait_message = rev != -1;
                 }
                 b"branch" => {
-                    summary.branch = String::from_utf8(value.to_vec()).unwrap();
+                    summary.branch = encoding.decode_lossy(value).into_owned();
                 }
                 b"commit" => {
                     let clean = b"(clean)";
@@ -130,7 +96,7 @@ impl Client {
                     };
                 }
                 b"remote" => {
-                    if !x.remote {
+                    if !self.remote {
                         continue;
                     }
 
@@ -171,8 +137,8 @@ impl Client {
                 }
                 b"mq" => {}
                 _ => {
-                    let name = String::from_utf8(name.to_vec()).unwrap();
-                    let value = String::from_utf8(value.to_vec()).unwrap();
+                    let name = encoding.decode_lossy(name).into_owned();
+                    let value = encoding.decode_lossy(value).into_owned();
 
                     summary.others.insert(name, value);
                 }
@@ -182,3 +148,42 @@ impl Client {
         Ok(summary)
     }
 }
+
+#[derive(Debug, Default, PartialEq)]
+pub struct Revision {
+    pub rev: i64,
+    pub node: String,
+    pub tags: String,
+    pub message: String,
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub struct Remote {
+    pub outgoing: u64,
+    pub incoming: u64,
+    pub outgoing_bookmarks: u64,
+    pub incoming_bookmarks: u64,
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub struct Mq {
+    pub applied: u64,
+    pub unapplied: u64,
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub struct Summary {
+    pub parent: Vec<Revision>,
+    pub branch: String,
+    pub commit: bool,
+    pub update: u32,
+    pub remote: Option<Remote>,
+    pub mq: Mq,
+    pub others: HashMap<String, String>,
+}
+
+impl Client {
+    pub fn summary(&mut self, x: Arg) -> Result<Summary, HglibError> {
+        x.run(self)
+    }
+}
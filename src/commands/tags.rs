@@ -3,6 +3,7 @@
 // You can obtain one at http://mozilla.org/MPL/2.0/.
 
 use crate::client::{Client, HglibError, Runner};
+use crate::json;
 use crate::{runcommand, MkArg};
 
 pub struct Arg {}
@@ -14,22 +15,42 @@ impl Default for Arg {
 }
 
 impl Arg {
-    fn run(&self, client: &mut Client) -> Result<(Vec<u8>, i32), HglibError> {
-        runcommand!(client, "tags", &[""], "-v", true)
+    fn runcommand<T: Runner>(
+        &self,
+        client: &mut T,
+        template: &str,
+    ) -> Result<(Vec<u8>, i32), HglibError> {
+        runcommand!(client, "tags", &[""], "-v", true, "-T", template)
     }
-}
 
-#[derive(Debug, PartialEq)]
-pub struct Tag {
-    pub name: String,
-    pub rev: u64,
-    pub node: String,
-    pub islocal: bool,
-}
+    pub fn run<T: Runner>(&self, client: &mut T) -> Result<Vec<Tag>, HglibError> {
+        if client.use_json()? {
+            match self.run_json(client) {
+                Err(HglibError::Parse { .. }) => {}
+                res => return res,
+            }
+        }
+        self.run_text(client)
+    }
 
-impl Client {
-    pub fn tags(&mut self, x: Arg) -> Result<Vec<Tag>, HglibError> {
-        let (data, _) = x.run(self)?;
+    fn run_json<T: Runner>(&self, client: &mut T) -> Result<Vec<Tag>, HglibError> {
+        let (data, _) = self.runcommand(client, "json")?;
+        json::parse(&data)?
+            .iter()
+            .map(|obj| {
+                Ok(Tag {
+                    name: json::get_str(obj, "tag")?,
+                    rev: json::get_u64(obj, "rev")?,
+                    node: json::get_short_node(obj, "node")?,
+                    islocal: json::get_str(obj, "type")? == "local",
+                })
+            })
+            .collect()
+    }
+
+    fn run_text<T: Runner>(&self, client: &mut T) -> Result<Vec<Tag>, HglibError> {
+        let encoding = client.server_encoding();
+        let (data, _) = self.runcommand(client, "")?;
         let mut tags = Vec::new();
         for line in data.split(|x| *x == b'\n').filter(|x| !x.is_empty()) {
             let islocal = line.ends_with(b" local");
@@ -41,7 +62,8 @@ impl Client {
 
             let mut iter = line.rsplitn(2, |x| *x == b' ');
             let rev_node = iter.next().unwrap();
-            let name = std::str::from_utf8(iter.next().unwrap())?
+            let name = encoding
+                .decode(iter.next().unwrap())?
                 .trim_end()
                 .to_string();
 
@@ -50,7 +72,7 @@ impl Client {
                 .take_while(|x| **x != b':')
                 .fold(0, |r, x| r * 10 + u64::from(*x - b'0'));
             let node = iter.as_slice();
-            let node = String::from_utf8(node.to_vec())?;
+            let node = encoding.decode_string(node)?;
             tags.push(Tag {
                 name,
                 rev,
@@ -61,3 +83,17 @@ impl Client {
         Ok(tags)
     }
 }
+
+#[derive(Debug, PartialEq)]
+pub struct Tag {
+    pub name: String,
+    pub rev: u64,
+    pub node: String,
+    pub islocal: bool,
+}
+
+impl Client {
+    pub fn tags(&mut self, x: Arg) -> Result<Vec<Tag>, HglibError> {
+        x.run(self)
+    }
+}
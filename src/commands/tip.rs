@@ -6,32 +6,39 @@ use super::common;
 use crate::client::{Client, HglibError, Runner};
 use crate::{runcommand, MkArg};
 
-pub struct Arg {}
+pub struct Arg {
+    pub filestatus: bool,
+}
 
 impl Default for Arg {
     fn default() -> Self {
-        Self {}
+        Self { filestatus: false }
     }
 }
 
 impl Arg {
-    fn run(&self, client: &mut Client) -> Result<(Vec<u8>, i32), HglibError> {
+    fn runcommand<T: Runner>(&self, client: &mut T) -> Result<(Vec<u8>, i32), HglibError> {
         runcommand!(
             client,
             "tip",
             &[""],
             "--template",
-            common::CHANGESETS_TEMPLATE
+            common::changesets_template(self.filestatus)
         )
     }
-}
 
-impl Client {
-    pub fn tip(&mut self, x: Arg) -> Result<common::Revision, HglibError> {
-        let (data, _) = x.run(self)?;
-        let mut rev = common::parserevs(data)?;
+    pub fn run<T: Runner>(&self, client: &mut T) -> Result<common::Revision, HglibError> {
+        let encoding = client.server_encoding();
+        let (data, _) = self.runcommand(client)?;
+        let mut rev = common::parserevs(data, &encoding)?;
         let rev = rev.pop().unwrap();
 
         Ok(rev)
     }
 }
+
+impl Client {
+    pub fn tip(&mut self, x: Arg) -> Result<common::Revision, HglibError> {
+        x.run(self)
+    }
+}
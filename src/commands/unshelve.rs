@@ -0,0 +1,77 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this file,
+// You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use super::common;
+use super::resolve;
+use crate::client::{Client, HglibError, Runner};
+use crate::path::HgPath;
+use crate::{runcommand, MkArg};
+
+pub struct Arg<'a> {
+    /// the shelf to restore (the most recent one if empty)
+    pub name: &'a str,
+    pub keep: bool,
+    pub tool: &'a str,
+    pub cont: bool,
+    pub abort: bool,
+}
+
+impl<'a> Default for Arg<'a> {
+    fn default() -> Self {
+        Self {
+            name: "",
+            keep: false,
+            tool: "",
+            cont: false,
+            abort: false,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Unshelve {
+    Unshelved,
+    /// The unshelve stopped on some conflicts: the unresolved files must be
+    /// resolved before continuing
+    Conflicts(Vec<HgPath>),
+}
+
+impl<'a> Arg<'a> {
+    fn runcommand<T: Runner>(&self, client: &mut T) -> Result<(Vec<u8>, i32), HglibError> {
+        runcommand!(
+            client,
+            "unshelve",
+            &[self.name],
+            "-k",
+            self.keep,
+            "-t",
+            self.tool,
+            "-c",
+            self.cont,
+            "-a",
+            self.abort,
+            "--config",
+            "extensions.shelve="
+        )
+    }
+
+    pub fn run<T: Runner>(&self, client: &mut T) -> Result<Unshelve, HglibError> {
+        let err = match self.runcommand(client) {
+            Ok(_) => return Ok(Unshelve::Unshelved),
+            Err(err) => err,
+        };
+        let msg = err.err().unwrap_or_default();
+        if common::contains(msg, b"unresolved conflicts") {
+            Ok(Unshelve::Conflicts(resolve::unresolved(client)?))
+        } else {
+            Err(err)
+        }
+    }
+}
+
+impl Client {
+    pub fn unshelve(&mut self, x: Arg) -> Result<Unshelve, HglibError> {
+        x.run(self)
+    }
+}
@@ -2,7 +2,10 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this file,
 // You can obtain one at http://mozilla.org/MPL/2.0/.
 
+use super::common;
 use crate::client::{Client, HglibError, Runner};
+use crate::encoding::Encoding;
+use crate::path::HgPath;
 use crate::{runcommand, MkArg};
 
 pub struct Arg<'a> {
@@ -24,7 +27,7 @@ impl<'a> Default for Arg<'a> {
 }
 
 impl<'a> Arg<'a> {
-    fn run(&self, client: &mut Client) -> Result<(Vec<u8>, i32), HglibError> {
+    fn runcommand<T: Runner>(&self, client: &mut T) -> Result<(Vec<u8>, i32), HglibError> {
         runcommand!(
             client,
             "update",
@@ -39,60 +42,112 @@ impl<'a> Arg<'a> {
             self.date
         )
     }
+
+    pub fn run<T: Runner>(&self, client: &mut T) -> Result<Update, HglibError> {
+        let encoding = client.server_encoding();
+        let (out, err) = match self.runcommand(client) {
+            Ok((out, _)) => (out, Vec::new()),
+            // Some files are unresolved
+            Err(err) if err.code() == 1 => (
+                err.out().unwrap_or_default().to_vec(),
+                err.err().unwrap_or_default().to_vec(),
+            ),
+            Err(err) => return Err(err),
+        };
+        let mut update = Update::parse(&out, &err, &encoding)?;
+        update.merging = update.unresolved != 0;
+        Ok(update)
+    }
 }
 
-#[derive(Debug, PartialEq)]
+/// The outcome of an update or a merge
+#[derive(Debug, Default, PartialEq)]
 pub struct Update {
     pub updated: u32,
     pub merged: u32,
     pub removed: u32,
     pub unresolved: u32,
+    /// the files where the merge failed
+    pub conflicts: Vec<HgPath>,
+    /// true when the working directory is left in a merge state (to commit or to resolve)
+    pub merging: bool,
+    pub activated: Option<String>,
+    pub deactivated: Option<String>,
 }
 
-impl Client {
-    pub fn update(&mut self, x: Arg) -> Result<Update, HglibError> {
-        let data = match x.run(self) {
-            Ok((data, _)) => data,
-            Err(err) => {
-                if err.code == 1 && err.out.is_some() {
-                    err.out.unwrap()
-                } else {
-                    return Err(err);
-                }
-            }
-        };
-
-        let iter = &mut data.iter();
-        let n = iter.find(|x| b'0' <= **x && **x <= b'9').unwrap();
-        let n = u32::from(n - b'0');
-
-        let updated = iter
-            .take_while(|x| **x != b' ')
-            .fold(n, |r, x| r * 10 + u32::from(*x - b'0'));
-        iter.find(|x| **x == b',').unwrap();
-        iter.next();
+fn between<'a>(line: &'a [u8], prefix: &[u8], suffix: &[u8]) -> Option<&'a [u8]> {
+    if line.len() >= prefix.len() + suffix.len()
+        && line.starts_with(prefix)
+        && line.ends_with(suffix)
+    {
+        Some(&line[prefix.len()..line.len() - suffix.len()])
+    } else {
+        None
+    }
+}
 
-        let merged = iter
-            .take_while(|x| **x != b' ')
-            .fold(0, |r, x| r * 10 + u32::from(*x - b'0'));
-        iter.find(|x| **x == b',').unwrap();
-        iter.next();
+/// Get the file from a line like "warning: conflicts while merging a! (...)",
+/// "merging a failed!" or "merging a incomplete! (...)" (with old versions)
+fn conflict(line: &[u8]) -> Option<&[u8]> {
+    if let Some(rest) = line.strip_prefix(b"warning: conflicts while merging ") {
+        return rest.split(|c| *c == b'!').next();
+    }
+    let rest = line.strip_prefix(b"merging ")?;
+    let end = rest.iter().rposition(|c| *c == b'!')?;
+    let rest = &rest[..end];
+    rest.strip_suffix(b" failed")
+        .or_else(|| rest.strip_suffix(b" incomplete"))
+}
 
-        let removed = iter
-            .take_while(|x| **x != b' ')
-            .fold(0, |r, x| r * 10 + u32::from(*x - b'0'));
-        iter.find(|x| **x == b',').unwrap();
-        iter.next();
+/// Parse "N files updated, N files merged, N files removed, N files unresolved":
+/// the line can start with the text of a prompt which has been answered
+fn parse_counts(line: &[u8], update: &mut Update) -> Option<()> {
+    let parts: Vec<&[u8]> = line.split(|x| *x == b',').collect();
+    for part in &parts[parts.len().checked_sub(4)?..] {
+        let mut words = part.split(|x| *x == b' ').filter(|x| !x.is_empty());
+        let kind = words.next_back()?;
+        words.next_back()?;
+        let n = common::parse_number(words.next_back()?).ok()?;
+        match kind {
+            b"updated" => update.updated = n,
+            b"merged" => update.merged = n,
+            b"removed" => update.removed = n,
+            b"unresolved" => update.unresolved = n,
+            _ => return None,
+        }
+    }
+    Some(())
+}
 
-        let unresolved = iter
-            .take_while(|x| **x != b' ')
-            .fold(0, |r, x| r * 10 + u32::from(*x - b'0'));
+impl Update {
+    /// Parse the messages written by update or merge
+    pub(crate) fn parse(out: &[u8], err: &[u8], encoding: &Encoding) -> Result<Update, HglibError> {
+        let mut update = Update::default();
+        let mut counts = false;
+        // The messages can be on any channel according to the version of Mercurial
+        for line in out.split(|x| *x == b'\n').chain(err.split(|x| *x == b'\n')) {
+            if let Some(name) = between(line, b"(activating bookmark ", b")") {
+                update.activated = Some(encoding.decode_lossy(name).into_owned());
+            } else if let Some(name) = between(line, b"(leaving bookmark ", b")") {
+                update.deactivated = Some(encoding.decode_lossy(name).into_owned());
+            } else if let Some(path) = conflict(line) {
+                let path = HgPath::from(path);
+                if !update.conflicts.contains(&path) {
+                    update.conflicts.push(path);
+                }
+            } else if !counts && line.ends_with(b" unresolved") {
+                counts = parse_counts(line, &mut update).is_some();
+            }
+        }
+        if !counts {
+            return Err(HglibError::parse("Invalid update output", out));
+        }
+        Ok(update)
+    }
+}
 
-        Ok(Update {
-            updated,
-            merged,
-            removed,
-            unresolved,
-        })
+impl Client {
+    pub fn update(&mut self, x: Arg) -> Result<Update, HglibError> {
+        x.run(self)
     }
 }
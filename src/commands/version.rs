@@ -16,9 +16,39 @@ impl Default for Arg {
 }
 
 impl Arg {
-    fn run(&self, client: &mut Client) -> Result<(Vec<u8>, i32), HglibError> {
+    fn runcommand<T: Runner>(&self, client: &mut T) -> Result<(Vec<u8>, i32), HglibError> {
         runcommand!(client, "version", &[""], "-q", true)
     }
+
+    pub fn run<T: Runner>(&self, client: &mut T) -> Result<Version, HglibError> {
+        let encoding = client.server_encoding();
+        let (data, _) = self.runcommand(client)?;
+        let pat = Regex::new(r".*?(\d+)\.(\d+)\.?(\d+)?(\+[0-9a-f-]+)?").unwrap();
+        let cap = pat.captures_iter(&data).next().unwrap();
+
+        let micro = if let Some(buf) = cap.get(3) {
+            Some(
+                buf.as_bytes()
+                    .iter()
+                    .fold(0, |r, x| r * 10 + u32::from(*x - b'0')),
+            )
+        } else {
+            None
+        };
+
+        let build_info = if let Some(buf) = cap.get(4) {
+            Some(encoding.decode_string(buf.as_bytes())?)
+        } else {
+            None
+        };
+
+        Ok(Version {
+            major: cap[1].iter().fold(0, |r, x| r * 10 + u32::from(*x - b'0')),
+            minor: cap[2].iter().fold(0, |r, x| r * 10 + u32::from(*x - b'0')),
+            micro,
+            build_info,
+        })
+    }
 }
 
 #[derive(Debug, PartialEq, Eq)]
@@ -77,31 +107,6 @@ impl PartialOrd<(u32, u32, Option<u32>)> for Version {
 
 impl Client {
     pub fn version(&mut self, x: Arg) -> Result<Version, HglibError> {
-        let (data, _) = x.run(self)?;
-        let pat = Regex::new(r".*?(\d+)\.(\d+)\.?(\d+)?(\+[0-9a-f-]+)?").unwrap();
-        let cap = pat.captures_iter(&data).next().unwrap();
-
-        let micro = if let Some(buf) = cap.get(3) {
-            Some(
-                buf.as_bytes()
-                    .iter()
-                    .fold(0, |r, x| r * 10 + u32::from(*x - b'0')),
-            )
-        } else {
-            None
-        };
-
-        let build_info = if let Some(buf) = cap.get(4) {
-            Some(String::from_utf8(buf.as_bytes().to_vec())?)
-        } else {
-            None
-        };
-
-        Ok(Version {
-            major: cap[1].iter().fold(0, |r, x| r * 10 + u32::from(*x - b'0')),
-            minor: cap[2].iter().fold(0, |r, x| r * 10 + u32::from(*x - b'0')),
-            micro,
-            build_info,
-        })
+        x.run(self)
     }
 }
@@ -0,0 +1,124 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this file,
+// You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use std::borrow::Cow;
+use std::convert::TryFrom;
+
+use crate::error::HglibError;
+
+/// The characters 0x80..0x9f in cp1252 (the undefined ones are mapped on the C1 controls)
+const CP1252: [char; 32] = [
+    '\u{20ac}', '\u{81}', '\u{201a}', '\u{192}', '\u{201e}', '\u{2026}', '\u{2020}', '\u{2021}',
+    '\u{2c6}', '\u{2030}', '\u{160}', '\u{2039}', '\u{152}', '\u{8d}', '\u{17d}', '\u{8f}',
+    '\u{90}', '\u{2018}', '\u{2019}', '\u{201c}', '\u{201d}', '\u{2022}', '\u{2013}', '\u{2014}',
+    '\u{2dc}', '\u{2122}', '\u{161}', '\u{203a}', '\u{153}', '\u{9d}', '\u{17e}', '\u{178}',
+];
+
+/// The encoding used by hg to read the arguments and to write its output
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum Encoding {
+    #[default]
+    Utf8,
+    Ascii,
+    Latin1,
+    Cp1252,
+    /// An encoding we can't transcode: the data are handled as UTF-8
+    Other(String),
+}
+
+impl Encoding {
+    /// Get the encoding from its name (e.g. UTF-8, latin-1, cp1252)
+    pub fn from_name(name: &str) -> Encoding {
+        match name.to_lowercase().replace('_', "-").as_str() {
+            "utf-8" | "utf8" => Encoding::Utf8,
+            "ascii" | "us-ascii" | "646" => Encoding::Ascii,
+            "latin-1" | "latin1" | "iso-8859-1" | "iso8859-1" | "l1" => Encoding::Latin1,
+            "cp1252" | "windows-1252" => Encoding::Cp1252,
+            _ => Encoding::Other(name.to_string()),
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        match self {
+            Encoding::Utf8 => "UTF-8",
+            Encoding::Ascii => "ascii",
+            Encoding::Latin1 => "latin-1",
+            Encoding::Cp1252 => "cp1252",
+            Encoding::Other(name) => name,
+        }
+    }
+
+    /// Decode some data written by hg.
+    ///
+    /// If they can't be decoded, the error contains the raw bytes.
+    pub fn decode<'a>(&self, data: &'a [u8]) -> Result<Cow<'a, str>, HglibError> {
+        if data.is_ascii() {
+            if let Ok(s) = std::str::from_utf8(data) {
+                return Ok(Cow::Borrowed(s));
+            }
+        }
+        match self {
+            Encoding::Latin1 => Ok(Cow::Owned(data.iter().map(|c| char::from(*c)).collect())),
+            Encoding::Cp1252 => Ok(Cow::Owned(
+                data.iter()
+                    .map(|c| match c {
+                        0x80..=0x9f => CP1252[usize::from(c - 0x80)],
+                        _ => char::from(*c),
+                    })
+                    .collect(),
+            )),
+            _ => std::str::from_utf8(data)
+                .map(Cow::Borrowed)
+                .map_err(|source| HglibError::Encoding {
+                    data: data.to_vec(),
+                    source,
+                }),
+        }
+    }
+
+    /// Decode some data written by hg: the invalid sequences are replaced by U+FFFD
+    pub fn decode_lossy<'a>(&self, data: &'a [u8]) -> Cow<'a, str> {
+        self.decode(data)
+            .unwrap_or_else(|_| String::from_utf8_lossy(data))
+    }
+
+    /// Decode some data written by hg into a String
+    pub fn decode_string(&self, data: &[u8]) -> Result<String, HglibError> {
+        self.decode(data).map(Cow::into_owned)
+    }
+
+    /// Encode a string to send it to hg
+    pub fn encode<'a>(&self, s: &'a str) -> Result<Cow<'a, [u8]>, HglibError> {
+        if s.is_ascii() {
+            return Ok(Cow::Borrowed(s.as_bytes()));
+        }
+        let encode_char = |c: char| -> Option<u8> {
+            match self {
+                Encoding::Ascii => None,
+                Encoding::Latin1 => u8::try_from(u32::from(c)).ok(),
+                _ => {
+                    if let Some(pos) = CP1252.iter().position(|x| *x == c) {
+                        Some(0x80 + pos as u8)
+                    } else {
+                        u8::try_from(u32::from(c))
+                            .ok()
+                            .filter(|c| *c < 0x80 || *c >= 0xa0)
+                    }
+                }
+            }
+        };
+        match self {
+            Encoding::Utf8 | Encoding::Other(_) => Ok(Cow::Borrowed(s.as_bytes())),
+            _ => s
+                .chars()
+                .map(|c| {
+                    encode_char(c).ok_or_else(|| {
+                        HglibError::invalid_arg(format!("Cannot encode {:?} in {}", s, self.name()))
+                    })
+                })
+                .collect::<Result<Vec<u8>, _>>()
+                .map(Cow::Owned),
+        }
+    }
+}
@@ -0,0 +1,275 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this file,
+// You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use std::fmt;
+use std::io;
+use std::str::Utf8Error;
+use std::string::FromUtf8Error;
+
+use subprocess::PopenError;
+
+/// What has been collected when running a command which failed
+#[derive(Debug)]
+pub struct CommandError {
+    /// the exit code
+    pub code: i32,
+    /// the data written on the output channel
+    pub out: Vec<u8>,
+    /// the data written on the error channel
+    pub err: Vec<u8>,
+    /// the command line which has been run
+    pub args: Vec<String>,
+}
+
+#[derive(Debug)]
+pub enum HglibError {
+    /// The hg process cannot be spawned
+    Spawn(io::Error),
+    /// An io error occurred when talking with the hg process
+    Io(io::Error),
+    /// The server doesn't follow the command server protocol
+    Protocol(String),
+    /// The command returned a non-zero exit code
+    Command(Box<CommandError>),
+    /// The command aborted: `message` and `hint` are extracted from the error channel
+    Abort {
+        message: String,
+        hint: Option<String>,
+        command: Box<CommandError>,
+    },
+    /// The output of the command cannot be parsed
+    Parse { msg: String, data: Vec<u8> },
+    /// The command expects some data on its input but no `Prompt` has been given
+    PromptRequired(Vec<String>),
+    /// Some data aren't correctly encoded
+    Encoding { data: Vec<u8>, source: Utf8Error },
+    /// The arguments given to the command are invalid
+    InvalidArg(String),
+}
+
+impl CommandError {
+    pub(crate) fn new(args: &[&str], code: i32, out: Vec<u8>, err: Vec<u8>) -> CommandError {
+        CommandError {
+            code,
+            out,
+            err,
+            args: args.iter().map(|arg| arg.to_string()).collect(),
+        }
+    }
+}
+
+impl HglibError {
+    /// Build the error for a command which returned a non-zero exit code
+    pub(crate) fn command(args: &[&str], code: i32, out: Vec<u8>, err: Vec<u8>) -> HglibError {
+        let command = Box::new(CommandError::new(args, code, out, err));
+        if let Some((message, hint)) = parse_abort(&command.err) {
+            HglibError::Abort {
+                message,
+                hint,
+                command,
+            }
+        } else {
+            HglibError::Command(command)
+        }
+    }
+
+    pub(crate) fn spawn(err: PopenError) -> HglibError {
+        match err.into() {
+            HglibError::Io(err) => HglibError::Spawn(err),
+            err => err,
+        }
+    }
+
+    pub(crate) fn parse<S: Into<String>>(msg: S, data: &[u8]) -> HglibError {
+        HglibError::Parse {
+            msg: msg.into(),
+            data: data.to_vec(),
+        }
+    }
+
+    pub(crate) fn protocol<S: Into<String>>(msg: S) -> HglibError {
+        HglibError::Protocol(msg.into())
+    }
+
+    pub(crate) fn invalid_arg<S: Into<String>>(msg: S) -> HglibError {
+        HglibError::InvalidArg(msg.into())
+    }
+
+    /// Get the failed command if any
+    pub fn command_error(&self) -> Option<&CommandError> {
+        match self {
+            HglibError::Command(command) | HglibError::Abort { command, .. } => Some(command),
+            _ => None,
+        }
+    }
+
+    /// Get the exit code of the command or -1 if the error doesn't come from the command
+    pub fn code(&self) -> i32 {
+        self.command_error().map_or(-1, |c| c.code)
+    }
+
+    /// Get the data written on the output channel by the failed command
+    pub fn out(&self) -> Option<&[u8]> {
+        self.command_error().map(|c| c.out.as_slice())
+    }
+
+    /// Get the data written on the error channel by the failed command
+    pub fn err(&self) -> Option<&[u8]> {
+        self.command_error().map(|c| c.err.as_slice())
+    }
+
+    /// Consume the error and get the data written on the output channel
+    pub fn into_out(self) -> Option<Vec<u8>> {
+        match self {
+            HglibError::Command(command) | HglibError::Abort { command, .. } => Some(command.out),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn handle_err(x: Result<(Vec<u8>, i32), HglibError>) -> Result<bool, HglibError> {
+        match x {
+            Ok((_, code)) => Ok(code == 0),
+            Err(err) => {
+                if err.code() == 0 {
+                    Ok(true)
+                } else if err.code() == 1 {
+                    Ok(false)
+                } else {
+                    Err(err)
+                }
+            }
+        }
+    }
+}
+
+/// Get the message and the hint from an error channel containing `abort: ...`
+fn parse_abort(err: &[u8]) -> Option<(String, Option<String>)> {
+    let err = String::from_utf8_lossy(err);
+    let mut lines = err.lines().skip_while(|l| !l.starts_with("abort: "));
+    let message = lines.next()?["abort: ".len()..].to_string();
+    let hint = lines
+        .next()
+        .map(|l| l.trim())
+        .filter(|l| l.starts_with('(') && l.ends_with(')'))
+        .map(|l| l[1..l.len() - 1].to_string());
+    Some((message, hint))
+}
+
+impl fmt::Display for CommandError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let msg = if self.err.is_empty() {
+            String::from_utf8_lossy(&self.out)
+        } else {
+            String::from_utf8_lossy(&self.err)
+        };
+        write!(
+            f,
+            "hg {} returned {}: {}",
+            self.args.join(" "),
+            self.code,
+            msg.trim_end()
+        )
+    }
+}
+
+impl fmt::Display for HglibError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            HglibError::Spawn(e) => write!(f, "Cannot spawn hg: {}", e),
+            HglibError::Io(e) => write!(f, "Io error: {}", e),
+            HglibError::Protocol(msg) => write!(f, "Protocol error: {}", msg),
+            HglibError::Command(command) => write!(f, "{}", command),
+            HglibError::Abort { message, hint, .. } => {
+                write!(f, "abort: {}", message)?;
+                if let Some(hint) = hint {
+                    write!(f, " ({})", hint)?;
+                }
+                Ok(())
+            }
+            HglibError::Parse { msg, data } => write!(
+                f,
+                "Cannot parse the output: {}: {}",
+                msg,
+                String::from_utf8_lossy(data)
+            ),
+            HglibError::PromptRequired(args) => write!(
+                f,
+                "hg {} expects something on its input, please implement a Prompt",
+                args.join(" ")
+            ),
+            HglibError::Encoding { source, .. } => write!(f, "Encoding error: {}", source),
+            HglibError::InvalidArg(msg) => write!(f, "Invalid argument: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for CommandError {}
+
+impl std::error::Error for HglibError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            HglibError::Spawn(e) | HglibError::Io(e) => Some(e),
+            HglibError::Command(command) | HglibError::Abort { command, .. } => {
+                Some(command.as_ref())
+            }
+            HglibError::Encoding { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for HglibError {
+    fn from(err: io::Error) -> HglibError {
+        HglibError::Io(err)
+    }
+}
+
+impl From<PopenError> for HglibError {
+    fn from(err: PopenError) -> HglibError {
+        match err {
+            PopenError::IoError(err) => HglibError::Io(err),
+            err => HglibError::Io(io::Error::other(err)),
+        }
+    }
+}
+
+impl From<FromUtf8Error> for HglibError {
+    fn from(err: FromUtf8Error) -> HglibError {
+        HglibError::Encoding {
+            source: err.utf8_error(),
+            data: err.into_bytes(),
+        }
+    }
+}
+
+impl From<Utf8Error> for HglibError {
+    fn from(err: Utf8Error) -> HglibError {
+        HglibError::Encoding {
+            data: Vec::new(),
+            source: err,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_parse_abort() {
+        assert_eq!(parse_abort(b"some warning\n"), None);
+        assert_eq!(
+            parse_abort(b"abort: unknown revision 'foo'!\n"),
+            Some(("unknown revision 'foo'!".to_string(), None))
+        );
+        assert_eq!(
+            parse_abort(b"warning: bar\nabort: uncommitted changes\n(commit or update --clean to discard changes)\n"),
+            Some((
+                "uncommitted changes".to_string(),
+                Some("commit or update --clean to discard changes".to_string())
+            ))
+        );
+    }
+}
@@ -0,0 +1,62 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this file,
+// You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Helpers to parse the output of the commands run with `-T json`.
+
+use serde_json::{Map, Value};
+
+use crate::error::HglibError;
+
+pub(crate) type Object = Map<String, Value>;
+
+/// The oldest hg version (major, minor) supporting `-T json` for all the read commands
+pub(crate) const MIN_VERSION: (u32, u32) = (4, 3);
+
+/// Parse the output of a command: a list of objects
+pub(crate) fn parse(data: &[u8]) -> Result<Vec<Object>, HglibError> {
+    let value: Value = serde_json::from_slice(data)
+        .map_err(|e| HglibError::parse(format!("Invalid json: {}", e), data))?;
+    match value {
+        Value::Array(items) => items
+            .into_iter()
+            .map(|item| match item {
+                Value::Object(obj) => Ok(obj),
+                _ => Err(HglibError::parse("Expected a json object", data)),
+            })
+            .collect(),
+        _ => Err(HglibError::parse("Expected a json array", data)),
+    }
+}
+
+fn missing(obj: &Object, key: &str) -> HglibError {
+    HglibError::parse(
+        format!("Missing or invalid json field: {}", key),
+        Value::Object(obj.clone()).to_string().as_bytes(),
+    )
+}
+
+pub(crate) fn get_str(obj: &Object, key: &str) -> Result<String, HglibError> {
+    obj.get(key)
+        .and_then(Value::as_str)
+        .map(str::to_string)
+        .ok_or_else(|| missing(obj, key))
+}
+
+pub(crate) fn get_u64(obj: &Object, key: &str) -> Result<u64, HglibError> {
+    obj.get(key)
+        .and_then(Value::as_u64)
+        .ok_or_else(|| missing(obj, key))
+}
+
+/// Get a node as printed in the text output (i.e. the short form)
+pub(crate) fn get_short_node(obj: &Object, key: &str) -> Result<String, HglibError> {
+    let mut node = get_str(obj, key)?;
+    node.truncate(12);
+    Ok(node)
+}
+
+/// Get a boolean field (false if it's missing)
+pub(crate) fn get_bool(obj: &Object, key: &str) -> bool {
+    obj.get(key).and_then(Value::as_bool).unwrap_or(false)
+}
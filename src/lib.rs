@@ -5,6 +5,24 @@
 pub mod client;
 pub use self::client::*;
 
+pub mod error;
+pub use self::error::*;
+
+pub mod encoding;
+pub use self::encoding::*;
+
+pub mod path;
+pub use self::path::*;
+
+pub mod revset;
+pub use self::revset::*;
+
+pub mod replay;
+
+pub mod patch;
+
+pub(crate) mod json;
+
 pub mod builder;
 pub use self::builder::*;
 
@@ -0,0 +1,404 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this file,
+// You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Parse the diffs written by `hg diff` and the patches written by `hg export`
+//! (plain or git format).
+
+use chrono::prelude::*;
+
+use crate::commands::common::{parse_hgdate, parse_number};
+use crate::encoding::Encoding;
+use crate::error::HglibError;
+use crate::path::{HgPath, HgText};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LineKind {
+    Context,
+    Added,
+    Removed,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Line {
+    pub kind: LineKind,
+    /// the content without the prefix and the end of line
+    pub content: Vec<u8>,
+    /// false when the line is followed by "\ No newline at end of file"
+    pub eol: bool,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Hunk {
+    pub old_start: u64,
+    pub old_lines: u64,
+    pub new_start: u64,
+    pub new_lines: u64,
+    /// the text after the ranges (e.g. the function name with --show-function)
+    pub section: String,
+    pub lines: Vec<Line>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Change {
+    Added,
+    Removed,
+    Modified,
+    Renamed,
+    Copied,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct FileDiff {
+    pub change: Change,
+    /// None for an added file
+    pub old_path: Option<HgPath>,
+    /// None for a removed file
+    pub new_path: Option<HgPath>,
+    /// the modes are only written in the git format
+    pub old_mode: Option<u32>,
+    pub new_mode: Option<u32>,
+    pub binary: bool,
+    pub hunks: Vec<Hunk>,
+}
+
+impl FileDiff {
+    /// Get the path of the file after the change (or before if it has been removed)
+    pub fn path(&self) -> &HgPath {
+        self.new_path.as_ref().or(self.old_path.as_ref()).unwrap()
+    }
+}
+
+/// A changeset written by export
+#[derive(Clone, Debug, PartialEq)]
+pub struct Patch {
+    pub user: HgText,
+    pub date: DateTime<FixedOffset>,
+    pub branch: HgText,
+    pub node: String,
+    /// the parents (the null revision isn't included)
+    pub parents: Vec<String>,
+    pub message: HgText,
+    pub files: Vec<FileDiff>,
+}
+
+/// Parse the output of diff
+pub fn parse_diff(data: &[u8], encoding: &Encoding) -> Result<Vec<FileDiff>, HglibError> {
+    Parser::new(data, encoding).files()
+}
+
+/// Parse the output of export
+pub fn parse_export(data: &[u8], encoding: &Encoding) -> Result<Vec<Patch>, HglibError> {
+    let mut parser = Parser::new(data, encoding);
+    let mut patches = Vec::new();
+    while let Some(line) = parser.peek() {
+        if line == PATCH_HEADER {
+            patches.push(parser.patch()?);
+        } else {
+            parser.pos += 1;
+        }
+    }
+    Ok(patches)
+}
+
+const PATCH_HEADER: &[u8] = b"# HG changeset patch";
+
+struct Parser<'a> {
+    lines: Vec<&'a [u8]>,
+    pos: usize,
+    encoding: &'a Encoding,
+}
+
+fn parse_mode(data: &[u8]) -> Result<u32, HglibError> {
+    std::str::from_utf8(data)
+        .ok()
+        .and_then(|s| u32::from_str_radix(s, 8).ok())
+        .ok_or_else(|| HglibError::parse("Invalid mode", data))
+}
+
+/// Parse a range in a hunk header: "start,count" or "start"
+fn parse_range(data: &[u8]) -> Result<(u64, u64), HglibError> {
+    let mut iter = data.splitn(2, |c| *c == b',');
+    let start = parse_number::<u64>(iter.next().unwrap())?;
+    let count = iter.next().map_or(Ok(1), parse_number::<u64>)?;
+    Ok((start, count))
+}
+
+/// Get the path in a ---/+++ line (None for /dev/null)
+fn parse_side(data: &[u8]) -> Option<HgPath> {
+    let path = data.split(|c| *c == b'\t').next().unwrap();
+    if path == b"/dev/null" {
+        None
+    } else {
+        let path = path
+            .strip_prefix(b"a/")
+            .or_else(|| path.strip_prefix(b"b/"))
+            .unwrap_or(path);
+        Some(HgPath::from(path))
+    }
+}
+
+/// Get the paths in "a/foo b/bar"
+fn parse_git_header(data: &[u8]) -> Result<(HgPath, HgPath), HglibError> {
+    // The paths are the same unless the file has been renamed or copied
+    if data.len() % 2 == 1 {
+        let (a, b) = data.split_at(data.len() / 2);
+        if a.starts_with(b"a/") && b.starts_with(b" b/") && a[2..] == b[3..] {
+            return Ok((HgPath::from(&a[2..]), HgPath::from(&b[3..])));
+        }
+    }
+    let pos = data
+        .windows(3)
+        .position(|w| w == b" b/")
+        .filter(|_| data.starts_with(b"a/"))
+        .ok_or_else(|| HglibError::parse("Invalid git header", data))?;
+    Ok((HgPath::from(&data[2..pos]), HgPath::from(&data[pos + 3..])))
+}
+
+/// Get the path in "-r node [-r node] path"
+fn parse_plain_header(mut data: &[u8]) -> HgPath {
+    while let Some(rest) = data.strip_prefix(b"-r ") {
+        data = rest
+            .iter()
+            .position(|c| *c == b' ')
+            .map_or(&[], |pos| &rest[pos + 1..]);
+    }
+    HgPath::from(data)
+}
+
+impl<'a> Parser<'a> {
+    fn new(data: &'a [u8], encoding: &'a Encoding) -> Self {
+        let mut lines: Vec<&[u8]> = data.split(|c| *c == b'\n').collect();
+        if lines.last().is_some_and(|l| l.is_empty()) {
+            lines.pop();
+        }
+        Self {
+            lines,
+            pos: 0,
+            encoding,
+        }
+    }
+
+    fn peek(&self) -> Option<&'a [u8]> {
+        self.lines.get(self.pos).copied()
+    }
+
+    fn next_line(&mut self) -> Option<&'a [u8]> {
+        let line = self.peek();
+        if line.is_some() {
+            self.pos += 1;
+        }
+        line
+    }
+
+    fn decode(&self, data: &[u8]) -> String {
+        self.encoding.decode_lossy(data).into_owned()
+    }
+
+    fn is_file_start(line: &[u8]) -> bool {
+        line.starts_with(b"diff -r ") || line.starts_with(b"diff --git ")
+    }
+
+    fn files(&mut self) -> Result<Vec<FileDiff>, HglibError> {
+        let mut files = Vec::new();
+        while let Some(line) = self.peek() {
+            if Self::is_file_start(line) {
+                files.push(self.file()?);
+            } else if line == PATCH_HEADER {
+                break;
+            } else {
+                self.pos += 1;
+            }
+        }
+        Ok(files)
+    }
+
+    fn file(&mut self) -> Result<FileDiff, HglibError> {
+        let header = self.next_line().unwrap();
+        let (old_path, new_path) = if let Some(rest) = header.strip_prefix(b"diff --git ") {
+            let (a, b) = parse_git_header(rest)?;
+            (Some(a), Some(b))
+        } else {
+            let path = parse_plain_header(&header[b"diff ".len()..]);
+            (Some(path.clone()), Some(path))
+        };
+        let mut file = FileDiff {
+            change: Change::Modified,
+            old_path,
+            new_path,
+            old_mode: None,
+            new_mode: None,
+            binary: false,
+            hunks: Vec::new(),
+        };
+
+        while let Some(line) = self.peek() {
+            if line.starts_with(b"@@ ") || Self::is_file_start(line) || line == PATCH_HEADER {
+                break;
+            }
+            self.pos += 1;
+            if let Some(rest) = line.strip_prefix(b"--- ") {
+                file.old_path = parse_side(rest);
+                if file.old_path.is_none() {
+                    file.change = Change::Added;
+                }
+            } else if let Some(rest) = line.strip_prefix(b"+++ ") {
+                file.new_path = parse_side(rest);
+                if file.new_path.is_none() {
+                    file.change = Change::Removed;
+                }
+            } else if let Some(rest) = line.strip_prefix(b"new file mode ") {
+                file.change = Change::Added;
+                file.old_path = None;
+                file.new_mode = Some(parse_mode(rest)?);
+            } else if let Some(rest) = line.strip_prefix(b"deleted file mode ") {
+                file.change = Change::Removed;
+                file.new_path = None;
+                file.old_mode = Some(parse_mode(rest)?);
+            } else if let Some(rest) = line.strip_prefix(b"old mode ") {
+                file.old_mode = Some(parse_mode(rest)?);
+            } else if let Some(rest) = line.strip_prefix(b"new mode ") {
+                file.new_mode = Some(parse_mode(rest)?);
+            } else if let Some(rest) = line.strip_prefix(b"rename from ") {
+                file.change = Change::Renamed;
+                file.old_path = Some(HgPath::from(rest));
+            } else if let Some(rest) = line.strip_prefix(b"rename to ") {
+                file.new_path = Some(HgPath::from(rest));
+            } else if let Some(rest) = line.strip_prefix(b"copy from ") {
+                file.change = Change::Copied;
+                file.old_path = Some(HgPath::from(rest));
+            } else if let Some(rest) = line.strip_prefix(b"copy to ") {
+                file.new_path = Some(HgPath::from(rest));
+            } else if line == b"GIT binary patch" || line.starts_with(b"Binary file") {
+                // The data of a binary patch are skipped
+                file.binary = true;
+            }
+        }
+
+        while self.peek().is_some_and(|l| l.starts_with(b"@@ ")) {
+            let hunk = self.hunk()?;
+            file.hunks.push(hunk);
+        }
+
+        Ok(file)
+    }
+
+    fn hunk(&mut self) -> Result<Hunk, HglibError> {
+        let header = self.next_line().unwrap();
+        let err = || HglibError::parse("Invalid hunk header", header);
+        let rest = header.strip_prefix(b"@@ -").ok_or_else(err)?;
+        let end = rest.windows(3).position(|w| w == b" @@").ok_or_else(err)?;
+        let mut ranges = rest[..end].splitn(2, |c| *c == b' ');
+        let (old_start, old_lines) = parse_range(ranges.next().unwrap())?;
+        let new = ranges.next().ok_or_else(err)?;
+        let (new_start, new_lines) = parse_range(new.strip_prefix(b"+").ok_or_else(err)?)?;
+        let section = rest[end + 3..]
+            .strip_prefix(b" ")
+            .unwrap_or(&rest[end + 3..]);
+
+        let mut hunk = Hunk {
+            old_start,
+            old_lines,
+            new_start,
+            new_lines,
+            section: self.decode(section),
+            lines: Vec::new(),
+        };
+        let (mut old, mut new) = (old_lines, new_lines);
+        while old > 0 || new > 0 {
+            let line = self
+                .next_line()
+                .ok_or_else(|| HglibError::parse("Truncated hunk", header))?;
+            let (kind, content) = match line.split_first() {
+                Some((b' ', content)) => (LineKind::Context, content),
+                Some((b'+', content)) => (LineKind::Added, content),
+                Some((b'-', content)) => (LineKind::Removed, content),
+                Some((b'\\', _)) => {
+                    if let Some(last) = hunk.lines.last_mut() {
+                        last.eol = false;
+                    }
+                    continue;
+                }
+                // Some tools strip the trailing spaces of the empty context lines
+                None => (LineKind::Context, line),
+                _ => return Err(HglibError::parse("Invalid line in hunk", line)),
+            };
+            match kind {
+                LineKind::Context if old > 0 && new > 0 => {
+                    old -= 1;
+                    new -= 1;
+                }
+                LineKind::Added if new > 0 => new -= 1,
+                LineKind::Removed if old > 0 => old -= 1,
+                _ => return Err(HglibError::parse("Too many lines in hunk", header)),
+            }
+            hunk.lines.push(Line {
+                kind,
+                content: content.to_vec(),
+                eol: true,
+            });
+        }
+        if self.peek().is_some_and(|l| l.starts_with(b"\\")) {
+            self.pos += 1;
+            if let Some(last) = hunk.lines.last_mut() {
+                last.eol = false;
+            }
+        }
+
+        Ok(hunk)
+    }
+
+    fn patch(&mut self) -> Result<Patch, HglibError> {
+        let start = self.next_line().unwrap();
+        let mut user = HgText::default();
+        let mut date = None;
+        let mut branch = HgText::from("default");
+        let mut node = String::new();
+        let mut parents = Vec::new();
+
+        while let Some(line) = self.peek() {
+            let line = match line.strip_prefix(b"# ") {
+                Some(line) => line,
+                None => break,
+            };
+            self.pos += 1;
+            if let Some(rest) = line.strip_prefix(b"User ") {
+                user = HgText::from(rest);
+            } else if let Some(rest) = line.strip_prefix(b"Date ") {
+                date = Some(parse_hgdate(rest)?);
+            } else if let Some(rest) = line.strip_prefix(b"Branch ") {
+                branch = HgText::from(rest);
+            } else if let Some(rest) = line.strip_prefix(b"Node ID ") {
+                node = self.decode(rest);
+            } else if let Some(rest) = line.strip_prefix(b"Parent ") {
+                let parent = self.decode(rest).trim().to_string();
+                if parent.bytes().any(|c| c != b'0') {
+                    parents.push(parent);
+                }
+            }
+        }
+
+        let mut message = Vec::new();
+        while let Some(line) = self.peek() {
+            if Self::is_file_start(line) || line == PATCH_HEADER {
+                break;
+            }
+            self.pos += 1;
+            message.push(line);
+        }
+        // The message is followed by an empty line
+        while message.last().is_some_and(|l| l.is_empty()) {
+            message.pop();
+        }
+
+        Ok(Patch {
+            user,
+            date: date.ok_or_else(|| HglibError::parse("No date in the patch", start))?,
+            branch,
+            node,
+            parents,
+            message: HgText::from(message.join(&b'\n')),
+            files: self.files()?,
+        })
+    }
+}
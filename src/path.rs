@@ -0,0 +1,146 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this file,
+// You can obtain one at http://mozilla.org/MPL/2.0/.
+
+use std::borrow::Cow;
+use std::fmt;
+use std::path::PathBuf;
+
+use crate::encoding::Encoding;
+use crate::error::HglibError;
+
+/// The methods and the conversions shared by the types wrapping some bytes written by hg
+macro_rules! bytes_type {
+    ($name: ident) => {
+        impl $name {
+            pub fn new<B: Into<Vec<u8>>>(bytes: B) -> $name {
+                $name {
+                    bytes: bytes.into(),
+                }
+            }
+
+            pub fn as_bytes(&self) -> &[u8] {
+                &self.bytes
+            }
+
+            pub fn into_bytes(self) -> Vec<u8> {
+                self.bytes
+            }
+
+            pub fn is_empty(&self) -> bool {
+                self.bytes.is_empty()
+            }
+
+            /// Get the bytes as a str if they're valid UTF-8
+            pub fn to_str(&self) -> Option<&str> {
+                std::str::from_utf8(&self.bytes).ok()
+            }
+
+            /// Get the bytes as a string, the invalid sequences are replaced by U+FFFD
+            pub fn to_string_lossy(&self) -> Cow<'_, str> {
+                String::from_utf8_lossy(&self.bytes)
+            }
+
+            /// Decode the bytes with the given encoding
+            pub fn decode(&self, encoding: &Encoding) -> Result<String, HglibError> {
+                encoding.decode_string(&self.bytes)
+            }
+        }
+
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                write!(f, "{}", self.to_string_lossy())
+            }
+        }
+
+        impl AsRef<[u8]> for $name {
+            fn as_ref(&self) -> &[u8] {
+                &self.bytes
+            }
+        }
+
+        impl From<&[u8]> for $name {
+            fn from(bytes: &[u8]) -> $name {
+                $name::new(bytes)
+            }
+        }
+
+        impl From<Vec<u8>> for $name {
+            fn from(bytes: Vec<u8>) -> $name {
+                $name::new(bytes)
+            }
+        }
+
+        impl From<&str> for $name {
+            fn from(s: &str) -> $name {
+                $name::new(s)
+            }
+        }
+
+        impl From<String> for $name {
+            fn from(s: String) -> $name {
+                $name::new(s)
+            }
+        }
+
+        impl PartialEq<str> for $name {
+            fn eq(&self, other: &str) -> bool {
+                self.bytes == other.as_bytes()
+            }
+        }
+
+        impl PartialEq<&str> for $name {
+            fn eq(&self, other: &&str) -> bool {
+                self.bytes == other.as_bytes()
+            }
+        }
+
+        impl PartialEq<String> for $name {
+            fn eq(&self, other: &String) -> bool {
+                self.bytes == other.as_bytes()
+            }
+        }
+    };
+}
+
+/// A path in a repository as written by hg: the bytes are kept as is
+/// so filenames which aren't valid in the current encoding are preserved.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct HgPath {
+    bytes: Vec<u8>,
+}
+
+bytes_type!(HgPath);
+
+impl HgPath {
+    /// Get the path relatively to the repository root (the bytes are used as is)
+    #[cfg(unix)]
+    pub fn to_path_buf(&self) -> PathBuf {
+        use std::ffi::OsStr;
+        use std::os::unix::ffi::OsStrExt;
+        PathBuf::from(OsStr::from_bytes(&self.bytes))
+    }
+
+    /// Get the path relatively to the repository root
+    #[cfg(not(unix))]
+    pub fn to_path_buf(&self) -> PathBuf {
+        PathBuf::from(self.to_string_lossy().into_owned())
+    }
+}
+
+/// Some text written by hg (e.g. a description, an author or a branch):
+/// the bytes are kept as is since the history may contain some data
+/// which aren't valid in the current encoding.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct HgText {
+    bytes: Vec<u8>,
+}
+
+bytes_type!(HgText);
+
+impl HgText {
+    /// Decode the text with the given encoding: the invalid sequences are replaced by U+FFFD
+    pub fn decode_lossy(&self, encoding: &Encoding) -> String {
+        encoding.decode_lossy(&self.bytes).into_owned()
+    }
+}
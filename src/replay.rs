@@ -0,0 +1,350 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this file,
+// You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Record the sessions of a `Client` and replay them without hg.
+//!
+//! A session file is a sequence of records, each one being a header line
+//! followed by some raw data:
+//! ```text
+//! command <len>\n<arg0\0arg1\0...>\n
+//! o <len>\n<data>\n
+//! e <len>\n<data>\n
+//! L <size>\n
+//! r <code>\n
+//! ```
+
+use std::collections::VecDeque;
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+
+use crate::client::{HglibError, Output, Prompt, Runner};
+use crate::encoding::Encoding;
+
+/// A frame sent by the command server
+#[derive(Clone, Debug, PartialEq)]
+pub enum Frame {
+    /// Some data on the output channel
+    Output(Vec<u8>),
+    /// Some data on the error channel
+    Error(Vec<u8>),
+    /// A line is expected on the input (with the given max size)
+    Line(usize),
+    /// Some data are expected on the input (with the given max size)
+    Input(usize),
+    /// The command returned
+    Result(i32),
+}
+
+/// A command and the frames it produced
+#[derive(Clone, Debug, PartialEq)]
+pub struct Session {
+    pub args: Vec<String>,
+    pub frames: Vec<Frame>,
+}
+
+impl Session {
+    pub fn new(args: &[&str]) -> Self {
+        Self {
+            args: args.iter().map(|arg| arg.to_string()).collect(),
+            frames: Vec::new(),
+        }
+    }
+
+    /// Add some data on the output channel
+    pub fn out(mut self, data: &[u8]) -> Self {
+        self.frames.push(Frame::Output(data.to_vec()));
+        self
+    }
+
+    /// Add some data on the error channel
+    pub fn err(mut self, data: &[u8]) -> Self {
+        self.frames.push(Frame::Error(data.to_vec()));
+        self
+    }
+
+    /// Add a line request
+    pub fn line(mut self, size: usize) -> Self {
+        self.frames.push(Frame::Line(size));
+        self
+    }
+
+    /// Add an input request
+    pub fn input(mut self, size: usize) -> Self {
+        self.frames.push(Frame::Input(size));
+        self
+    }
+
+    /// Add the result code
+    pub fn ret(mut self, code: i32) -> Self {
+        self.frames.push(Frame::Result(code));
+        self
+    }
+}
+
+/// Write the sessions
+pub fn write_sessions<W: Write>(w: &mut W, sessions: &[Session]) -> Result<(), HglibError> {
+    for session in sessions {
+        let args = session.args.join("\0");
+        writeln!(w, "command {}", args.len())?;
+        writeln!(w, "{}", args)?;
+        for frame in session.frames.iter() {
+            match frame {
+                Frame::Output(data) | Frame::Error(data) => {
+                    let chan = if let Frame::Output(_) = frame {
+                        'o'
+                    } else {
+                        'e'
+                    };
+                    writeln!(w, "{} {}", chan, data.len())?;
+                    w.write_all(data)?;
+                    writeln!(w)?;
+                }
+                Frame::Line(size) => writeln!(w, "L {}", size)?,
+                Frame::Input(size) => writeln!(w, "I {}", size)?,
+                Frame::Result(code) => writeln!(w, "r {}", code)?,
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Read the sessions written by `write_sessions`
+pub fn read_sessions(data: &[u8]) -> Result<Vec<Session>, HglibError> {
+    let mut sessions: Vec<Session> = Vec::new();
+    let mut pos = 0;
+    while pos < data.len() {
+        let end = data[pos..]
+            .iter()
+            .position(|c| *c == b'\n')
+            .map_or(data.len(), |n| pos + n);
+        let header = std::str::from_utf8(&data[pos..end])?;
+        let err = || HglibError::parse("Invalid session header", header.as_bytes());
+        pos = end + 1;
+
+        if header.is_empty() {
+            continue;
+        }
+
+        let mut iter = header.splitn(2, ' ');
+        let kind = iter.next().ok_or_else(err)?;
+        let value: i64 = iter.next().ok_or_else(err)?.parse().map_err(|_| err())?;
+
+        let mut take = |len: i64| -> Result<&[u8], HglibError> {
+            let len = len as usize;
+            if len > data.len() || pos + len > data.len() {
+                return Err(HglibError::parse("Truncated session data", &data[pos..]));
+            }
+            let buf = &data[pos..pos + len];
+            pos += len + 1;
+            Ok(buf)
+        };
+
+        if kind == "command" {
+            let args = std::str::from_utf8(take(value)?)?;
+            let args: Vec<&str> = args.split('\0').collect();
+            sessions.push(Session::new(&args));
+            continue;
+        }
+
+        let session = sessions
+            .last_mut()
+            .ok_or_else(|| HglibError::parse("Frame without command", header.as_bytes()))?;
+        let frame = match kind {
+            "o" => Frame::Output(take(value)?.to_vec()),
+            "e" => Frame::Error(take(value)?.to_vec()),
+            "L" => Frame::Line(value as usize),
+            "I" => Frame::Input(value as usize),
+            "r" => Frame::Result(value as i32),
+            _ => return Err(err()),
+        };
+        session.frames.push(frame);
+    }
+    Ok(sessions)
+}
+
+/// Save the sessions in a file
+pub fn save<P: AsRef<Path>>(path: P, sessions: &[Session]) -> Result<(), HglibError> {
+    let mut data = Vec::new();
+    write_sessions(&mut data, sessions)?;
+    fs::write(path, data)?;
+    Ok(())
+}
+
+/// A runner replaying some recorded sessions
+#[derive(Debug, Default)]
+pub struct Replay {
+    sessions: VecDeque<Session>,
+    warnings: Vec<u8>,
+    encoding: Encoding,
+    json: bool,
+}
+
+impl Replay {
+    pub fn new(sessions: Vec<Session>) -> Self {
+        Self {
+            sessions: sessions.into(),
+            warnings: Vec::new(),
+            encoding: Encoding::Utf8,
+            json: false,
+        }
+    }
+
+    /// Set the encoding used by the recorded server (default is UTF-8)
+    pub fn encoding(mut self, encoding: &str) -> Self {
+        self.encoding = Encoding::from_name(encoding);
+        self
+    }
+
+    /// Replay sessions recorded with `-T json` (default is false)
+    pub fn json(mut self, json: bool) -> Self {
+        self.json = json;
+        self
+    }
+
+    /// Load the sessions saved in a file
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, HglibError> {
+        let data = fs::read(path)?;
+        Ok(Self::new(read_sessions(&data)?))
+    }
+
+    /// Add a session to replay
+    pub fn push(&mut self, session: Session) {
+        self.sessions.push_back(session);
+    }
+
+    /// Check that all the sessions have been replayed
+    pub fn is_done(&self) -> bool {
+        self.sessions.is_empty()
+    }
+
+    /// Get the data written on the error channel by the last command
+    /// which succeeded (e.g. warnings)
+    pub fn warnings(&self) -> &[u8] {
+        &self.warnings
+    }
+
+    fn next_session(&mut self, args: &[&str]) -> Result<Session, HglibError> {
+        let session = self.sessions.front().ok_or_else(|| {
+            HglibError::protocol(format!("No session to replay for: {}", args.join(" ")))
+        })?;
+        if session
+            .args
+            .iter()
+            .map(|arg| arg.as_str())
+            .ne(args.iter().copied())
+        {
+            return Err(HglibError::protocol(format!(
+                "Unexpected command: {} (expected: {})",
+                args.join(" "),
+                session.args.join(" ")
+            )));
+        }
+        Ok(self.sessions.pop_front().unwrap())
+    }
+}
+
+impl Runner for Replay {
+    fn warnings(&self) -> &[u8] {
+        Replay::warnings(self)
+    }
+
+    fn server_encoding(&self) -> Encoding {
+        self.encoding.clone()
+    }
+
+    fn use_json(&mut self) -> Result<bool, HglibError> {
+        Ok(self.json)
+    }
+
+    fn runcommand<'a>(
+        &mut self,
+        args: &'a [&str],
+        mut prompt: Option<Box<dyn Prompt + 'a>>,
+    ) -> Result<(Vec<u8>, i32), HglibError> {
+        let session = self.next_session(args)?;
+
+        let mut out = Vec::new();
+        let mut err = Vec::new();
+        self.warnings.clear();
+        for frame in session.frames {
+            match frame {
+                Frame::Output(data) => out.extend_from_slice(&data),
+                Frame::Error(data) => err.extend_from_slice(&data),
+                Frame::Line(size) | Frame::Input(size) => {
+                    if let Some(prompt) = prompt.as_mut() {
+                        prompt.answer(size, &out);
+                    } else {
+                        return Err(HglibError::PromptRequired(session.args));
+                    }
+                }
+                Frame::Result(code) => {
+                    return if code != 0 {
+                        Err(HglibError::command(args, code, out, err))
+                    } else {
+                        self.warnings = err;
+                        Ok((out, code))
+                    };
+                }
+            }
+        }
+        Err(HglibError::protocol("No result in the session"))
+    }
+
+    fn runcommand_stream<'r>(
+        &'r mut self,
+        args: &[&str],
+    ) -> Result<Box<dyn Output + 'r>, HglibError> {
+        let session = self.next_session(args)?;
+        self.warnings.clear();
+        Ok(Box::new(ReplayOutput {
+            replay: self,
+            args: session.args,
+            frames: session.frames.into(),
+            err: Vec::new(),
+            done: false,
+        }))
+    }
+}
+
+/// The output of a replayed session: each output frame is a chunk
+struct ReplayOutput<'r> {
+    replay: &'r mut Replay,
+    args: Vec<String>,
+    frames: VecDeque<Frame>,
+    err: Vec<u8>,
+    done: bool,
+}
+
+impl<'r> Output for ReplayOutput<'r> {
+    fn next_chunk(&mut self) -> Result<Option<Vec<u8>>, HglibError> {
+        while let Some(frame) = self.frames.pop_front() {
+            match frame {
+                Frame::Output(data) => return Ok(Some(data)),
+                Frame::Error(data) => self.err.extend_from_slice(&data),
+                Frame::Line(_) | Frame::Input(_) => {
+                    self.frames.clear();
+                    self.done = true;
+                    return Err(HglibError::PromptRequired(self.args.clone()));
+                }
+                Frame::Result(code) => {
+                    self.frames.clear();
+                    self.done = true;
+                    let err = std::mem::take(&mut self.err);
+                    if code != 0 {
+                        let args: Vec<&str> = self.args.iter().map(String::as_str).collect();
+                        return Err(HglibError::command(&args, code, Vec::new(), err));
+                    }
+                    self.replay.warnings = err;
+                }
+            }
+        }
+        if self.done {
+            Ok(None)
+        } else {
+            self.done = true;
+            Err(HglibError::protocol("No result in the session"))
+        }
+    }
+}
@@ -0,0 +1,335 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this file,
+// You can obtain one at http://mozilla.org/MPL/2.0/.
+
+//! Build revset expressions (see `hg help revsets`) with correctly quoted arguments.
+//!
+//! A `Revset` derefs to `&str` so it can be given to any command expecting revisions:
+//! ```
+//! use hglib::{log, update, Revset};
+//!
+//! let revs = Revset::branch("my (branch)")
+//!     .and(Revset::author("o'brien"))
+//!     .limit(10);
+//! assert_eq!(&*revs, "limit((branch('my (branch)') and author('o\\'brien')), 10)");
+//! let log = log::Arg {
+//!     revrange: &[&revs],
+//!     ..Default::default()
+//! };
+//! let update = update::Arg {
+//!     rev: &revs.last(),
+//!     ..Default::default()
+//! };
+//! ```
+
+use std::fmt;
+use std::ops::{BitAnd, BitOr, Deref, Not, Sub};
+
+/// A revset expression
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Revset {
+    expr: String,
+}
+
+/// Quote a string to use it as a revset string literal
+pub fn quote(s: &str) -> String {
+    let mut res = String::with_capacity(s.len() + 2);
+    res.push('\'');
+    for c in s.chars() {
+        match c {
+            '\\' => res.push_str("\\\\"),
+            '\'' => res.push_str("\\'"),
+            '\n' => res.push_str("\\n"),
+            '\r' => res.push_str("\\r"),
+            '\t' => res.push_str("\\t"),
+            '\0' => res.push_str("\\x00"),
+            _ => res.push(c),
+        }
+    }
+    res.push('\'');
+    res
+}
+
+impl Revset {
+    /// Use an expression as is (nothing is quoted)
+    pub fn raw<S: Into<String>>(expr: S) -> Revset {
+        Revset { expr: expr.into() }
+    }
+
+    fn func(name: &str, args: &[&str]) -> Revset {
+        Revset::raw(format!("{}({})", name, args.join(", ")))
+    }
+
+    fn binop(&self, op: &str, other: &Revset) -> Revset {
+        Revset::raw(format!("({} {} {})", self.expr, op, other.expr))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.expr
+    }
+
+    /// A revision number
+    pub fn rev(rev: u64) -> Revset {
+        Revset::raw(rev.to_string())
+    }
+
+    /// A symbol: node (or a prefix), tag, bookmark or branch name
+    pub fn id(symbol: &str) -> Revset {
+        Revset::raw(quote(symbol))
+    }
+
+    /// The working directory parent
+    pub fn wdir_parent() -> Revset {
+        Revset::raw(".")
+    }
+
+    pub fn tip() -> Revset {
+        Revset::raw("tip")
+    }
+
+    pub fn null() -> Revset {
+        Revset::raw("null")
+    }
+
+    pub fn all() -> Revset {
+        Revset::func("all", &[])
+    }
+
+    pub fn none() -> Revset {
+        Revset::func("none", &[])
+    }
+
+    /// `x::y`: the descendants of x which are ancestors of y
+    pub fn dag_range(&self, other: &Revset) -> Revset {
+        Revset::raw(format!("({}::{})", self.expr, other.expr))
+    }
+
+    /// `x:y`: the revisions with a number between x and y
+    pub fn range(&self, other: &Revset) -> Revset {
+        Revset::raw(format!("({}:{})", self.expr, other.expr))
+    }
+
+    pub fn and(&self, other: Revset) -> Revset {
+        self.binop("and", &other)
+    }
+
+    pub fn or(&self, other: Revset) -> Revset {
+        self.binop("or", &other)
+    }
+
+    /// The revisions in self but not in other
+    pub fn minus(&self, other: Revset) -> Revset {
+        self.binop("-", &other)
+    }
+
+    pub fn ancestors(&self) -> Revset {
+        Revset::func("ancestors", &[&self.expr])
+    }
+
+    pub fn descendants(&self) -> Revset {
+        Revset::func("descendants", &[&self.expr])
+    }
+
+    /// The ancestors of self which aren't ancestors of other
+    pub fn only(&self, other: &Revset) -> Revset {
+        Revset::func("only", &[&self.expr, &other.expr])
+    }
+
+    pub fn parents(&self) -> Revset {
+        Revset::func("parents", &[&self.expr])
+    }
+
+    pub fn children(&self) -> Revset {
+        Revset::func("children", &[&self.expr])
+    }
+
+    pub fn heads(&self) -> Revset {
+        Revset::func("heads", &[&self.expr])
+    }
+
+    pub fn roots(&self) -> Revset {
+        Revset::func("roots", &[&self.expr])
+    }
+
+    pub fn first(&self) -> Revset {
+        Revset::func("first", &[&self.expr])
+    }
+
+    pub fn last(&self) -> Revset {
+        Revset::func("last", &[&self.expr])
+    }
+
+    pub fn max(&self) -> Revset {
+        Revset::func("max", &[&self.expr])
+    }
+
+    pub fn min(&self) -> Revset {
+        Revset::func("min", &[&self.expr])
+    }
+
+    pub fn reverse(&self) -> Revset {
+        Revset::func("reverse", &[&self.expr])
+    }
+
+    /// The first n revisions
+    pub fn limit(&self, n: usize) -> Revset {
+        Revset::func("limit", &[&self.expr, &n.to_string()])
+    }
+
+    /// Sort the revisions by the given keys (e.g. `-date`, `rev`, `user`)
+    pub fn sort(&self, keys: &[&str]) -> Revset {
+        Revset::func("sort", &[&self.expr, &quote(&keys.join(" "))])
+    }
+
+    /// The revisions on the given branch
+    pub fn branch(name: &str) -> Revset {
+        Revset::func("branch", &[&quote(name)])
+    }
+
+    /// The revisions on the same branches as the revisions in self
+    pub fn same_branch(&self) -> Revset {
+        Revset::func("branch", &[&self.expr])
+    }
+
+    /// The revisions whose author contains the given string
+    pub fn author(author: &str) -> Revset {
+        Revset::func("author", &[&quote(author)])
+    }
+
+    /// The revisions matching the date spec (see `hg help dates`)
+    pub fn date(spec: &str) -> Revset {
+        Revset::func("date", &[&quote(spec)])
+    }
+
+    /// The revisions committed between the two dates (inclusive)
+    pub fn date_range(from: &str, to: &str) -> Revset {
+        Revset::date(&format!("{} to {}", from, to))
+    }
+
+    /// The revisions committed since the given date
+    pub fn since(date: &str) -> Revset {
+        Revset::date(&format!(">{}", date))
+    }
+
+    /// The revisions committed before the given date
+    pub fn before(date: &str) -> Revset {
+        Revset::date(&format!("<{}", date))
+    }
+
+    /// The revisions touching a file matching the pattern
+    pub fn file(pattern: &str) -> Revset {
+        Revset::func("file", &[&quote(pattern)])
+    }
+
+    /// The revisions whose message, user or files contain the keyword
+    pub fn keyword(keyword: &str) -> Revset {
+        Revset::func("keyword", &[&quote(keyword)])
+    }
+
+    /// The revisions whose description contains the string
+    pub fn desc(s: &str) -> Revset {
+        Revset::func("desc", &[&quote(s)])
+    }
+
+    /// The bookmarked revision (all the bookmarked ones if the name is empty)
+    pub fn bookmark(name: &str) -> Revset {
+        if name.is_empty() {
+            Revset::func("bookmark", &[])
+        } else {
+            Revset::func("bookmark", &[&quote(name)])
+        }
+    }
+
+    /// The tagged revision (all the tagged ones if the name is empty)
+    pub fn tag(name: &str) -> Revset {
+        if name.is_empty() {
+            Revset::func("tag", &[])
+        } else {
+            Revset::func("tag", &[&quote(name)])
+        }
+    }
+
+    pub fn head() -> Revset {
+        Revset::func("head", &[])
+    }
+
+    pub fn merge() -> Revset {
+        Revset::func("merge", &[])
+    }
+
+    pub fn public() -> Revset {
+        Revset::func("public", &[])
+    }
+
+    pub fn draft() -> Revset {
+        Revset::func("draft", &[])
+    }
+
+    pub fn secret() -> Revset {
+        Revset::func("secret", &[])
+    }
+}
+
+impl fmt::Display for Revset {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.expr)
+    }
+}
+
+impl Deref for Revset {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.expr
+    }
+}
+
+impl AsRef<str> for Revset {
+    fn as_ref(&self) -> &str {
+        &self.expr
+    }
+}
+
+impl From<u64> for Revset {
+    fn from(rev: u64) -> Revset {
+        Revset::rev(rev)
+    }
+}
+
+impl From<&str> for Revset {
+    fn from(symbol: &str) -> Revset {
+        Revset::id(symbol)
+    }
+}
+
+impl BitAnd for Revset {
+    type Output = Revset;
+
+    fn bitand(self, other: Revset) -> Revset {
+        self.binop("and", &other)
+    }
+}
+
+impl BitOr for Revset {
+    type Output = Revset;
+
+    fn bitor(self, other: Revset) -> Revset {
+        self.binop("or", &other)
+    }
+}
+
+impl Sub for Revset {
+    type Output = Revset;
+
+    fn sub(self, other: Revset) -> Revset {
+        self.binop("-", &other)
+    }
+}
+
+impl Not for Revset {
+    type Output = Revset;
+
+    fn not(self) -> Revset {
+        Revset::raw(format!("(not {})", self.expr))
+    }
+}
@@ -36,11 +36,11 @@ fn test_basic() {
         hg!(c.client, annotate, files = &["a"]).unwrap(),
         vec![
             Line {
-                info: "0",
+                info: b"0",
                 content: b"a",
             },
             Line {
-                info: "1",
+                info: b"1",
                 content: b"b",
             },
         ],
@@ -61,11 +61,11 @@ fn test_basic() {
         .unwrap(),
         vec![
             Line {
-                info: &format!("test 0 {} a:1", &rev0.node[..12]),
+                info: format!("test 0 {} a:1", &rev0.node[..12]).as_bytes(),
                 content: b"a",
             },
             Line {
-                info: &format!("test 1 {} a:2", &rev1.node[..12]),
+                info: format!("test 1 {} a:2", &rev1.node[..12]).as_bytes(),
                 content: b"b",
             },
         ],
@@ -84,11 +84,11 @@ fn test_files() {
         hg!(c.client, annotate, files = &["a", "b"]).unwrap(),
         vec![
             Line {
-                info: "0",
+                info: b"0",
                 content: b"a",
             },
             Line {
-                info: "1",
+                info: b"1",
                 content: b"b",
             },
         ],
@@ -104,7 +104,7 @@ fn test_two_colons() {
     compare(
         hg!(c.client, annotate, files = &["a"]).unwrap(),
         vec![Line {
-            info: "0",
+            info: b"0",
             content: b"a: b",
         }],
     );
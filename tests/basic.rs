@@ -0,0 +1,112 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this file,
+// You can obtain one at http://mozilla.org/MPL/2.0/.
+
+extern crate hglib;
+
+use std::env;
+use std::fs;
+
+use crate::hglib::{init, root, Basic, Prompt, Runner, HG};
+
+#[test]
+fn test_basic() {
+    let path = env::temp_dir().canonicalize().unwrap().join("basic_basic");
+    let _ = fs::remove_dir_all(&path);
+    let path_str = path.to_str().unwrap();
+
+    assert!(HG!(init, dest = path_str).is_ok());
+    let err = HG!(init, dest = path_str).unwrap_err();
+    assert_eq!(err.code(), 255);
+    assert!(err.err().unwrap().starts_with(b"abort: "));
+
+    let mut basic = Basic::default().cwd(&path).encoding("UTF-8");
+    let root = (root::Arg {}).run(&mut basic).unwrap();
+    assert_eq!(root, path_str);
+
+    assert!(fs::remove_dir_all(&path).is_ok());
+}
+
+#[cfg(unix)]
+#[test]
+fn test_output_and_code() {
+    let mut basic = Basic::default().hg("sh");
+    let (out, code) = basic
+        .runcommand(&["-c", "echo out; echo warn >&2"], None)
+        .unwrap();
+    assert_eq!(out, b"out\n");
+    assert_eq!(code, 0);
+    assert_eq!(basic.warnings(), b"warn\n");
+
+    let err = basic
+        .runcommand(&["-c", "echo out; echo err >&2; exit 3"], None)
+        .unwrap_err();
+    assert_eq!(err.code(), 3);
+    assert_eq!(err.out().unwrap(), b"out\n");
+    assert_eq!(err.err().unwrap(), b"err\n");
+    assert!(basic.warnings().is_empty());
+}
+
+#[cfg(unix)]
+#[test]
+fn test_cwd_and_env() {
+    let tmp = env::temp_dir().canonicalize().unwrap();
+    let mut basic = Basic::default().hg("sh").cwd(&tmp).encoding("latin-1");
+    let (out, _) = basic
+        .runcommand(&["-c", "echo $PWD $HGPLAIN $HGENCODING"], None)
+        .unwrap();
+    assert_eq!(
+        String::from_utf8(out).unwrap(),
+        format!("{} 1 latin-1\n", tmp.to_str().unwrap())
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn test_inherited_env() {
+    // The inherited value is replaced
+    env::set_var("HGENCODING", "ascii");
+    let mut basic = Basic::default().hg("sh").encoding("latin-1");
+    let (out, _) = basic
+        .runcommand(&["-c", "env | grep ^HGENCODING="], None)
+        .unwrap();
+    assert_eq!(out, b"HGENCODING=latin-1\n");
+}
+
+// A prompt which always answers the same thing
+struct Yes;
+
+impl Prompt for Yes {
+    fn call(&mut self, _size: usize) -> &[u8] {
+        b"y\n"
+    }
+}
+
+// A prompt giving all its data at once
+struct Data;
+
+impl Prompt for Data {
+    fn call(&mut self, _size: usize) -> &[u8] {
+        b"foo"
+    }
+
+    fn input(&mut self) -> Option<Vec<u8>> {
+        Some(b"foo".to_vec())
+    }
+}
+
+#[cfg(unix)]
+#[test]
+fn test_prompt() {
+    let mut basic = Basic::default().hg("sh");
+    // The prompt is never asked so the input is empty
+    let (out, _) = basic
+        .runcommand(&["-c", "cat"], Some(Box::new(Yes)))
+        .unwrap();
+    assert!(out.is_empty());
+
+    let (out, _) = basic
+        .runcommand(&["-c", "cat"], Some(Box::new(Data)))
+        .unwrap();
+    assert_eq!(out, b"foo");
+}
@@ -34,7 +34,7 @@ fn test_basic() {
     assert_eq!(
         hg!(c.client, branches).unwrap()[0],
         Branch {
-            name: rev.branch.clone(),
+            name: rev.branch.to_string(),
             rev: rev.rev,
             node: rev.node[..12].to_string()
         }
@@ -32,7 +32,7 @@ fn test_basic() {
     for rev in [rev1, rev0].iter() {
         let rev = &hg!(c.client, log, revrange = &[&rev.rev.to_string()]).unwrap()[0];
         expected.push(Branch {
-            name: rev.branch.clone(),
+            name: rev.branch.to_string(),
             rev: rev.rev,
             node: rev.node[..12].to_string(),
         });
@@ -0,0 +1,234 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this file,
+// You can obtain one at http://mozilla.org/MPL/2.0/.
+
+extern crate hglib;
+
+use std::env;
+
+use crate::hglib::{root, ClientBuilder, HglibError, Runner, ServerInfo};
+
+mod common;
+
+// A fake command server which only sends its hello: the encoding field
+// contains some environment variables so they can be checked
+#[cfg(unix)]
+const FAKE_SERVER: &str = r#"
+msg="capabilities: getencoding runcommand
+encoding: $HGENCODING,$HGPLAIN,$HGPLAINEXCEPT,$HGFOO,$(pwd),$*"
+printf "o\000\000\000\\$(printf %03o ${#msg})%s" "$msg"
+cat > /dev/null
+"#;
+
+#[cfg(unix)]
+#[test]
+fn test_builder_options() {
+    let tmp = env::temp_dir().canonicalize().unwrap();
+    let tmp_str = tmp.to_str().unwrap();
+    let client = ClientBuilder::new(&tmp)
+        .hg("sh")
+        .hg_arg("-c")
+        .hg_arg(FAKE_SERVER)
+        .hg_arg("fakehg")
+        .env("HGFOO", "foo")
+        .encoding("latin-1")
+        .plain_except("i18n")
+        .plain_except("revnums")
+        .config("ui.foo=bar")
+        .traceback(true)
+        .cwd("/")
+        .open()
+        .unwrap();
+    assert_eq!(
+        client.encoding(),
+        format!(
+            "latin-1,1,i18n,revnums,foo,/,serve --cmdserver pipe -R {} --config ui.foo=bar --traceback",
+            tmp_str
+        )
+    );
+
+    let client = ClientBuilder::new(&tmp)
+        .hg("sh")
+        .hg_arg("-c")
+        .hg_arg(FAKE_SERVER)
+        .hg_arg("fakehg")
+        .env("HGFOO", "foo")
+        .env_remove("HGFOO")
+        .plain(false)
+        .plain_except("i18n")
+        .open()
+        .unwrap();
+    assert_eq!(
+        client.encoding(),
+        format!(",,,,{},serve --cmdserver pipe -R {}", tmp_str, tmp_str)
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn test_builder_env_clear() {
+    let tmp = env::temp_dir().canonicalize().unwrap();
+    let tmp_str = tmp.to_str().unwrap();
+    // The variables set explicitly are kept whatever the order of the calls
+    let client = ClientBuilder::new(&tmp)
+        .hg("/bin/sh")
+        .hg_arg("-c")
+        .hg_arg(FAKE_SERVER)
+        .hg_arg("fakehg")
+        .env("HGFOO", "foo")
+        .env_clear()
+        .cwd("/")
+        .open()
+        .unwrap();
+    assert_eq!(
+        client.encoding(),
+        format!(",1,,foo,/,serve --cmdserver pipe -R {}", tmp_str)
+    );
+}
+
+// A fake command server sending its hello in two parts
+#[cfg(unix)]
+const FAKE_SERVER_SLOW: &str = r#"
+msg="capabilities: getencoding runcommand attachio
+encoding: UTF-8
+pid: $$
+pgid: 1234
+foo: bar"
+printf "o\000\000\000\\$(printf %03o ${#msg})"
+sleep 0.1
+printf "%s" "$msg"
+cat > /dev/null
+"#;
+
+#[cfg(unix)]
+#[test]
+fn test_server_info() {
+    let tmp = env::temp_dir().canonicalize().unwrap();
+    let client = ClientBuilder::new(&tmp)
+        .hg("sh")
+        .hg_arg("-c")
+        .hg_arg(FAKE_SERVER_SLOW)
+        .hg_arg("fakehg")
+        .open()
+        .unwrap();
+    let info = client.server_info();
+    assert_eq!(
+        info.capabilities,
+        vec!["getencoding", "runcommand", "attachio"]
+    );
+    assert_eq!(info.encoding, "UTF-8");
+    assert!(info.pid.is_some());
+    assert_eq!(info.pgid, Some(1234));
+    assert_eq!(info.others.get("foo").unwrap(), "bar");
+    assert!(client.has_capability("attachio"));
+    assert!(!client.has_capability("foo"));
+    assert_eq!(Runner::server_info(&client), Some(info));
+}
+
+// A fake command server answering two commands: the first one writes
+// its output in three frames and the second one prints a path
+#[cfg(unix)]
+const FAKE_SERVER_STREAM: &str = r#"
+frame() {
+    printf "$1\000\000\000\\$(printf %03o ${#2})%s" "$2"
+}
+frame o "capabilities: runcommand
+encoding: UTF-8"
+frame o "foo"
+frame e "warning"
+frame o "bar"
+frame o "baz"
+printf "r\000\000\000\004\000\000\000\000"
+frame o "/repo
+"
+printf "r\000\000\000\004\000\000\000\000"
+cat > /dev/null
+"#;
+
+#[cfg(unix)]
+#[test]
+fn test_stream() {
+    let tmp = env::temp_dir().canonicalize().unwrap();
+    let mut client = ClientBuilder::new(&tmp)
+        .hg("sh")
+        .hg_arg("-c")
+        .hg_arg(FAKE_SERVER_STREAM)
+        .hg_arg("fakehg")
+        .open()
+        .unwrap();
+    {
+        let mut output = client.runcommand_stream(&["log"]).unwrap();
+        assert_eq!(output.next_chunk().unwrap().unwrap(), b"foo");
+    }
+    // The remaining frames of the first command have been skipped
+    assert_eq!(root::Arg {}.run(&mut client).unwrap(), "/repo");
+}
+
+// A fake command server answering two commands: the first one asks for
+// a line and aborts since the answer is empty
+#[cfg(unix)]
+const FAKE_SERVER_PROMPT: &str = r#"
+frame() {
+    printf "$1\000\000\000\\$(printf %03o ${#2})%s" "$2"
+}
+frame o "capabilities: runcommand
+encoding: UTF-8"
+printf "L\000\000\020\000"
+frame e "abort: response expected
+"
+printf "r\000\000\000\004\000\000\000\377"
+frame o "/repo
+"
+printf "r\000\000\000\004\000\000\000\000"
+cat > /dev/null
+"#;
+
+#[cfg(unix)]
+#[test]
+fn test_prompt_required() {
+    let tmp = env::temp_dir().canonicalize().unwrap();
+    let mut client = ClientBuilder::new(&tmp)
+        .hg("sh")
+        .hg_arg("-c")
+        .hg_arg(FAKE_SERVER_PROMPT)
+        .hg_arg("fakehg")
+        .open()
+        .unwrap();
+    match client.runcommand(&["merge"], None) {
+        Err(HglibError::PromptRequired(args)) => assert_eq!(args, vec!["merge"]),
+        x => panic!("Expected a prompt error: {:?}", x),
+    }
+    // The remaining frames of the first command have been read
+    assert_eq!(root::Arg {}.run(&mut client).unwrap(), "/repo");
+}
+
+#[test]
+fn test_server_info_parse() {
+    let info = ServerInfo::parse(b"capabilities: runcommand\nencoding: ascii").unwrap();
+    assert_eq!(info.capabilities, vec!["runcommand"]);
+    assert_eq!(info.encoding, "ascii");
+    assert_eq!(info.pid, None);
+
+    match ServerInfo::parse(b"encoding: ascii") {
+        Err(HglibError::Protocol(_)) => {}
+        x => panic!("Expected a protocol error: {:?}", x),
+    }
+}
+
+#[test]
+fn test_builder() {
+    let c = common::TestClient::new("client_builder", &[]);
+    let mut client = ClientBuilder::new(&c.path)
+        .encoding("UTF-8")
+        .config("ui.username=foo")
+        .cwd(env::temp_dir())
+        .open()
+        .unwrap();
+    assert_eq!(client.encoding(), "UTF-8");
+    assert!(client.has_capability("runcommand"));
+    assert!(client.server_info().pid.is_some());
+    assert_eq!(
+        (root::Arg {}).run(&mut client).unwrap(),
+        c.path.to_str().unwrap()
+    );
+}
@@ -63,7 +63,7 @@ fn test_close_branch() {
     assert_eq!(
         hg!(c.client, branches).unwrap(),
         vec![Branch {
-            name: revs[0].branch.clone(),
+            name: revs[0].branch.to_string(),
             rev: revs[0].rev,
             node: revs[0].node[..12].to_string(),
         }]
@@ -73,12 +73,12 @@ fn test_close_branch() {
         hg!(c.client, branches, closed = true).unwrap(),
         vec![
             Branch {
-                name: revs[2].branch.clone(),
+                name: revs[2].branch.to_string(),
                 rev: revs[2].rev,
                 node: revs[2].node[..12].to_string(),
             },
             Branch {
-                name: revs[0].branch.clone(),
+                name: revs[0].branch.to_string(),
                 rev: revs[0].rev,
                 node: revs[0].node[..12].to_string(),
             },
@@ -121,3 +121,40 @@ impl TestClient {
         self.client = Client::open(self.path.to_str().unwrap(), "UTF-8", &[]).unwrap();
     }
 }
+
+/// Join the fields of a changeset as written with the changeset templates
+#[allow(dead_code)]
+pub fn changeset_data(fields: &[&[u8]]) -> Vec<u8> {
+    let mut data = fields.join(&b'\0');
+    data.push(b'\0');
+    data
+}
+
+/// A draft changeset touching "a" as written with CHANGESETS_TEMPLATE,
+/// followed by the extra fields of the template of the command
+#[allow(dead_code)]
+pub fn changeset(rev: u64, desc: &str, extra: &[&str]) -> Vec<u8> {
+    let rev = rev.to_string();
+    let node = format!("{:0>40}", rev);
+    let mut fields: Vec<&[u8]> = vec![
+        rev.as_bytes(),
+        node.as_bytes(),
+        b"-1 0000000000000000000000000000000000000000 -1 0000000000000000000000000000000000000000",
+        b"draft",
+        b"",
+        b"",
+        b"default",
+        b"test",
+        desc.as_bytes(),
+        b"0 0",
+        b"",
+        b"",
+        b"a",
+        b"",
+        b"",
+        b"",
+        b"",
+    ];
+    fields.extend(extra.iter().map(|x| x.as_bytes()));
+    changeset_data(&fields)
+}
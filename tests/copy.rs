@@ -5,7 +5,7 @@
 extern crate hglib;
 
 use crate::hglib::{
-    commit, copy, hg, status,
+    commit, copy, hg, log, status,
     status::{Code, Status},
 };
 
@@ -21,7 +21,7 @@ fn test_basic() {
         hg!(c.client, status).unwrap(),
         vec![Status {
             code: Code::Added,
-            filename: "b".to_string(),
+            filename: "b".into(),
         }]
     );
     c.append("c", &["a"]);
@@ -31,12 +31,28 @@ fn test_basic() {
         vec![
             Status {
                 code: Code::Added,
-                filename: "b".to_string(),
+                filename: "b".into(),
             },
             Status {
                 code: Code::Added,
-                filename: "c".to_string(),
+                filename: "c".into(),
             }
         ]
     );
 }
+
+#[test]
+fn test_log_copies() {
+    let mut c = common::TestClient::new("copy_log_copies", &[]);
+    c.append("a", &["a"]);
+    hg!(c.client, commit, message = "first", addremove = true).unwrap();
+    assert!(hg!(c.client, copy, source = &["a"], dest = "b").unwrap());
+    hg!(c.client, commit, message = "second").unwrap();
+
+    let revs = hg!(c.client, log, copies = true).unwrap();
+    assert_eq!(revs[0].copies, vec![("b".into(), "a".into())]);
+    assert!(revs[1].copies.is_empty());
+
+    let revs = hg!(c.client, log).unwrap();
+    assert!(revs[0].copies.is_empty());
+}
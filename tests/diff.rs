@@ -4,7 +4,13 @@
 
 extern crate hglib;
 
-use crate::hglib::{add, commit, diff, hg};
+use crate::hglib::{
+    add, commit,
+    diff::{self, DiffStat, FileStat},
+    hg,
+    replay::{Replay, Session},
+    HglibError,
+};
 
 mod common;
 
@@ -94,3 +100,105 @@ fn test_basic() {
     let diff = String::from_utf8(diff).unwrap();
     assert_eq!(diff4, diff);
 }
+
+#[test]
+fn test_options() {
+    let mut r = Replay::new(vec![Session::new(&[
+        "diff",
+        "-p",
+        "--reverse",
+        "-w",
+        "-b",
+        "-B",
+        "-Z",
+        "-U",
+        "5",
+        "--root",
+        "sub",
+        "--from",
+        "1",
+        "--to",
+        "2",
+    ])
+    .ret(0)]);
+    let diff = diff::Arg {
+        ignoreallspace: true,
+        ignorespacechange: true,
+        ignoreblanklines: true,
+        ignorespaceateol: true,
+        unified: Some(5),
+        showfunction: true,
+        reverse: true,
+        root: "sub",
+        from: "1",
+        to: "2",
+        ..Default::default()
+    }
+    .run(&mut r)
+    .unwrap();
+    assert!(diff.is_empty());
+    assert!(r.is_done());
+
+    match (diff::Arg {
+        from: "1",
+        change: "2",
+        ..Default::default()
+    })
+    .run(&mut r)
+    {
+        Err(HglibError::InvalidArg(_)) => {}
+        x => panic!("Expected an invalid argument: {:?}", x),
+    }
+}
+
+#[test]
+fn test_stat() {
+    let out = b"diff --git a/a b/a
+--- a/a
++++ b/a
+@@ -1,3 +1,2 @@
+-a
+-b
++c
+ d
+diff --git a/b.bin b/b.bin
+new file mode 100644
+GIT binary patch
+literal 1
+Ic${Nk000310RR91
+
+";
+    let mut r = Replay::new(vec![Session::new(&["diff", "-c", "1", "-g"])
+        .out(out)
+        .ret(0)]);
+    let stat = diff::Arg {
+        change: "1",
+        git: true,
+        stat: true,
+        ..Default::default()
+    }
+    .run_stat(&mut r)
+    .unwrap();
+    assert!(r.is_done());
+    assert_eq!(
+        stat,
+        DiffStat {
+            files: vec![
+                FileStat {
+                    path: "a".into(),
+                    insertions: 1,
+                    deletions: 2,
+                    binary: false,
+                },
+                FileStat {
+                    path: "b.bin".into(),
+                    insertions: 0,
+                    deletions: 0,
+                    binary: true,
+                },
+            ],
+            insertions: 1,
+            deletions: 2,
+        }
+    );
+}
@@ -4,10 +4,89 @@
 
 extern crate hglib;
 
-mod common;
+use std::env;
 
+use crate::hglib::{
+    replay::{Replay, Session},
+    root, Basic, ClientBuilder, Encoding, HglibError, Runner,
+};
+
+#[test]
+fn test_transcoding() {
+    let latin1 = Encoding::from_name("ISO-8859-1");
+    assert_eq!(latin1, Encoding::Latin1);
+    assert_eq!(latin1.decode(b"caf\xe9").unwrap(), "café");
+    assert_eq!(latin1.encode("café").unwrap().as_ref(), b"caf\xe9");
+    assert!(latin1.encode("€").is_err());
+
+    let cp1252 = Encoding::from_name("cp1252");
+    assert_eq!(cp1252.decode(b"\x80 \x9c\xe9").unwrap(), "€ œé");
+    assert_eq!(cp1252.encode("€ œé").unwrap().as_ref(), b"\x80 \x9c\xe9");
+
+    assert!(Encoding::Ascii.encode("é").is_err());
+    assert_eq!(Encoding::Utf8.encode("é").unwrap().as_ref(), "é".as_bytes());
+    match Encoding::Utf8.decode(b"caf\xe9") {
+        Err(HglibError::Encoding { data, .. }) => assert_eq!(data, b"caf\xe9"),
+        x => panic!("Expected an encoding error: {:?}", x),
+    }
+}
+
+#[test]
+fn test_decode_output() {
+    let session = Session::new(&["root", "--"]).out(b"/caf\xe9\n").ret(0);
+    let mut r = Replay::new(vec![session.clone()]).encoding("latin-1");
+    assert_eq!(root::Arg {}.run(&mut r).unwrap(), "/café");
+
+    // The raw bytes are kept in the error
+    let mut r = Replay::new(vec![session]);
+    match (root::Arg {}).run(&mut r) {
+        Err(HglibError::Encoding { data, .. }) => assert_eq!(data, b"/caf\xe9"),
+        x => panic!("Expected an encoding error: {:?}", x),
+    }
+}
+
+#[cfg(unix)]
+#[test]
+fn test_basic_args() {
+    let mut basic = Basic::default().hg("sh").encoding("latin-1");
+    let (out, _) = basic.runcommand(&["-c", "printf %s 'é'"], None).unwrap();
+    assert_eq!(out, b"\xe9");
+}
+
+// A fake command server answering to getencoding and echoing the arguments
+// of runcommand in hexadecimal
+#[cfg(unix)]
+const FAKE_SERVER: &str = r#"
+msg="capabilities: getencoding runcommand
+encoding: ascii"
+printf "o\000\000\000\\$(printf %03o ${#msg})%s" "$msg"
+read cmd
+printf "r\000\000\000\007latin-1"
+read cmd
+len=$(dd bs=1 count=4 2> /dev/null | od -An -tu1 | awk '{print $1 * 16777216 + $2 * 65536 + $3 * 256 + $4}')
+args=$(dd bs=1 count=$len 2> /dev/null | od -An -tx1 | tr -d ' \n')
+printf "o\000\000\000\\$(printf %03o ${#args})%s" "$args"
+printf "r\000\000\000\004\000\000\000\000"
+cat > /dev/null
+"#;
+
+#[cfg(unix)]
 #[test]
-fn test_encoding() {
-    let c = common::TestClient::new("encoding", &[]);
-    assert_eq!(c.client.encoding(), "UTF-8");
+fn test_getencoding() {
+    let mut client = ClientBuilder::new(env::temp_dir())
+        .hg("sh")
+        .hg_arg("-c")
+        .hg_arg(FAKE_SERVER)
+        .hg_arg("fakehg")
+        .open()
+        .unwrap();
+    assert_eq!(client.encoding(), "ascii");
+    assert!(client.runcommand(&["é"], None).is_err());
+
+    assert_eq!(client.getencoding().unwrap(), "latin-1");
+    assert_eq!(client.encoding(), "latin-1");
+    assert_eq!(client.server_encoding(), Encoding::Latin1);
+
+    let (out, _) = client.runcommand(&["log", "-u", "é"], None).unwrap();
+    assert_eq!(out, b"6c6f67002d7500e9");
 }
@@ -0,0 +1,43 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this file,
+// You can obtain one at http://mozilla.org/MPL/2.0/.
+
+extern crate hglib;
+
+use crate::hglib::{HglibError, Runner};
+
+mod common;
+
+#[test]
+fn test_abort() {
+    let mut c = common::TestClient::new("errors_abort", &[]);
+    let err = c
+        .client
+        .runcommand(&["update", "-r", "foo"], None)
+        .unwrap_err();
+    assert_eq!(err.code(), 255);
+    assert!(err
+        .err()
+        .unwrap()
+        .starts_with(b"abort: unknown revision 'foo'"));
+    match err {
+        HglibError::Abort {
+            message, command, ..
+        } => {
+            assert_eq!(message, "unknown revision 'foo'!");
+            assert_eq!(command.args, vec!["update", "-r", "foo"]);
+        }
+        _ => panic!("Expected an abort"),
+    }
+}
+
+#[test]
+fn test_warning() {
+    let mut c = common::TestClient::new("errors_warning", &[]);
+    let (_, code) = c.client.runcommand(&["status", "foo"], None).unwrap();
+    assert_eq!(code, 0);
+    assert!(c.client.warnings().starts_with(b"foo: "));
+
+    c.client.runcommand(&["status"], None).unwrap();
+    assert!(c.client.warnings().is_empty());
+}
@@ -0,0 +1,129 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this file,
+// You can obtain one at http://mozilla.org/MPL/2.0/.
+
+extern crate hglib;
+
+use crate::hglib::{
+    commit,
+    graft::{self, Graft},
+    hg,
+    replay::{Replay, Session},
+    update, HgPath, CHANGESETS_TEMPLATE,
+};
+
+mod common;
+
+const SOURCE: &str = "2222222222222222222222222222222222222222";
+
+fn tip() -> Session {
+    Session::new(&["log", "-r", "tip", "--template", "{rev}", "--"])
+}
+
+#[test]
+fn test_replay() {
+    let template = format!(
+        "{}{{get(extras, 'intermediate-source')}}\\0{{get(extras, 'source')}}\\0",
+        CHANGESETS_TEMPLATE
+    );
+    let mut r = Replay::new(vec![
+        tip().out(b"3").ret(0),
+        Session::new(&["graft", "-D", "--", "2", "3", "0"])
+            .out(b"grafting 2:222222222222 \"third\"\n")
+            .err(b"skipping ancestor revision 0:000000000000\n")
+            .err(b"skipping revision 3:333333333333 (already grafted to 1:111111111111)\n")
+            .ret(0),
+        tip().out(b"4").ret(0),
+        Session::new(&["log", "-r", "4:tip", "--template", &template, "--"])
+            .out(&common::changeset(4, "third", &["", SOURCE]))
+            .ret(0),
+        tip().out(b"4").ret(0),
+        Session::new(&["graft", "--", "3"])
+            .out(b"grafting 3:333333333333 \"fourth\"\nmerging a\n")
+            .err(b"warning: conflicts while merging a! (edit, then use 'hg resolve --mark')\n")
+            .err(b"abort: unresolved conflicts, can't continue\n")
+            .err(b"(use 'hg resolve' and 'hg graft --continue')\n")
+            .ret(255),
+        tip().out(b"4").ret(0),
+        Session::new(&["resolve", "-l"]).out(b"U a\n").ret(0),
+        tip().out(b"4").ret(0),
+        Session::new(&["graft", "--", "3"])
+            .err(b"skipping revision 3:333333333333 (already grafted to 1:111111111111)\n")
+            .ret(255),
+        tip().out(b"4").ret(0),
+        tip().out(b"4").ret(0),
+        Session::new(&["graft", "--", "5"])
+            .err(b"abort: unknown revision '5'!\n")
+            .ret(255),
+    ]);
+
+    let res = graft::Arg {
+        revs: &["2", "3", "0"],
+        currentdate: true,
+        ..Default::default()
+    }
+    .run(&mut r)
+    .unwrap();
+    assert_eq!(res.grafted.len(), 1);
+    assert_eq!(res.grafted[0].source, SOURCE);
+    assert_eq!(res.grafted[0].revision.rev, 4);
+    assert_eq!(res.grafted[0].revision.desc, "third");
+    assert_eq!(res.skipped, vec![0, 3]);
+    assert!(!res.interrupted);
+
+    assert_eq!(
+        graft::Arg {
+            revs: &["3"],
+            ..Default::default()
+        }
+        .run(&mut r)
+        .unwrap(),
+        Graft {
+            interrupted: true,
+            conflicts: vec![HgPath::from("a")],
+            ..Default::default()
+        }
+    );
+    assert_eq!(
+        graft::Arg {
+            revs: &["3"],
+            ..Default::default()
+        }
+        .run(&mut r)
+        .unwrap(),
+        Graft {
+            skipped: vec![3],
+            ..Default::default()
+        }
+    );
+    assert!(graft::Arg {
+        revs: &["5"],
+        ..Default::default()
+    }
+    .run(&mut r)
+    .is_err());
+    assert!(r.is_done());
+}
+
+#[test]
+fn test_graft() {
+    let mut c = common::TestClient::new("graft", &[]);
+    c.append("a", &["a\n"]);
+    let first = hg!(c.client, commit, message = "first", addremove = true).unwrap();
+    c.append("b", &["b\n"]);
+    let second = hg!(c.client, commit, message = "second", addremove = true).unwrap();
+    hg!(c.client, update, rev = &first.node).unwrap();
+    c.append("c", &["c\n"]);
+    hg!(c.client, commit, message = "third", addremove = true).unwrap();
+
+    let res = hg!(c.client, graft, revs = &[&second.node, &first.node]).unwrap();
+    assert_eq!(res.grafted.len(), 1);
+    assert_eq!(res.grafted[0].source, second.node);
+    assert_eq!(res.grafted[0].revision.desc, "second");
+    assert_eq!(res.skipped, vec![0]);
+    assert!(!res.interrupted);
+
+    let res = hg!(c.client, graft, revs = &[&second.node]).unwrap();
+    assert!(res.grafted.is_empty());
+    assert_eq!(res.skipped, vec![1]);
+}
@@ -41,22 +41,22 @@ fn test_basic() {
             &mut c.client,
             vec![
                 grep::GrepRes {
-                    filename: "a".to_string(),
+                    filename: "a".into(),
                     rev: Some(0),
                     line: None,
                     match_status: None,
                     user: None,
                     date: None,
-                    matched: Some("x".to_string())
+                    matched: Some("x".into())
                 },
                 grep::GrepRes {
-                    filename: "b".to_string(),
+                    filename: "b".into(),
                     rev: Some(0),
                     line: None,
                     match_status: None,
                     user: None,
                     date: None,
-                    matched: Some("xy".to_string())
+                    matched: Some("xy".into())
                 },
             ]
         ),
@@ -67,13 +67,13 @@ fn test_basic() {
         remove_rev(
             &mut c.client,
             vec![grep::GrepRes {
-                filename: "a".to_string(),
+                filename: "a".into(),
                 rev: Some(0),
                 line: None,
                 match_status: None,
                 user: None,
                 date: None,
-                matched: Some("x".to_string())
+                matched: Some("x".into())
             }]
         ),
     );
@@ -83,13 +83,13 @@ fn test_basic() {
         remove_rev(
             &mut c.client,
             vec![grep::GrepRes {
-                filename: "b".to_string(),
+                filename: "b".into(),
                 rev: Some(0),
                 line: None,
                 match_status: None,
                 user: None,
                 date: None,
-                matched: Some("xy".to_string())
+                matched: Some("xy".into())
             },]
         ),
     );
@@ -106,22 +106,22 @@ fn test_options() {
         hg!(c.client, grep, pattern = "x", all = true).unwrap(),
         vec![
             grep::GrepRes {
-                filename: "a".to_string(),
+                filename: "a".into(),
                 rev: Some(0),
                 line: None,
                 match_status: Some("+".to_string()),
                 user: None,
                 date: None,
-                matched: Some("x".to_string())
+                matched: Some("x".into())
             },
             grep::GrepRes {
-                filename: "b".to_string(),
+                filename: "b".into(),
                 rev: Some(0),
                 line: None,
                 match_status: Some("+".to_string()),
                 user: None,
                 date: None,
-                matched: Some("xy".to_string())
+                matched: Some("xy".into())
             },
         ],
     );
@@ -132,7 +132,7 @@ fn test_options() {
             &mut c.client,
             vec![
                 grep::GrepRes {
-                    filename: "a".to_string(),
+                    filename: "a".into(),
                     rev: Some(0),
                     line: None,
                     match_status: None,
@@ -141,7 +141,7 @@ fn test_options() {
                     matched: None,
                 },
                 grep::GrepRes {
-                    filename: "b".to_string(),
+                    filename: "b".into(),
                     rev: Some(0),
                     line: None,
                     match_status: None,
@@ -159,22 +159,22 @@ fn test_options() {
             &mut c.client,
             vec![
                 grep::GrepRes {
-                    filename: "a".to_string(),
+                    filename: "a".into(),
                     rev: Some(0),
                     line: Some(1),
                     match_status: None,
                     user: None,
                     date: None,
-                    matched: Some("x".to_string()),
+                    matched: Some("x".into()),
                 },
                 grep::GrepRes {
-                    filename: "b".to_string(),
+                    filename: "b".into(),
                     rev: Some(0),
                     line: Some(1),
                     match_status: None,
                     user: None,
                     date: None,
-                    matched: Some("xy".to_string())
+                    matched: Some("xy".into())
                 },
             ]
         ),
@@ -186,22 +186,22 @@ fn test_options() {
             &mut c.client,
             vec![
                 grep::GrepRes {
-                    filename: "a".to_string(),
+                    filename: "a".into(),
                     rev: Some(0),
                     line: None,
                     match_status: None,
                     user: Some("test".to_string()),
                     date: None,
-                    matched: Some("x".to_string()),
+                    matched: Some("x".into()),
                 },
                 grep::GrepRes {
-                    filename: "b".to_string(),
+                    filename: "b".into(),
                     rev: Some(0),
                     line: None,
                     match_status: None,
                     user: Some("test".to_string()),
                     date: None,
-                    matched: Some("xy".to_string())
+                    matched: Some("xy".into())
                 },
             ]
         ),
@@ -220,7 +220,7 @@ fn test_options() {
         .unwrap(),
         vec![
             grep::GrepRes {
-                filename: "a".to_string(),
+                filename: "a".into(),
                 rev: Some(0),
                 line: Some(1),
                 match_status: Some("+".to_string()),
@@ -229,7 +229,7 @@ fn test_options() {
                 matched: None,
             },
             grep::GrepRes {
-                filename: "b".to_string(),
+                filename: "b".into(),
                 rev: Some(0),
                 line: Some(1),
                 match_status: Some("+".to_string()),
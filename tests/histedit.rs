@@ -0,0 +1,173 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this file,
+// You can obtain one at http://mozilla.org/MPL/2.0/.
+
+extern crate hglib;
+
+use crate::hglib::{
+    commit, hg,
+    histedit::{self, Action, Histedit, Rule},
+    log,
+    replay::{Replay, Session},
+    HgPath,
+};
+
+mod common;
+
+fn session(args: &[&str]) -> Session {
+    let mut all = vec!["histedit"];
+    all.extend(args);
+    all.extend(&[
+        "--config",
+        "extensions.histedit=",
+        "--config",
+        "ui.editor=true",
+        "--",
+    ]);
+    Session::new(&all)
+}
+
+#[test]
+fn test_rule() {
+    let rule = Rule {
+        action: Action::Roll,
+        node: "5d205f8b35b6",
+    };
+    assert_eq!(rule.to_string(), "roll 5d205f8b35b6");
+    assert_eq!(Action::Mess.as_str(), "mess");
+}
+
+#[test]
+fn test_replay() {
+    let mut r = Replay::new(vec![
+        session(&["-c"])
+            .err(b"Editing (5d205f8b35b6), you may commit or record as needed now.\n")
+            .err(b"(hg histedit --continue to resume)\n")
+            .ret(1),
+        session(&["-c"])
+            .out(b"merging a\n")
+            .err(b"warning: conflicts while merging a! (edit, then use 'hg resolve --mark')\n")
+            .err(b"Fix up the change (pick 5d205f8b35b6)\n")
+            .err(b"(hg histedit --continue to resume)\n")
+            .ret(1),
+        Session::new(&["resolve", "-l"]).out(b"U a\n").ret(0),
+        session(&["--abort"]).ret(0),
+        session(&["-c"])
+            .err(b"abort: no histedit in progress\n")
+            .ret(255),
+    ]);
+    let cont = histedit::Arg {
+        cont: true,
+        ..Default::default()
+    };
+
+    assert_eq!(
+        cont.run(&mut r).unwrap(),
+        Histedit::Edit("5d205f8b35b6".to_string())
+    );
+    assert_eq!(
+        cont.run(&mut r).unwrap(),
+        Histedit::Conflicts {
+            action: Action::Pick,
+            node: "5d205f8b35b6".to_string(),
+            files: vec![HgPath::from("a")],
+        }
+    );
+    assert_eq!(
+        histedit::Arg {
+            abort: true,
+            ..Default::default()
+        }
+        .run(&mut r)
+        .unwrap(),
+        Histedit::Done
+    );
+    assert!(cont.run(&mut r).is_err());
+    assert!(r.is_done());
+}
+
+#[test]
+fn test_replay_plan() {
+    // The plan is sent on the input of the command
+    let mut r = Replay::new(vec![Session::new(&[
+        "histedit",
+        "--config",
+        "extensions.histedit=",
+        "--config",
+        "ui.editor=true",
+        "--commands",
+        "-",
+        "--",
+        "1",
+    ])
+    .input(4096)
+    .input(4096)
+    .out(b"saved backup bundle to /repo/.hg/strip-backup/5d205f8b35b6-histedit.hg\n")
+    .ret(0)]);
+    let plan = [
+        Rule {
+            action: Action::Pick,
+            node: "5d205f8b35b6",
+        },
+        Rule {
+            action: Action::Drop,
+            node: "0c24f9c8e8d4",
+        },
+    ];
+    assert_eq!(
+        histedit::Arg {
+            plan: &plan,
+            rev: "1",
+            ..Default::default()
+        }
+        .run(&mut r)
+        .unwrap(),
+        Histedit::Done
+    );
+    assert!(r.is_done());
+}
+
+#[test]
+fn test_histedit() {
+    let mut c = common::TestClient::new("histedit", &[]);
+    let mut nodes = Vec::new();
+    for i in 0..4 {
+        c.append(&format!("f{}", i), &["a"]);
+        let rev = hg!(
+            c.client,
+            commit,
+            message = &format!("commit {}", i),
+            addremove = true
+        )
+        .unwrap();
+        nodes.push(rev.node);
+    }
+
+    let plan = [
+        Rule {
+            action: Action::Pick,
+            node: &nodes[1],
+        },
+        Rule {
+            action: Action::Drop,
+            node: &nodes[2],
+        },
+        Rule {
+            action: Action::Edit,
+            node: &nodes[3],
+        },
+    ];
+    let res = hg!(c.client, histedit, plan = &plan, rev = &nodes[1]).unwrap();
+    assert_eq!(res, Histedit::Edit(nodes[3][..12].to_string()));
+
+    assert_eq!(
+        hg!(c.client, histedit, cont = true).unwrap(),
+        Histedit::Done
+    );
+    let descs: Vec<String> = hg!(c.client, log)
+        .unwrap()
+        .into_iter()
+        .map(|r| r.desc.to_string())
+        .collect();
+    assert_eq!(descs, vec!["commit 3", "commit 1", "commit 0"]);
+}
@@ -0,0 +1,129 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this file,
+// You can obtain one at http://mozilla.org/MPL/2.0/.
+
+extern crate hglib;
+
+use crate::hglib::{
+    bookmark, commit, hg,
+    identify::{self, Identify},
+    replay::{Replay, Session},
+    HgText,
+};
+
+mod common;
+
+const NODE: &str = "c4c0b8b8c6a8f7a2d1e3b5f6a7c8d9e0f1a2b3c4";
+const WDIR: &str = "ffffffffffffffffffffffffffffffffffffffff";
+
+fn local(rev: &str) -> Vec<&str> {
+    let mut args = vec!["identify"];
+    if !rev.is_empty() {
+        args.extend(&["-r", rev]);
+    }
+    args.extend(&[
+        "-T",
+        "{rev}\\0{node}\\0{p1rev}\\0{p1node}\\0{dirty}\\0{branch}\\0\
+         {join(tags, '\\n')}\\0{join(bookmarks, '\\n')}",
+        "--",
+    ]);
+    args
+}
+
+#[test]
+fn test_replay() {
+    let wdir = format!(
+        "2147483647\0{}\03\0{}\0+\0stable\0tip\0foo\nbar",
+        WDIR, NODE
+    );
+    let rev = format!("1\0{}\00\0{}\0\0default\0\0", NODE, WDIR);
+    let mut r = Replay::new(vec![
+        Session::new(&local("")).out(wdir.as_bytes()).ret(0),
+        Session::new(&local("1")).out(rev.as_bytes()).ret(0),
+        Session::new(&[
+            "identify",
+            "-B",
+            "-T",
+            "{id}\\0{join(bookmarks, '\\n')}",
+            "--",
+            "https://example.com/repo",
+        ])
+        .out(format!("{}\0@", NODE).as_bytes())
+        .ret(0),
+        Session::new(&local("")).out(b"garbage").ret(0),
+    ]);
+
+    let id = identify::Arg::default().run(&mut r).unwrap();
+    assert_eq!(
+        id,
+        Identify {
+            node: NODE.to_string(),
+            rev: Some(3),
+            dirty: true,
+            branch: HgText::from("stable"),
+            tags: vec![HgText::from("tip")],
+            bookmarks: vec![HgText::from("foo"), HgText::from("bar")],
+        }
+    );
+    assert_eq!(id.short(), &NODE[..12]);
+
+    let id = identify::Arg {
+        rev: "1",
+        ..Default::default()
+    }
+    .run(&mut r)
+    .unwrap();
+    assert_eq!((id.rev, id.dirty), (Some(1), false));
+    assert!(id.tags.is_empty());
+
+    assert_eq!(
+        identify::Arg {
+            source: "https://example.com/repo",
+            ..Default::default()
+        }
+        .run(&mut r)
+        .unwrap(),
+        Identify {
+            node: NODE.to_string(),
+            rev: None,
+            dirty: false,
+            branch: HgText::default(),
+            tags: Vec::new(),
+            bookmarks: vec![HgText::from("@")],
+        }
+    );
+
+    assert!(identify::Arg::default().run(&mut r).is_err());
+    assert!(r.is_done());
+}
+
+#[test]
+fn test_identify() {
+    let mut c = common::TestClient::new("identify", &[]);
+    let id = hg!(c.client, identify).unwrap();
+    assert_eq!(id.rev, Some(-1));
+    assert_eq!(id.short(), "000000000000");
+
+    c.append("a", &["a"]);
+    let rev = hg!(c.client, commit, message = "first", addremove = true).unwrap();
+    hg!(c.client, bookmark, name = "foo").unwrap();
+    c.append("a", &["b"]);
+
+    let id = hg!(c.client, identify).unwrap();
+    assert_eq!(id.node, rev.node);
+    assert_eq!(id.rev, Some(0));
+    assert!(id.dirty);
+    assert_eq!(id.branch, "default");
+    assert_eq!(id.tags, vec!["tip"]);
+    assert_eq!(id.bookmarks, vec!["foo"]);
+
+    let id = hg!(c.client, identify, rev = "0").unwrap();
+    assert_eq!(id.node, rev.node);
+    assert!(!id.dirty);
+
+    let path = c.path.to_str().unwrap().to_string();
+    let id = hg!(c.client, identify, source = &path).unwrap();
+    assert_eq!(id.node, rev.node);
+    assert_eq!(id.rev, None);
+    assert_eq!(id.bookmarks, vec!["foo"]);
+}
@@ -4,7 +4,12 @@
 
 extern crate hglib;
 
-use crate::hglib::{cat, hg, import, update};
+use crate::hglib::{
+    cat, commit, hg,
+    import::{self, Reject},
+    replay::{Replay, Session},
+    update, CHANGESETS_TEMPLATE,
+};
 
 mod common;
 
@@ -46,3 +51,105 @@ fn test_basic_file() {
     let a = hg!(c.client, cat, files = &["a"]).unwrap().unwrap();
     assert_eq!(String::from_utf8(a).unwrap(), "1\n");
 }
+
+fn tip() -> Session {
+    Session::new(&["log", "-r", "tip", "--template", "{rev}", "--"])
+}
+
+#[test]
+fn test_replay() {
+    let mut r = Replay::new(vec![
+        tip().out(b"0").ret(0),
+        Session::new(&["import", "--exact", "-"])
+            .input(4096)
+            .input(4096)
+            .err(b"applying patch from stdin\n")
+            .ret(0),
+        tip().out(b"2").ret(0),
+        Session::new(&["log", "--template", CHANGESETS_TEMPLATE, "-r", "1:tip"])
+            .out(
+                &[
+                    common::changeset(1, "first", &[]),
+                    common::changeset(2, "second", &[]),
+                ]
+                .concat(),
+            )
+            .ret(0),
+        tip().out(b"2").ret(0),
+        Session::new(&["import", "--no-commit", "--partial", "--", "a.patch"])
+            .err(b"applying a.patch\npatching file a\nHunk #2 FAILED at 3\n")
+            .err(b"1 out of 2 hunks FAILED -- saving rejects to file a.rej\n")
+            .err(b"patch applied partially\n")
+            .ret(1),
+        tip().out(b"2").ret(0),
+    ]);
+
+    let res = import::Arg {
+        input: PATCH.as_bytes(),
+        exact: true,
+        ..Default::default()
+    }
+    .run(&mut r)
+    .unwrap();
+    assert_eq!(res.changesets.len(), 2);
+    assert_eq!(res.changesets[0].desc, "first");
+    assert_eq!(res.changesets[1].rev, 2);
+    assert!(res.rejects.is_empty());
+
+    let res = import::Arg {
+        patches: &["a.patch"],
+        nocommit: true,
+        partial: true,
+        ..Default::default()
+    }
+    .run(&mut r)
+    .unwrap();
+    assert!(res.changesets.is_empty());
+    assert_eq!(
+        res.rejects,
+        vec![Reject {
+            path: "a".into(),
+            failed: 1,
+            hunks: 2,
+        }]
+    );
+    assert!(r.is_done());
+}
+
+#[test]
+fn test_input() {
+    let mut c = common::TestClient::new("import_input", &[]);
+    let res = hg!(c.client, import, input = PATCH.as_bytes(), exact = true).unwrap();
+    assert_eq!(res.changesets.len(), 1);
+    assert_eq!(
+        res.changesets[0].node,
+        "c103a3dec114d882c98382d684d8af798d09d857"
+    );
+    assert_eq!(c.read("a"), "1\n");
+}
+
+#[test]
+fn test_partial() {
+    let mut c = common::TestClient::new("import_partial", &[]);
+    c.append("a", &["2\n"]);
+    hg!(c.client, commit, message = "first", addremove = true).unwrap();
+
+    let res = hg!(
+        c.client,
+        import,
+        input = PATCH.as_bytes(),
+        partial = true,
+        message = "partial"
+    )
+    .unwrap();
+    assert_eq!(
+        res.rejects,
+        vec![Reject {
+            path: "a".into(),
+            failed: 1,
+            hunks: 1,
+        }]
+    );
+    assert_eq!(res.changesets.len(), 1);
+    assert_eq!(res.changesets[0].desc, "partial");
+}
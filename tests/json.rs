@@ -0,0 +1,325 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this file,
+// You can obtain one at http://mozilla.org/MPL/2.0/.
+
+extern crate hglib;
+
+use crate::hglib::{
+    bookmark,
+    bookmarks::{self, Bookmark, Bookmarks},
+    branches::{self, Branch},
+    commit, hg,
+    manifest::{self, File, Manifest},
+    paths::{self, Paths},
+    phase::{self, Phase},
+    replay::{Replay, Session},
+    resolve::{self, Kind, Resolve},
+    tags::{self, Tag},
+    ClientBuilder,
+};
+
+mod common;
+
+const NODE: &str = "c4c0b8b8c6a8f7a2d1e3b5f6a7c8d9e0f1a2b3c4";
+
+#[test]
+fn test_branches() {
+    let out = format!(
+        "[\n {{\n  \"active\": true,\n  \"branch\": \"my branch\",\n  \"closed\": false,\n  \"current\": true,\n  \"node\": \"{}\",\n  \"rev\": 3\n }}\n]\n",
+        NODE
+    );
+    let mut r = Replay::new(vec![Session::new(&["branches", "-T", "json", "--"])
+        .out(out.as_bytes())
+        .ret(0)])
+    .json(true);
+    assert_eq!(
+        branches::Arg::default().run(&mut r).unwrap(),
+        vec![Branch {
+            name: "my branch".to_string(),
+            rev: 3,
+            node: NODE[..12].to_string(),
+        }]
+    );
+    assert!(r.is_done());
+}
+
+#[test]
+fn test_bookmarks() {
+    let out = format!(
+        "[\n {{\n  \"active\": false,\n  \"bookmark\": \"a\",\n  \"node\": \"{0}\",\n  \"rev\": 0\n }},\n {{\n  \"active\": true,\n  \"bookmark\": \"b\",\n  \"node\": \"{0}\",\n  \"rev\": 0\n }}\n]\n",
+        NODE
+    );
+    let mut r = Replay::new(vec![
+        Session::new(&["bookmarks", "-T", "json", "--"])
+            .out(out.as_bytes())
+            .ret(0),
+        Session::new(&["bookmarks", "-T", "json", "--"])
+            .out(b"[\n]\n")
+            .ret(0),
+    ])
+    .json(true);
+    let bookmark = |name: &str| Bookmark {
+        name: name.to_string(),
+        rev: 0,
+        node: NODE[..12].to_string(),
+    };
+    assert_eq!(
+        bookmarks::Arg {}.run(&mut r).unwrap(),
+        Bookmarks {
+            bookmarks: vec![bookmark("a"), bookmark("b")],
+            current: Some(1),
+        }
+    );
+    assert_eq!(
+        bookmarks::Arg {}.run(&mut r).unwrap(),
+        Bookmarks {
+            bookmarks: Vec::new(),
+            current: None,
+        }
+    );
+    assert!(r.is_done());
+}
+
+#[test]
+fn test_tags() {
+    let out = format!(
+        "[\n {{\n  \"node\": \"{0}\",\n  \"rev\": 1,\n  \"tag\": \"tip\",\n  \"type\": \"\"\n }},\n {{\n  \"node\": \"{0}\",\n  \"rev\": 1,\n  \"tag\": \"local tag\",\n  \"type\": \"local\"\n }}\n]\n",
+        NODE
+    );
+    let mut r = Replay::new(vec![Session::new(&["tags", "-v", "-T", "json", "--"])
+        .out(out.as_bytes())
+        .ret(0)])
+    .json(true);
+    assert_eq!(
+        tags::Arg {}.run(&mut r).unwrap(),
+        vec![
+            Tag {
+                name: "tip".to_string(),
+                rev: 1,
+                node: NODE[..12].to_string(),
+                islocal: false,
+            },
+            Tag {
+                name: "local tag".to_string(),
+                rev: 1,
+                node: NODE[..12].to_string(),
+                islocal: true,
+            },
+        ]
+    );
+    assert!(r.is_done());
+}
+
+#[test]
+fn test_paths() {
+    let out = b"[\n {\n  \"name\": \"default\",\n  \"url\": \"https://example.com/repo\"\n }\n]\n";
+    let mut r = Replay::new(vec![
+        Session::new(&["paths", "-T", "json", "--"]).out(out).ret(0),
+        Session::new(&["paths", "-T", "json", "--", "default"])
+            .out(out)
+            .ret(0),
+    ])
+    .json(true);
+    match paths::Arg::default().run(&mut r).unwrap() {
+        Paths::Map(map) => {
+            assert_eq!(map.len(), 1);
+            assert_eq!(map["default"], "https://example.com/repo");
+        }
+        _ => panic!("Expected a map"),
+    }
+    match (paths::Arg { name: "default" }).run(&mut r).unwrap() {
+        Paths::Value(url) => assert_eq!(url, "https://example.com/repo"),
+        _ => panic!("Expected a value"),
+    }
+    assert!(r.is_done());
+}
+
+#[test]
+fn test_resolve() {
+    let out = b"[\n {\n  \"mergestatus\": \"R\",\n  \"path\": \"a\"\n }, \n {\n  \"mergestatus\": \"U\",\n  \"path\": \"b\"\n }\n]\n";
+    let mut r = Replay::new(vec![
+        Session::new(&["resolve", "-l", "-T", "json"])
+            .out(out)
+            .ret(0),
+        Session::new(&["resolve", "-m", "--", "b"]).ret(0),
+    ])
+    .json(true);
+    assert_eq!(
+        resolve::Arg {
+            listfiles: true,
+            ..Default::default()
+        }
+        .run(&mut r)
+        .unwrap(),
+        Some(vec![
+            Resolve {
+                kind: Kind::Resolved,
+                filename: "a".into(),
+            },
+            Resolve {
+                kind: Kind::Unresolved,
+                filename: "b".into(),
+            },
+        ])
+    );
+    assert_eq!(
+        resolve::Arg {
+            file: &["b"],
+            mark: true,
+            ..Default::default()
+        }
+        .run(&mut r)
+        .unwrap(),
+        None
+    );
+    assert!(r.is_done());
+}
+
+#[test]
+fn test_manifest() {
+    let out = format!(
+        "[\n {{\n  \"hash\": \"{0}\",\n  \"mode\": \"644\",\n  \"path\": \"a\",\n  \"type\": \"\"\n }},\n {{\n  \"hash\": \"{0}\",\n  \"mode\": \"755\",\n  \"path\": \"b\",\n  \"type\": \"*\"\n }}\n]\n",
+        NODE
+    );
+    let mut r = Replay::new(vec![
+        Session::new(&["manifest", "--debug", "-T", "json", "--"])
+            .out(out.as_bytes())
+            .ret(0),
+        Session::new(&["manifest", "--all", "--debug", "-T", "json", "--"])
+            .out(b"[\n {\n  \"path\": \"a\"\n },\n {\n  \"path\": \"b\"\n }\n]\n")
+            .ret(0),
+    ])
+    .json(true);
+    assert_eq!(
+        manifest::Arg::default().run(&mut r).unwrap(),
+        Manifest::Info(vec![
+            File {
+                node: NODE.to_string(),
+                perm: "644".to_string(),
+                symlink: false,
+                executable: false,
+                filename: "a".into(),
+            },
+            File {
+                node: NODE.to_string(),
+                perm: "755".to_string(),
+                symlink: false,
+                executable: true,
+                filename: "b".into(),
+            },
+        ])
+    );
+    assert_eq!(
+        manifest::Arg {
+            all: true,
+            ..Default::default()
+        }
+        .run(&mut r)
+        .unwrap(),
+        Manifest::All(vec!["a".into(), "b".into()])
+    );
+    assert!(r.is_done());
+}
+
+#[test]
+fn test_phase() {
+    let out = b"[\n {\n  \"phase\": \"public\",\n  \"rev\": 0\n },\n {\n  \"phase\": \"draft\",\n  \"rev\": 1\n }\n]\n";
+    let mut r = Replay::new(vec![
+        Session::new(&["log", "-r", ".", "-T", "json", "--"])
+            .out(out)
+            .ret(0),
+        Session::new(&["phase", "--public", "--", "1"]).ret(0),
+    ])
+    .json(true);
+    assert_eq!(
+        phase::Arg::default().run(&mut r).unwrap(),
+        Some(vec![
+            Phase {
+                num: 0,
+                phase: "public".to_string(),
+            },
+            Phase {
+                num: 1,
+                phase: "draft".to_string(),
+            },
+        ])
+    );
+    assert_eq!(
+        phase::Arg {
+            revs: &["1"],
+            public: true,
+            ..Default::default()
+        }
+        .run(&mut r)
+        .unwrap(),
+        None
+    );
+    assert!(r.is_done());
+}
+
+#[test]
+fn test_fallback() {
+    let mut r = Replay::new(vec![
+        Session::new(&["branches", "-T", "json", "--"])
+            .out(b"default 0:ad7ea8e3e9ff\n")
+            .ret(0),
+        Session::new(&["branches", "--"])
+            .out(b"default                        0:ad7ea8e3e9ff\n")
+            .ret(0),
+        Session::new(&["tags", "-v", "-T", "json", "--"])
+            .out(b"[\n {\n  \"tag\": \"tip\"\n }\n]\n")
+            .ret(0),
+        Session::new(&["tags", "-v", "--"])
+            .out(b"tip                                0:ad7ea8e3e9ff\n")
+            .ret(0),
+    ])
+    .json(true);
+    assert_eq!(
+        branches::Arg::default().run(&mut r).unwrap(),
+        vec![Branch {
+            name: "default".to_string(),
+            rev: 0,
+            node: "ad7ea8e3e9ff".to_string(),
+        }]
+    );
+    assert_eq!(
+        tags::Arg {}.run(&mut r).unwrap(),
+        vec![Tag {
+            name: "tip".to_string(),
+            rev: 0,
+            node: "ad7ea8e3e9ff".to_string(),
+            islocal: false,
+        }]
+    );
+    assert!(r.is_done());
+}
+
+#[test]
+fn test_same_results() {
+    let mut c = common::TestClient::new("json_same_results", &[]);
+    c.append("a", &["a"]);
+    hg!(c.client, commit, message = "first", addremove = true).unwrap();
+    hg!(c.client, bookmark, name = "foo").unwrap();
+
+    let mut json = ClientBuilder::new(&c.path).json(true).open().unwrap();
+    assert_eq!(
+        branches::Arg::default().run(&mut c.client).unwrap(),
+        branches::Arg::default().run(&mut json).unwrap()
+    );
+    assert_eq!(
+        bookmarks::Arg {}.run(&mut c.client).unwrap(),
+        bookmarks::Arg {}.run(&mut json).unwrap()
+    );
+    assert_eq!(
+        tags::Arg {}.run(&mut c.client).unwrap(),
+        tags::Arg {}.run(&mut json).unwrap()
+    );
+    assert_eq!(
+        manifest::Arg::default().run(&mut c.client).unwrap(),
+        manifest::Arg::default().run(&mut json).unwrap()
+    );
+    assert_eq!(
+        phase::Arg::default().run(&mut c.client).unwrap(),
+        phase::Arg::default().run(&mut json).unwrap()
+    );
+}
@@ -0,0 +1,124 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this file,
+// You can obtain one at http://mozilla.org/MPL/2.0/.
+
+extern crate hglib;
+
+use crate::hglib::{
+    commit, hg, log,
+    replay::{Replay, Session},
+    root, HglibError, CHANGESETS_TEMPLATE,
+};
+
+mod common;
+
+fn session() -> Session {
+    Session::new(&["log", "--template", CHANGESETS_TEMPLATE])
+}
+
+#[test]
+fn test_stream() {
+    let mut data = common::changeset(0, "first", &[]);
+    data.extend(common::changeset(1, "second", &[]));
+    data.extend(common::changeset(2, "third", &[]));
+    // The frames don't match the changesets
+    let mut r = Replay::new(vec![session()
+        .out(&data[..10])
+        .out(&data[10..150])
+        .out(&data[150..])
+        .ret(0)]);
+    let revs = log::Arg::default()
+        .stream(&mut r)
+        .unwrap()
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    assert!(r.is_done());
+    assert_eq!(revs.len(), 3);
+    assert_eq!(revs[0].desc, "first");
+    assert_eq!(revs[1].desc, "second");
+    assert_eq!(revs[2].rev, 2);
+    assert_eq!(revs[2].files, vec!["a"]);
+    // The file status isn't computed by default
+    assert!(revs[2].added.is_empty());
+}
+
+#[test]
+fn test_stream_stop() {
+    let mut r = Replay::new(vec![
+        session()
+            .out(&common::changeset(0, "first", &[]))
+            .out(&common::changeset(1, "second", &[]))
+            .out(b"garbage")
+            .ret(0),
+        Session::new(&["root", "--"]).out(b"/repo\n").ret(0),
+    ]);
+    {
+        let mut revs = log::Arg::default().stream(&mut r).unwrap();
+        assert_eq!(revs.next().unwrap().unwrap().desc, "first");
+    }
+    assert_eq!(root::Arg {}.run(&mut r).unwrap(), "/repo");
+    assert!(r.is_done());
+}
+
+#[test]
+fn test_stream_errors() {
+    let mut r = Replay::new(vec![
+        session()
+            .out(&common::changeset(0, "first", &[]))
+            .err(b"abort: foo!\n")
+            .ret(255),
+        session()
+            .out(&common::changeset(0, "first", &[])[..20])
+            .ret(0),
+    ]);
+
+    let mut revs = log::Arg::default().stream(&mut r).unwrap();
+    assert_eq!(revs.next().unwrap().unwrap().desc, "first");
+    match revs.next() {
+        Some(Err(HglibError::Abort { message, .. })) => assert_eq!(message, "foo!"),
+        x => panic!("Expected an abort: {:?}", x),
+    }
+    assert!(revs.next().is_none());
+    drop(revs);
+
+    let mut revs = log::Arg::default().stream(&mut r).unwrap();
+    match revs.next() {
+        Some(Err(HglibError::Parse { .. })) => {}
+        x => panic!("Expected a parse error: {:?}", x),
+    }
+    assert!(revs.next().is_none());
+}
+
+#[test]
+fn test_stream_client() {
+    let mut c = common::TestClient::new("log_stream_client", &[]);
+    for i in 0..20 {
+        c.append("a", &[&i.to_string()]);
+        hg!(
+            c.client,
+            commit,
+            message = &format!("commit {}", i),
+            addremove = true
+        )
+        .unwrap();
+    }
+
+    let revs = c
+        .client
+        .log_stream(log::Arg::default())
+        .unwrap()
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    assert_eq!(revs, hg!(c.client, log).unwrap());
+
+    let first = c
+        .client
+        .log_stream(log::Arg::default())
+        .unwrap()
+        .next()
+        .unwrap()
+        .unwrap();
+    assert_eq!(first.desc, "commit 19");
+    // The server must be usable after having stopped the iteration
+    assert_eq!(hg!(c.client, log).unwrap().len(), 20);
+}
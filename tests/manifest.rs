@@ -22,7 +22,7 @@ fn test_basic() {
         perm: "644".to_string(),
         symlink: false,
         executable: false,
-        filename: "a".to_string(),
+        filename: "a".into(),
     }];
 
     if cfg!(unix) {
@@ -43,7 +43,7 @@ fn test_basic() {
             perm: "755".to_string(),
             symlink: false,
             executable: true,
-            filename: "b".to_string(),
+            filename: "b".into(),
         });
 
         manifest.push(File {
@@ -51,7 +51,7 @@ fn test_basic() {
             perm: "644".to_string(),
             symlink: true,
             executable: false,
-            filename: "c".to_string(),
+            filename: "c".into(),
         });
     }
 
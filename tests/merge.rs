@@ -4,7 +4,15 @@
 
 extern crate hglib;
 
-use crate::hglib::{commit, diff, hg, merge, update};
+use crate::hglib::{
+    commit, diff, hg,
+    merge::{self, Choice, Question},
+    replay::{Replay, Session},
+    update::{self, Update},
+    Basic, HgPath, CHANGESETS_TEMPLATE,
+};
+use std::env;
+use std::fs;
 use std::str;
 
 mod common;
@@ -55,3 +63,179 @@ fn test_basic() {
 
     assert_eq!(diff, expected)
 }
+
+#[test]
+fn test_replay() {
+    let config = format!("ui.logtemplate={}", CHANGESETS_TEMPLATE);
+    let mut r = Replay::new(vec![
+        Session::new(&["merge", "-r", "1", "-y", "--"])
+            .out(b"1 files updated, 1 files merged, 0 files removed, 0 files unresolved\n")
+            .out(b"(branch merge, don't forget to commit)\n")
+            .ret(0),
+        Session::new(&["merge", "-y", "-P", "--config", &config, "--"])
+            .out(b"1\x001111111111111111111111111111111111111111\x00")
+            .out(b"0 0000000000000000000000000000000000000000 -1 0000000000000000000000000000000000000000\x00")
+            .out(b"draft\x00\x00\x00default\x00test\x00second\x000 0\x00\x00\x00a\x00\x00\x00\x00\x00")
+            .ret(0),
+    ]);
+
+    assert_eq!(
+        merge::Arg {
+            rev: "1",
+            ..Default::default()
+        }
+        .run(&mut r)
+        .unwrap(),
+        Update {
+            updated: 1,
+            merged: 1,
+            merging: true,
+            ..Default::default()
+        }
+    );
+
+    let revs = merge::Arg::default().run_preview(&mut r).unwrap();
+    assert_eq!(revs.len(), 1);
+    assert_eq!(revs[0].rev, 1);
+    assert_eq!(revs[0].desc, "second");
+    assert!(r.is_done());
+}
+
+#[test]
+fn test_preview() {
+    let mut c = common::TestClient::new("merge_preview", &[]);
+    let (node0, node1) = set_up(&mut c);
+
+    hg!(c.client, update, rev = node0.as_str()).unwrap();
+    c.append("b", &["a"]);
+    hg!(c.client, commit, message = "new file", addremove = true).unwrap();
+
+    let revs = c
+        .client
+        .merge_preview(merge::Arg {
+            rev: &node1,
+            ..Default::default()
+        })
+        .unwrap();
+    assert_eq!(revs.len(), 1);
+    assert_eq!(revs[0].node, node1);
+
+    let merge = hg!(c.client, merge, rev = &node1).unwrap();
+    assert!(merge.merging);
+    assert!(merge.conflicts.is_empty());
+}
+
+fn choice(key: char, label: &str) -> Choice {
+    Choice {
+        key,
+        label: label.to_string(),
+    }
+}
+
+#[test]
+fn test_resolver() {
+    let mut r = Replay::new(vec![Session::new(&[
+        "merge",
+        "-r",
+        "1",
+        "--config",
+        "ui.interactive=True",
+    ])
+    .out(b"local [working copy] changed a which other [merge rev] deleted\n")
+    .out(b"use (c)hanged version, (d)elete, or leave (u)nresolved? ")
+    .line(4096)
+    .out(b"merging b\n")
+    .out(b"keep (l)ocal [working copy], take (o)ther [merge rev], or leave (u)nresolved for b? ")
+    .line(4096)
+    .out(b"0 files updated, 0 files merged, 0 files removed, 1 files unresolved\n")
+    .ret(1)]);
+
+    let mut questions = Vec::new();
+    let update = merge::Arg {
+        rev: "1",
+        ..Default::default()
+    }
+    .run_with_resolver(&mut r, &mut |q: &Question| {
+        let path = q.path.clone();
+        questions.push((path, q.choices.iter().map(|c| c.key).collect::<String>()));
+        if q.choices.iter().any(|c| c.key == 'c') {
+            Some('c')
+        } else {
+            Some('u')
+        }
+    })
+    .unwrap();
+    assert!(r.is_done());
+    assert_eq!(
+        questions,
+        vec![
+            (Some(HgPath::from("a")), "cdu".to_string()),
+            (Some(HgPath::from("b")), "lou".to_string()),
+        ]
+    );
+    assert_eq!(update.unresolved, 1);
+    assert!(update.merging);
+}
+
+#[test]
+fn test_resolver_client() {
+    let mut c = common::TestClient::new("merge_resolver", &[]);
+    c.append("a", &["a"]);
+    let node0 = hg!(c.client, commit, message = "first", addremove = true)
+        .unwrap()
+        .node;
+    c.append("a", &["b"]);
+    let node1 = hg!(c.client, commit, message = "change").unwrap().node;
+    hg!(c.client, update, rev = node0.as_str()).unwrap();
+    c.rm("a");
+    hg!(c.client, commit, message = "remove", addremove = true).unwrap();
+
+    let mut questions = Vec::new();
+    let update = c
+        .client
+        .merge_with_resolver(
+            merge::Arg {
+                rev: &node1,
+                ..Default::default()
+            },
+            &mut |q: &Question| {
+                questions.push((q.path.clone(), q.choices.contains(&choice('c', "changed"))));
+                Some('c')
+            },
+        )
+        .unwrap();
+    assert_eq!(questions, vec![(Some(HgPath::from("a")), true)]);
+    assert_eq!(update.unresolved, 0);
+    assert_eq!(c.read("a"), "ab");
+}
+
+// A fake hg which fails if it gets some input
+#[cfg(unix)]
+const FAKE_HG: &str = "#!/bin/sh
+test -z \"$(cat)\" || exit 2
+echo '1 files updated, 0 files merged, 0 files removed, 0 files unresolved'
+";
+
+#[cfg(unix)]
+#[test]
+fn test_resolver_basic() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let hg = env::temp_dir().join("merge_resolver_basic.sh");
+    fs::write(&hg, FAKE_HG).unwrap();
+    fs::set_permissions(&hg, fs::Permissions::from_mode(0o755)).unwrap();
+
+    // There is no line channel so the resolver can't be called
+    let mut calls = 0;
+    let update = merge::Arg::default()
+        .run_with_resolver(&mut Basic::default().hg(&hg), &mut |_: &Question| {
+            calls += 1;
+            None
+        })
+        .unwrap();
+    assert_eq!(calls, 0);
+    assert_eq!(update.updated, 1);
+    assert!(update.merging);
+
+    assert!(fs::remove_file(&hg).is_ok());
+}
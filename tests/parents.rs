@@ -4,7 +4,7 @@
 
 extern crate hglib;
 
-use crate::hglib::{commit, hg, parents};
+use crate::hglib::{bookmark, commit, hg, parents, tip, Parent};
 
 mod common;
 
@@ -26,3 +26,34 @@ fn test_basic() {
         hg!(c.client, parents, file = "a").unwrap()[0].node
     );
 }
+
+#[test]
+fn test_details() {
+    let mut c = common::TestClient::new("parents_details", &[]);
+    c.append("a", &["a"]);
+    c.append("b", &["b"]);
+    let first = hg!(c.client, commit, message = "first", addremove = true).unwrap();
+    c.append("a", &["a"]);
+    c.append("c", &["c"]);
+    c.rm("b");
+    hg!(c.client, commit, message = "second", addremove = true).unwrap();
+    hg!(c.client, bookmark, name = "book").unwrap();
+
+    let rev = hg!(c.client, tip, filestatus = true).unwrap();
+    assert_eq!(
+        rev.parents,
+        vec![Parent {
+            rev: first.rev,
+            node: first.node,
+        }]
+    );
+    assert_eq!(rev.phase, "draft");
+    assert_eq!(rev.tags, vec!["tip"]);
+    assert_eq!(rev.bookmarks, vec!["book"]);
+    assert_eq!(rev.files, vec!["a", "b", "c"]);
+    assert_eq!(rev.added, vec!["c"]);
+    assert_eq!(rev.modified, vec!["a"]);
+    assert_eq!(rev.removed, vec!["b"]);
+    assert!(!rev.obsolete);
+    assert!(rev.instabilities.is_empty());
+}
@@ -0,0 +1,296 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this file,
+// You can obtain one at http://mozilla.org/MPL/2.0/.
+
+extern crate hglib;
+
+use crate::hglib::{
+    add, commit, diff, export, hg,
+    patch::{self, Change, FileDiff, Hunk, Line, LineKind},
+    replay::{Replay, Session},
+    Encoding,
+};
+
+mod common;
+
+fn line(kind: LineKind, content: &str) -> Line {
+    Line {
+        kind,
+        content: content.as_bytes().to_vec(),
+        eol: true,
+    }
+}
+
+#[test]
+fn test_plain() {
+    let data = b"diff -r 000000000000 -r 1e0a4b1d7c8f a
+--- /dev/null\tThu Jan 01 00:00:00 1970 +0000
++++ b/a\tThu Jan 01 00:00:00 1970 +0000
+@@ -0,0 +1,2 @@
++a
++--- b
+diff -r 000000000000 -r 1e0a4b1d7c8f my file
+--- a/my file
++++ b/my file
+@@ -1,3 +1,3 @@ fn main() {
+ foo
+-bar
++baz
+
+\\ No newline at end of file
+diff -r 000000000000 -r 1e0a4b1d7c8f c
+--- a/c
++++ /dev/null
+@@ -1 +0,0 @@
+-c
+diff -r 000000000000 -r 1e0a4b1d7c8f d.bin
+Binary file d.bin has changed
+";
+    let files = patch::parse_diff(data, &Encoding::Utf8).unwrap();
+    assert_eq!(files.len(), 4);
+    assert_eq!(
+        files[0],
+        FileDiff {
+            change: Change::Added,
+            old_path: None,
+            new_path: Some("a".into()),
+            old_mode: None,
+            new_mode: None,
+            binary: false,
+            hunks: vec![Hunk {
+                old_start: 0,
+                old_lines: 0,
+                new_start: 1,
+                new_lines: 2,
+                section: String::new(),
+                lines: vec![line(LineKind::Added, "a"), line(LineKind::Added, "--- b")],
+            }],
+        }
+    );
+
+    assert_eq!(files[1].change, Change::Modified);
+    assert_eq!(files[1].path(), "my file");
+    let hunk = &files[1].hunks[0];
+    assert_eq!(hunk.section, "fn main() {");
+    assert_eq!(
+        hunk.lines,
+        vec![
+            line(LineKind::Context, "foo"),
+            line(LineKind::Removed, "bar"),
+            line(LineKind::Added, "baz"),
+            Line {
+                kind: LineKind::Context,
+                content: Vec::new(),
+                eol: false,
+            },
+        ]
+    );
+
+    assert_eq!(files[2].change, Change::Removed);
+    assert_eq!(files[2].path(), "c");
+    assert_eq!(files[2].new_path, None);
+    assert_eq!(
+        (files[2].hunks[0].old_start, files[2].hunks[0].old_lines),
+        (1, 1)
+    );
+
+    assert!(files[3].binary);
+    assert_eq!(files[3].path(), "d.bin");
+    assert!(files[3].hunks.is_empty());
+}
+
+#[test]
+fn test_git() {
+    let data = b"diff --git a/a b/a
+old mode 100644
+new mode 100755
+diff --git a/b b/c
+rename from b
+rename to c
+--- a/b
++++ b/c
+@@ -1,1 +1,1 @@
+-b
++c
+diff --git a/d b/e
+copy from d
+copy to e
+diff --git a/f b/f
+new file mode 100644
+--- /dev/null
++++ b/f
+@@ -0,0 +1,1 @@
++f
+diff --git a/g b/g
+deleted file mode 100755
+--- a/g
++++ /dev/null
+@@ -1,1 +0,0 @@
+-g
+diff --git a/h.bin b/h.bin
+new file mode 100644
+index 0000000000000000000000000000000000000000..7ae6a5b8bb5d2e1c7a3f0c0a2f4f3b1a8a0c9e6b
+GIT binary patch
+literal 3
+Kc${NkU}OLQ00031
+
+";
+    let files = patch::parse_diff(data, &Encoding::Utf8).unwrap();
+    assert_eq!(files.len(), 6);
+
+    assert_eq!(files[0].change, Change::Modified);
+    assert_eq!(
+        (files[0].old_mode, files[0].new_mode),
+        (Some(0o100644), Some(0o100755))
+    );
+    assert!(files[0].hunks.is_empty());
+
+    assert_eq!(files[1].change, Change::Renamed);
+    assert_eq!(files[1].old_path, Some("b".into()));
+    assert_eq!(files[1].new_path, Some("c".into()));
+    assert_eq!(files[1].hunks[0].lines.len(), 2);
+
+    assert_eq!(files[2].change, Change::Copied);
+    assert_eq!(files[2].old_path, Some("d".into()));
+    assert_eq!(files[2].new_path, Some("e".into()));
+
+    assert_eq!(files[3].change, Change::Added);
+    assert_eq!(files[3].old_path, None);
+    assert_eq!(files[3].new_mode, Some(0o100644));
+
+    assert_eq!(files[4].change, Change::Removed);
+    assert_eq!(files[4].old_mode, Some(0o100755));
+    assert_eq!(files[4].path(), "g");
+
+    assert_eq!(files[5].change, Change::Added);
+    assert!(files[5].binary);
+    assert_eq!(files[5].path(), "h.bin");
+}
+
+#[test]
+fn test_invalid() {
+    assert!(patch::parse_diff(
+        b"diff -r 000000000000 a\n--- a/a\n+++ b/a\n@@ -1,2 +1,2 @@\n-a\n+b\n",
+        &Encoding::Utf8
+    )
+    .is_err());
+    assert!(patch::parse_diff(
+        b"diff -r 000000000000 a\n--- a/a\n+++ b/a\n@@ -1,1 +1,1 @@\n*a\n",
+        &Encoding::Utf8
+    )
+    .is_err());
+    assert!(patch::parse_diff(b"", &Encoding::Utf8).unwrap().is_empty());
+}
+
+#[test]
+fn test_export_bytes() {
+    let out = b"# HG changeset patch
+# User Andr\xe9
+# Date 0 0
+# Branch caf\xe9
+# Node ID ad7ea8e3e9ffc3ab46ce0edf27b3d2c0e8fdb8dd
+# Parent  0000000000000000000000000000000000000000
+d\xe9j\xe0 vu
+";
+    let patches = patch::parse_export(out, &Encoding::Utf8).unwrap();
+    assert_eq!(patches.len(), 1);
+    assert_eq!(patches[0].user.as_bytes(), b"Andr\xe9");
+    assert_eq!(patches[0].branch.as_bytes(), b"caf\xe9");
+    assert_eq!(patches[0].message.as_bytes(), b"d\xe9j\xe0 vu");
+    assert_eq!(patches[0].user.decode_lossy(&Encoding::Latin1), "André");
+}
+
+#[test]
+fn test_export() {
+    let out = b"# HG changeset patch
+# User Foo Bar <foo@bar.com>
+# Date 1600000000 -7200
+#      Sun Sep 13 14:26:40 2020 +0200
+# Branch stable
+# Node ID c4c0b8b8c6a8f7a2d1e3b5f6a7c8d9e0f1a2b3c4
+# Parent  ad7ea8e3e9ffc3ab46ce0edf27b3d2c0e8fdb8dd
+# Parent  0000000000000000000000000000000000000000
+Fix the bug
+
+The details.
+
+diff -r ad7ea8e3e9ff -r c4c0b8b8c6a8 a
+--- a/a\tSun Sep 13 14:26:40 2020 +0200
++++ b/a\tSun Sep 13 14:26:40 2020 +0200
+@@ -1,1 +1,1 @@
+-a
++b
+# HG changeset patch
+# User test
+# Date 0 0
+#      Thu Jan 01 00:00:00 1970 +0000
+# Node ID ad7ea8e3e9ffc3ab46ce0edf27b3d2c0e8fdb8dd
+# Parent  2222222222222222222222222222222222222222
+# Parent  1111111111111111111111111111111111111111
+merge
+
+";
+    let mut r = Replay::new(vec![Session::new(&["export", "--", "1", "0"])
+        .out(out)
+        .ret(0)]);
+    let patches = export::Arg {
+        revs: &["1", "0"],
+        ..Default::default()
+    }
+    .run_parsed(&mut r)
+    .unwrap();
+    assert!(r.is_done());
+
+    assert_eq!(patches.len(), 2);
+    let p = &patches[0];
+    assert_eq!(p.user, "Foo Bar <foo@bar.com>");
+    assert_eq!(p.date.timestamp(), 1_600_000_000);
+    assert_eq!(p.date.offset().local_minus_utc(), 7200);
+    assert_eq!(p.branch, "stable");
+    assert_eq!(p.node, "c4c0b8b8c6a8f7a2d1e3b5f6a7c8d9e0f1a2b3c4");
+    assert_eq!(p.parents, vec!["ad7ea8e3e9ffc3ab46ce0edf27b3d2c0e8fdb8dd"]);
+    assert_eq!(p.message, "Fix the bug\n\nThe details.");
+    assert_eq!(p.files.len(), 1);
+    assert_eq!(p.files[0].path(), "a");
+
+    let p = &patches[1];
+    assert_eq!(p.branch, "default");
+    assert_eq!(p.parents.len(), 2);
+    assert_eq!(p.message, "merge");
+    assert!(p.files.is_empty());
+}
+
+#[test]
+fn test_diff_parsed() {
+    let mut c = common::TestClient::new("patch_diff_parsed", &[]);
+    c.append("a", &["a\n"]);
+    hg!(c.client, add, files = &["a"]).unwrap();
+    let rev = hg!(c.client, commit, message = "first").unwrap();
+    c.append("a", &["b\n"]);
+
+    let files = c
+        .client
+        .diff_parsed(diff::Arg {
+            git: true,
+            ..Default::default()
+        })
+        .unwrap();
+    assert_eq!(files.len(), 1);
+    assert_eq!(files[0].change, Change::Modified);
+    assert_eq!(
+        files[0].hunks[0].lines,
+        vec![line(LineKind::Context, "a"), line(LineKind::Added, "b")]
+    );
+
+    let patches = c
+        .client
+        .export_parsed(export::Arg {
+            revs: &[&rev.node],
+            ..Default::default()
+        })
+        .unwrap();
+    assert_eq!(patches.len(), 1);
+    assert_eq!(patches[0].node, rev.node);
+    assert_eq!(patches[0].message, "first");
+    assert_eq!(patches[0].files[0].change, Change::Added);
+}
@@ -0,0 +1,179 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this file,
+// You can obtain one at http://mozilla.org/MPL/2.0/.
+
+extern crate hglib;
+
+use crate::hglib::{
+    annotate,
+    common::CHANGESETS_FILES_TEMPLATE,
+    grep, log,
+    manifest::{self, Manifest},
+    replay::{Replay, Session},
+    status::{self, Code, Status},
+    Encoding, HgPath, HgText,
+};
+
+mod common;
+
+#[test]
+fn test_hgpath() {
+    let path = HgPath::from(&b"dir/caf\xe9"[..]);
+    assert_eq!(path.as_bytes(), b"dir/caf\xe9");
+    assert_eq!(path.to_str(), None);
+    assert_eq!(path.to_string_lossy(), "dir/caf\u{fffd}");
+    assert_eq!(path.to_string(), "dir/caf\u{fffd}");
+    assert_eq!(path.decode(&hglib::Encoding::Latin1).unwrap(), "dir/café");
+    assert_eq!(HgPath::from("a/b"), "a/b");
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::ffi::OsStrExt;
+        assert_eq!(path.to_path_buf().as_os_str().as_bytes(), b"dir/caf\xe9");
+    }
+}
+
+#[test]
+fn test_non_utf8_filenames() {
+    let mut r = Replay::new(vec![
+        Session::new(&["status", "--print0", "--"])
+            .out(b"M caf\xe9\0A b\0")
+            .ret(0),
+        Session::new(&["manifest", "--all", "--debug", "--"])
+            .out(b"caf\xe9\nb\n")
+            .ret(0),
+    ]);
+    assert_eq!(
+        status::Arg::default().run(&mut r).unwrap(),
+        vec![
+            Status {
+                code: Code::Modified,
+                filename: HgPath::from(&b"caf\xe9"[..]),
+            },
+            Status {
+                code: Code::Added,
+                filename: "b".into(),
+            },
+        ]
+    );
+    assert_eq!(
+        manifest::Arg {
+            all: true,
+            ..Default::default()
+        }
+        .run(&mut r)
+        .unwrap(),
+        Manifest::All(vec![HgPath::from(&b"caf\xe9"[..]), "b".into()])
+    );
+    assert!(r.is_done());
+}
+
+#[test]
+fn test_non_utf8_text() {
+    let mut r = Replay::new(vec![
+        Session::new(&["grep", "--print0", "--", "foo"])
+            .out(b"a\0caf\xe9 foo\0")
+            .ret(0),
+        Session::new(&["version", "-q", "--"])
+            .out(b"Mercurial Distributed SCM (version 5.9.1)\n")
+            .ret(0),
+        Session::new(&["annotate", "-u", "--", "a"])
+            .out(b"caf\xe9: foo\n")
+            .ret(0),
+    ]);
+    let res = grep::Arg {
+        pattern: "foo",
+        ..Default::default()
+    }
+    .run(&mut r)
+    .unwrap();
+    assert_eq!(res[0].matched, Some(HgText::from(&b"caf\xe9 foo"[..])));
+
+    let mut lines = annotate::Arg {
+        files: &["a"],
+        user: true,
+        ..Default::default()
+    }
+    .run(&mut r)
+    .unwrap();
+    let line = lines.next_line().unwrap().unwrap();
+    assert_eq!(line.info, b"caf\xe9");
+    assert_eq!(line.info_lossy(&Encoding::Latin1), "café");
+    assert_eq!(line.content, b"foo");
+    assert!(r.is_done());
+}
+
+#[test]
+fn test_log_legacy_data() {
+    let mut r = Replay::new(vec![Session::new(&[
+        "log",
+        "--template",
+        CHANGESETS_FILES_TEMPLATE,
+        "-C",
+    ])
+    .out(&common::changeset_data(&[
+        b"1",
+        b"c4c0b8b8c6a8f7a2d1e3b5f6a7c8d9e0f1a2b3c4",
+        b"0 ad7ea8e3e9ffc3ab46ce0edf27b3d2c0e8fdb8dd -1 0000000000000000000000000000000000000000",
+        b"draft",
+        b"tip",
+        b"f\xf6\xf6",
+        b"default",
+        b"test",
+        b"caf\xe9",
+        b"1600000000 0",
+        b"",
+        b"",
+        b"b\nd\xe9",
+        b"b\nd\xe9",
+        b"",
+        b"",
+        b"b\na\nd\xe9\nc\n",
+    ]))
+    .out(&common::changeset_data(&[
+        b"0",
+        b"ad7ea8e3e9ffc3ab46ce0edf27b3d2c0e8fdb8dd",
+        b"-1 0000000000000000000000000000000000000000 -1 0000000000000000000000000000000000000000",
+        b"public",
+        b"",
+        b"",
+        b"default",
+        b"test",
+        b"first",
+        b"1500000000 0",
+        b"",
+        b"",
+        b"a\nc",
+        b"a\nc",
+        b"",
+        b"",
+        b"",
+    ]))
+    .ret(0)]);
+    let revs = log::Arg {
+        copies: true,
+        filestatus: true,
+        ..Default::default()
+    }
+    .run(&mut r)
+    .unwrap();
+    assert_eq!(revs.len(), 2);
+    // The description isn't valid UTF-8: the bytes are preserved
+    assert_eq!(revs[0].desc.as_bytes(), b"caf\xe9");
+    assert_eq!(revs[0].desc.to_str(), None);
+    assert_eq!(revs[0].desc.decode_lossy(&Encoding::Utf8), "caf\u{fffd}");
+    assert_eq!(revs[0].desc.decode(&Encoding::Latin1).unwrap(), "café");
+    assert_eq!(revs[0].bookmarks, vec![HgText::from(&b"f\xf6\xf6"[..])]);
+    assert_eq!(
+        revs[0].copies,
+        vec![
+            ("b".into(), "a".into()),
+            (HgPath::from(&b"d\xe9"[..]), "c".into())
+        ]
+    );
+    assert_eq!(revs[0].files, vec!["b".into(), HgPath::from(&b"d\xe9"[..])]);
+    assert_eq!(revs[0].added, revs[0].files);
+    assert_eq!(revs[1].rev, 0);
+    assert_eq!(revs[1].desc, "first");
+    assert!(revs[1].copies.is_empty());
+}
@@ -45,6 +45,6 @@ fn test_unresolved() {
     assert!(!hg!(other, pull, update = true).unwrap());
     assert!(hg!(other, status).unwrap().contains(&Status {
         code: Code::Modified,
-        filename: "a".to_string(),
+        filename: "a".into(),
     }));
 }
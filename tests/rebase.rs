@@ -0,0 +1,126 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this file,
+// You can obtain one at http://mozilla.org/MPL/2.0/.
+
+extern crate hglib;
+
+use crate::hglib::{
+    commit, hg, log,
+    rebase::{self, Rebase},
+    replay::{Replay, Session},
+    update, HgPath, HglibError,
+};
+
+mod common;
+
+fn session(args: &[&str]) -> Session {
+    let mut all = vec!["rebase"];
+    all.extend(args);
+    all.extend(&["--config", "extensions.rebase=", "--"]);
+    Session::new(&all)
+}
+
+#[test]
+fn test_replay() {
+    let mut r = Replay::new(vec![
+        session(&["-s", "2", "-d", "1"])
+            .out(b"rebasing 2:4f8b8a3c1d2e \"third\"\n")
+            .ret(0),
+        session(&["-d", "1"]).out(b"nothing to rebase\n").ret(1),
+        session(&["-r", "2", "-r", "3", "-d", "1", "--keep"])
+            .out(b"rebasing 2:4f8b8a3c1d2e \"third\"\nmerging a\n")
+            .err(b"warning: conflicts while merging a! (edit, then use 'hg resolve --mark')\n")
+            .err(b"unresolved conflicts (see 'hg resolve', then 'hg rebase --continue')\n")
+            .ret(1),
+        Session::new(&["resolve", "-l"]).out(b"U a\nR b\n").ret(0),
+        session(&["--continue"])
+            .err(b"abort: no rebase in progress\n")
+            .ret(255),
+    ]);
+
+    assert_eq!(
+        rebase::Arg {
+            source: &["2"],
+            dest: "1",
+            ..Default::default()
+        }
+        .run(&mut r)
+        .unwrap(),
+        Rebase::Rebased
+    );
+    assert_eq!(
+        rebase::Arg {
+            dest: "1",
+            ..Default::default()
+        }
+        .run(&mut r)
+        .unwrap(),
+        Rebase::NothingToRebase
+    );
+    assert_eq!(
+        rebase::Arg {
+            rev: &["2", "3"],
+            dest: "1",
+            keep: true,
+            ..Default::default()
+        }
+        .run(&mut r)
+        .unwrap(),
+        Rebase::Conflicts(vec![HgPath::from("a")])
+    );
+    match (rebase::Arg {
+        cont: true,
+        ..Default::default()
+    })
+    .run(&mut r)
+    {
+        Err(HglibError::Abort { message, .. }) => assert_eq!(message, "no rebase in progress"),
+        x => panic!("Expected an abort: {:?}", x),
+    }
+    assert!(r.is_done());
+}
+
+#[test]
+fn test_rebase() {
+    let mut c = common::TestClient::new("rebase", &[]);
+    c.append("a", &["a\n"]);
+    let first = hg!(c.client, commit, message = "first", addremove = true).unwrap();
+    c.append("a", &["b\n"]);
+    hg!(c.client, commit, message = "second").unwrap();
+    hg!(c.client, update, rev = &first.node).unwrap();
+    c.append("b", &["b\n"]);
+    let third = hg!(c.client, commit, message = "third", addremove = true).unwrap();
+
+    assert_eq!(
+        hg!(c.client, rebase, source = &[&third.node], dest = "1").unwrap(),
+        Rebase::Rebased
+    );
+    let revs = hg!(c.client, log, revrange = &["desc(third)"]).unwrap();
+    assert_eq!(revs[0].parents[0].rev, 1);
+
+    assert_eq!(
+        hg!(c.client, rebase, dest = "1").unwrap(),
+        Rebase::NothingToRebase
+    );
+}
+
+#[test]
+fn test_conflicts() {
+    let mut c = common::TestClient::new("rebase_conflicts", &[]);
+    c.append("a", &["a\n"]);
+    let first = hg!(c.client, commit, message = "first", addremove = true).unwrap();
+    c.append("a", &["b\n"]);
+    hg!(c.client, commit, message = "second").unwrap();
+    hg!(c.client, update, rev = &first.node).unwrap();
+    c.append("a", &["c\n"]);
+    let third = hg!(c.client, commit, message = "third").unwrap();
+
+    assert_eq!(
+        hg!(c.client, rebase, source = &[&third.node], dest = "1").unwrap(),
+        Rebase::Conflicts(vec![HgPath::from("a")])
+    );
+    assert_eq!(
+        hg!(c.client, rebase, abort = true).unwrap(),
+        Rebase::Rebased
+    );
+}
@@ -0,0 +1,236 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this file,
+// You can obtain one at http://mozilla.org/MPL/2.0/.
+
+extern crate hglib;
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use crate::hglib::{
+    config::{self, Config},
+    grep::{self, GrepRes},
+    hg,
+    incoming::{self, Incoming},
+    replay::{self, Frame, Replay, Session},
+    summary::{self, Mq, Remote, Revision, Summary},
+    HglibError, Parent, Runner,
+};
+
+mod common;
+
+fn fixture(name: &str) -> Replay {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("fixtures")
+        .join(name);
+    Replay::open(path).unwrap()
+}
+
+#[test]
+fn test_summary() {
+    let mut r = fixture("summary_remote.replay");
+    let summary = summary::Arg { remote: true }.run(&mut r).unwrap();
+    assert!(r.is_done());
+    assert_eq!(
+        summary,
+        Summary {
+            parent: vec![Revision {
+                rev: 1,
+                node: "a1b2c3d4e5f6".to_string(),
+                tags: "tip".to_string(),
+                message: "second".to_string(),
+            }],
+            branch: "default".to_string(),
+            commit: true,
+            update: 0,
+            remote: Some(Remote {
+                outgoing: 2,
+                incoming: 1,
+                outgoing_bookmarks: 0,
+                incoming_bookmarks: 0,
+            }),
+            mq: Mq::default(),
+            others: {
+                let mut map = HashMap::default();
+                map.insert("phases".to_string(), "2 draft".to_string());
+                map
+            },
+        }
+    );
+}
+
+#[test]
+fn test_grep() {
+    let mut r = fixture("grep.replay");
+    let res = grep::Arg {
+        pattern: "foo",
+        ..Default::default()
+    }
+    .run(&mut r)
+    .unwrap();
+    assert!(r.is_done());
+    assert_eq!(
+        res,
+        vec![
+            GrepRes {
+                filename: "a".into(),
+                rev: None,
+                line: None,
+                match_status: None,
+                user: None,
+                date: None,
+                matched: Some("foo bar".into()),
+            },
+            GrepRes {
+                filename: "b".into(),
+                rev: None,
+                line: None,
+                match_status: None,
+                user: None,
+                date: None,
+                matched: Some("foo".into()),
+            },
+        ]
+    );
+}
+
+#[test]
+fn test_config_show_source() {
+    let mut r = fixture("config_showsource.replay");
+    let config = config::Arg {
+        showsource: true,
+        ..Default::default()
+    }
+    .run(&mut r)
+    .unwrap();
+    assert_eq!(
+        config,
+        vec![
+            Config {
+                source: Some("/repo/.hg/hgrc:2".to_string()),
+                section: "section".to_string(),
+                key: "key".to_string(),
+                value: "value".to_string(),
+            },
+            Config {
+                source: Some("--config".to_string()),
+                section: "ui".to_string(),
+                key: "foo".to_string(),
+                value: "bar".to_string(),
+            },
+        ]
+    );
+}
+
+#[test]
+fn test_incoming() {
+    let mut r = fixture("incoming.replay");
+    let incoming = incoming::Arg {
+        filestatus: true,
+        ..Default::default()
+    }
+    .run(&mut r)
+    .unwrap();
+    if let Incoming::Revisions(revs) = incoming {
+        assert_eq!(revs.len(), 1);
+        assert_eq!(revs[0].rev, 1);
+        assert_eq!(revs[0].node, "c4c0b8b8c6a8f7a2d1e3b5f6a7c8d9e0f1a2b3c4");
+        assert_eq!(revs[0].desc, "second");
+        assert_eq!(revs[0].date.timestamp(), 1_600_000_000);
+        assert_eq!(revs[0].date.offset().local_minus_utc(), 7200);
+        assert_eq!(
+            revs[0].parents,
+            vec![Parent {
+                rev: 0,
+                node: "ad7ea8e3e9ffc3ab46ce0edf27b3d2c0e8fdb8dd".to_string(),
+            }]
+        );
+        assert_eq!(revs[0].phase, "draft");
+        assert_eq!(revs[0].tags, vec!["tip"]);
+        assert!(revs[0].bookmarks.is_empty());
+        assert!(!revs[0].obsolete);
+        assert_eq!(revs[0].files, vec!["a", "b"]);
+        assert_eq!(revs[0].added, vec!["b"]);
+        assert_eq!(revs[0].modified, vec!["a"]);
+        assert!(revs[0].removed.is_empty());
+    } else {
+        panic!("Expected some revisions");
+    }
+
+    let incoming = incoming::Arg {
+        filestatus: true,
+        ..Default::default()
+    }
+    .run(&mut r)
+    .unwrap();
+    assert_eq!(incoming, Incoming::Empty);
+    assert!(r.is_done());
+}
+
+#[test]
+fn test_scripted() {
+    let mut r = Replay::new(vec![
+        Session::new(&["root", "--"]).out(b"/repo\n").ret(0),
+        Session::new(&["update", "-r", "foo"])
+            .err(b"abort: unknown revision 'foo'!\n")
+            .ret(255),
+        Session::new(&["import", "-"]).line(4096).ret(0),
+    ]);
+
+    assert_eq!(
+        hglib::root::Arg {}.run(&mut r).unwrap(),
+        "/repo".to_string()
+    );
+
+    assert!(r.runcommand(&["status"], None).is_err());
+
+    let err = r.runcommand(&["update", "-r", "foo"], None).unwrap_err();
+    assert_eq!(err.code(), 255);
+    match err {
+        HglibError::Abort { message, .. } => assert_eq!(message, "unknown revision 'foo'!"),
+        _ => panic!("Expected an abort"),
+    }
+
+    match r.runcommand(&["import", "-"], None).unwrap_err() {
+        HglibError::PromptRequired(args) => assert_eq!(args, vec!["import", "-"]),
+        _ => panic!("Expected a prompt error"),
+    }
+    assert!(r.is_done());
+}
+
+#[test]
+fn test_write_read() {
+    let sessions = vec![
+        Session::new(&["log", "--template", "{rev}\\0"])
+            .out(b"0\x001\x00")
+            .err(b"warning\n")
+            .ret(0),
+        Session {
+            args: vec!["import".to_string(), "-".to_string()],
+            frames: vec![Frame::Line(4096), Frame::Input(4096), Frame::Result(1)],
+        },
+    ];
+    let mut data = Vec::new();
+    replay::write_sessions(&mut data, &sessions).unwrap();
+    assert_eq!(replay::read_sessions(&data).unwrap(), sessions);
+
+    assert!(replay::read_sessions(b"o 3\nabc\n").is_err());
+    assert!(replay::read_sessions(b"command 6\nstatus\no 10\nabc\n").is_err());
+}
+
+#[test]
+fn test_record() {
+    let mut c = common::TestClient::new("replay_record", &[]);
+    c.append("a", &["a"]);
+    c.client.start_recording();
+    let summary = hg!(c.client, summary).unwrap();
+    let sessions = c.client.stop_recording();
+    assert_eq!(sessions.len(), 1);
+
+    let path = c.get_path("summary.replay");
+    replay::save(&path, &sessions).unwrap();
+    let mut r = Replay::open(&path).unwrap();
+    assert_eq!(summary::Arg::default().run(&mut r).unwrap(), summary);
+    assert!(r.is_done());
+}
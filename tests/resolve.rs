@@ -4,7 +4,7 @@
 
 extern crate hglib;
 
-use crate::hglib::{commit, hg, merge, resolve, update};
+use crate::hglib::{commit, hg, merge, resolve, update, HgPath};
 
 mod common;
 
@@ -27,7 +27,9 @@ fn test_basic() {
     c.append("b", &["a"]);
     assert!(hg!(c.client, commit, message = "thrid").is_ok());
 
-    assert!(hg!(c.client, merge, rev = &node1).is_err());
+    let merge = hg!(c.client, merge, rev = &node1).unwrap();
+    assert_eq!(merge.unresolved, 2);
+    assert_eq!(merge.conflicts, vec![HgPath::from("a"), HgPath::from("b")]);
     assert!(hg!(c.client, resolve, all = true).is_err());
 
     assert_eq!(
@@ -35,11 +37,11 @@ fn test_basic() {
         Some(vec![
             resolve::Resolve {
                 kind: resolve::Kind::Unresolved,
-                filename: "a".to_string(),
+                filename: "a".into(),
             },
             resolve::Resolve {
                 kind: resolve::Kind::Unresolved,
-                filename: "b".to_string(),
+                filename: "b".into(),
             },
         ])
     );
@@ -50,11 +52,11 @@ fn test_basic() {
         Some(vec![
             resolve::Resolve {
                 kind: resolve::Kind::Resolved,
-                filename: "a".to_string(),
+                filename: "a".into(),
             },
             resolve::Resolve {
                 kind: resolve::Kind::Unresolved,
-                filename: "b".to_string(),
+                filename: "b".into(),
             },
         ])
     );
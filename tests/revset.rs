@@ -0,0 +1,82 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this file,
+// You can obtain one at http://mozilla.org/MPL/2.0/.
+
+extern crate hglib;
+
+use crate::hglib::{branch, commit, hg, log, revset, Revset};
+
+mod common;
+
+#[test]
+fn test_quote() {
+    assert_eq!(revset::quote("foo"), "'foo'");
+    assert_eq!(revset::quote("it's"), "'it\\'s'");
+    assert_eq!(revset::quote("a\\b"), "'a\\\\b'");
+    assert_eq!(revset::quote("a\nb"), "'a\\nb'");
+}
+
+#[test]
+fn test_render() {
+    assert_eq!(
+        &*Revset::branch("default").and(Revset::draft()),
+        "(branch('default') and draft())"
+    );
+    assert_eq!(
+        &*(Revset::rev(1).dag_range(&Revset::tip()) - Revset::merge()),
+        "((1::tip) - merge())"
+    );
+    assert_eq!(
+        &*(!Revset::public() | Revset::bookmark("")),
+        "((not public()) or bookmark())"
+    );
+    // :: binds more tightly than not
+    assert_eq!(
+        &*(!Revset::id("a")).dag_range(&Revset::id("b")),
+        "((not 'a')::'b')"
+    );
+    assert_eq!(
+        &*Revset::id("feature (wip)")
+            .ancestors()
+            .only(&Revset::id("default")),
+        "only(ancestors('feature (wip)'), 'default')"
+    );
+    assert_eq!(
+        &*Revset::file("path:a b/c").sort(&["-date", "user"]).limit(5),
+        "limit(sort(file('path:a b/c'), '-date user'), 5)"
+    );
+    assert_eq!(
+        &*Revset::date_range("2020-01-01", "2020-12-31"),
+        "date('2020-01-01 to 2020-12-31')"
+    );
+    assert_eq!(&*Revset::from(3).range(&Revset::from("tip")), "(3:'tip')");
+    assert_eq!(Revset::keyword("it's").to_string(), "keyword('it\\'s')");
+}
+
+#[test]
+fn test_log() {
+    let mut c = common::TestClient::new("revset_log", &[]);
+    c.append("a", &["a"]);
+    hg!(c.client, commit, message = "first", addremove = true).unwrap();
+    hg!(c.client, branch, name = "it's (a) branch").unwrap();
+    c.append("a", &["a"]);
+    hg!(c.client, commit, message = "second").unwrap();
+
+    let revs = hg!(
+        c.client,
+        log,
+        revrange = &[&Revset::branch("it's (a) branch")]
+    )
+    .unwrap();
+    assert_eq!(revs.len(), 1);
+    assert_eq!(revs[0].desc, "second");
+
+    let revs = hg!(
+        c.client,
+        log,
+        revrange = &[&(Revset::all() - Revset::id("it's (a) branch"))]
+    )
+    .unwrap();
+    assert_eq!(revs.len(), 1);
+    assert_eq!(revs[0].desc, "first");
+}
@@ -0,0 +1,95 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this file,
+// You can obtain one at http://mozilla.org/MPL/2.0/.
+
+extern crate hglib;
+
+use crate::hglib::{
+    branches::{self, Branch},
+    status::{self, Code, Status},
+    HglibError, Prompt, Runner,
+};
+
+struct FakeRunner {
+    args: Vec<String>,
+    out: &'static [u8],
+}
+
+impl FakeRunner {
+    fn new(out: &'static [u8]) -> Self {
+        Self {
+            args: Vec::new(),
+            out,
+        }
+    }
+}
+
+impl Runner for FakeRunner {
+    fn runcommand<'a>(
+        &mut self,
+        args: &'a [&str],
+        _: Option<Box<dyn Prompt + 'a>>,
+    ) -> Result<(Vec<u8>, i32), HglibError> {
+        self.args = args.iter().map(|a| a.to_string()).collect();
+        Ok((self.out.to_vec(), 0))
+    }
+}
+
+#[test]
+fn test_fake_branches() {
+    let mut runner = FakeRunner::new(
+        b"foo                            1:0a0c8ba1ee3cb05ccb6d2d4ae2eb1b6c6e0a6e1c\n\
+          default                        0:ad7ea8e3e9ffc3ab46ce0edf27b3d2c0e8fdb8dd (inactive)\n",
+    );
+    let branches = branches::Arg {
+        ..Default::default()
+    }
+    .run(&mut runner)
+    .unwrap();
+
+    assert_eq!(runner.args, vec!["branches", "--"]);
+    assert_eq!(
+        branches,
+        vec![
+            Branch {
+                name: "foo".to_string(),
+                rev: 1,
+                node: "0a0c8ba1ee3cb05ccb6d2d4ae2eb1b6c6e0a6e1c".to_string(),
+            },
+            Branch {
+                name: "default".to_string(),
+                rev: 0,
+                node: "ad7ea8e3e9ffc3ab46ce0edf27b3d2c0e8fdb8dd".to_string(),
+            },
+        ]
+    );
+}
+
+#[test]
+fn test_fake_status() {
+    let mut runner = FakeRunner::new(b"M a\0A b\0? c\0");
+    let status = status::Arg {
+        ..Default::default()
+    }
+    .run(&mut runner)
+    .unwrap();
+
+    assert_eq!(runner.args, vec!["status", "--print0", "--"]);
+    assert_eq!(
+        status,
+        vec![
+            Status {
+                code: Code::Modified,
+                filename: "a".into(),
+            },
+            Status {
+                code: Code::Added,
+                filename: "b".into(),
+            },
+            Status {
+                code: Code::NotTracked,
+                filename: "c".into(),
+            },
+        ]
+    );
+}
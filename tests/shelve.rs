@@ -0,0 +1,152 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this file,
+// You can obtain one at http://mozilla.org/MPL/2.0/.
+
+extern crate hglib;
+
+use crate::hglib::{
+    commit,
+    diff::FileStat,
+    hg,
+    replay::{Replay, Session},
+    shelve::{self, Shelf},
+    unshelve::{self, Unshelve},
+    HgPath,
+};
+
+mod common;
+
+fn session(command: &str, args: &[&str], files: &[&str]) -> Session {
+    let mut all = vec![command];
+    all.extend(args);
+    all.extend(&["--config", "extensions.shelve="]);
+    if !files.is_empty() {
+        all.push("--");
+        all.extend(files.iter().filter(|f| !f.is_empty()));
+    }
+    Session::new(&all)
+}
+
+#[test]
+fn test_replay() {
+    let mut r = Replay::new(vec![
+        session("shelve", &["-n", "wip", "-u"], &["a"])
+            .out(b"shelved as wip\n0 files updated, 0 files merged, 1 files removed, 0 files unresolved\n")
+            .ret(0),
+        session("shelve", &[], &[]).out(b"nothing changed\n").ret(1),
+        session("shelve", &["-l"], &[])
+            .out(b"wip             (5m ago)    changes to: first\n")
+            .out(b"a-very-long-shelf-name(2h ago)    fix (the) bug\n")
+            .ret(0),
+        session("shelve", &["-l"], &["wip"])
+            .out(b"wip             (5m ago)    changes to: first\n")
+            .ret(0),
+        session("shelve", &["-p"], &["wip"])
+            .out(b"wip             (5m ago)    changes to: first\n\n")
+            .out(b"diff --git a/a b/a\n--- a/a\n+++ b/a\n@@ -1,1 +1,2 @@\n-a\n+b\n+c\n")
+            .ret(0),
+        session("unshelve", &["-k"], &["wip"])
+            .err(b"warning: conflicts while merging a! (edit, then use 'hg resolve --mark')\n")
+            .err(b"unresolved conflicts (see 'hg resolve', then 'hg unshelve --continue')\n")
+            .ret(1),
+        Session::new(&["resolve", "-l"]).out(b"U a\n").ret(0),
+        session("unshelve", &["-a"], &[""]).ret(0),
+    ]);
+
+    assert_eq!(
+        shelve::Arg {
+            files: &["a"],
+            name: "wip",
+            unknown: true,
+            ..Default::default()
+        }
+        .run(&mut r)
+        .unwrap(),
+        Some("wip".to_string())
+    );
+    assert_eq!(shelve::Arg::default().run(&mut r).unwrap(), None);
+
+    assert_eq!(
+        shelve::ListArg::default().run(&mut r).unwrap(),
+        vec![
+            Shelf {
+                name: "wip".to_string(),
+                age: "5m ago".to_string(),
+                description: "changes to: first".to_string(),
+                files: Vec::new(),
+            },
+            Shelf {
+                name: "a-very-long-shelf-name".to_string(),
+                age: "2h ago".to_string(),
+                description: "fix (the) bug".to_string(),
+                files: Vec::new(),
+            },
+        ]
+    );
+    let shelves = shelve::ListArg {
+        names: &["wip"],
+        stat: true,
+    }
+    .run(&mut r)
+    .unwrap();
+    assert_eq!(
+        shelves[0].files,
+        vec![FileStat {
+            path: HgPath::from("a"),
+            insertions: 2,
+            deletions: 1,
+            binary: false,
+        }]
+    );
+
+    assert_eq!(
+        unshelve::Arg {
+            name: "wip",
+            keep: true,
+            ..Default::default()
+        }
+        .run(&mut r)
+        .unwrap(),
+        Unshelve::Conflicts(vec![HgPath::from("a")])
+    );
+    assert_eq!(
+        unshelve::Arg {
+            abort: true,
+            ..Default::default()
+        }
+        .run(&mut r)
+        .unwrap(),
+        Unshelve::Unshelved
+    );
+    assert!(r.is_done());
+}
+
+#[test]
+fn test_shelve() {
+    let mut c = common::TestClient::new("shelve", &[]);
+    c.append("a", &["a\n"]);
+    hg!(c.client, commit, message = "first", addremove = true).unwrap();
+    c.append("a", &["b\n"]);
+
+    assert_eq!(
+        hg!(c.client, shelve, name = "wip").unwrap(),
+        Some("wip".to_string())
+    );
+    assert_eq!(c.read("a"), "a\n");
+    assert_eq!(hg!(c.client, shelve).unwrap(), None);
+
+    let shelves = c
+        .client
+        .shelve_list(shelve::ListArg {
+            stat: true,
+            ..Default::default()
+        })
+        .unwrap();
+    assert_eq!(shelves.len(), 1);
+    assert_eq!(shelves[0].name, "wip");
+    assert_eq!(shelves[0].description, "changes to: first");
+    assert_eq!(shelves[0].files[0].insertions, 1);
+
+    assert_eq!(hg!(c.client, unshelve).unwrap(), Unshelve::Unshelved);
+    assert_eq!(c.read("a"), "a\nb\n");
+}
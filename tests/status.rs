@@ -40,35 +40,35 @@ fn test_one_of_each() {
     let expected = vec![
         Status {
             code: Code::Modified,
-            filename: "modified".to_string(),
+            filename: "modified".into(),
         },
         Status {
             code: Code::Added,
-            filename: "added".to_string(),
+            filename: "added".into(),
         },
         Status {
             code: Code::Removed,
-            filename: "removed".to_string(),
+            filename: "removed".into(),
         },
         Status {
             code: Code::Clean,
-            filename: ".hgignore".to_string(),
+            filename: ".hgignore".into(),
         },
         Status {
             code: Code::Clean,
-            filename: "clean".to_string(),
+            filename: "clean".into(),
         },
         Status {
             code: Code::Missing,
-            filename: "missing".to_string(),
+            filename: "missing".into(),
         },
         Status {
             code: Code::NotTracked,
-            filename: "untracked".to_string(),
+            filename: "untracked".into(),
         },
         Status {
             code: Code::Ignored,
-            filename: "ignored".to_string(),
+            filename: "ignored".into(),
         },
     ];
 
@@ -89,11 +89,11 @@ fn test_copy() {
         vec![
             Status {
                 code: Code::Added,
-                filename: "dest".to_string(),
+                filename: "dest".into(),
             },
             Status {
                 code: Code::Origin,
-                filename: "source".to_string(),
+                filename: "source".into(),
             }
         ]
     );
@@ -110,11 +110,11 @@ fn test_copy_origin_space() {
         vec![
             Status {
                 code: Code::Added,
-                filename: "dest".to_string(),
+                filename: "dest".into(),
             },
             Status {
                 code: Code::Origin,
-                filename: "s ource".to_string(),
+                filename: "s ource".into(),
             }
         ]
     );
@@ -0,0 +1,177 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this file,
+// You can obtain one at http://mozilla.org/MPL/2.0/.
+
+extern crate hglib;
+
+use std::io::{ErrorKind, Read};
+
+use crate::hglib::{
+    archive, bundle, cat, commit, diff, export, hg,
+    replay::{Replay, Session},
+    HglibError,
+};
+
+mod common;
+
+#[test]
+fn test_cat() {
+    let mut r = Replay::new(vec![
+        Session::new(&["cat", "-r", "1", "--", "a"])
+            .out(b"foo\n")
+            .out(b"bar\n")
+            .ret(0),
+        Session::new(&["cat", "--", "a"])
+            .out(b"foo\n")
+            .out(b"bar\n")
+            .ret(0),
+        Session::new(&["cat", "--", "b"])
+            .out(b"foo")
+            .err(b"b: no such file in rev 000000000000\n")
+            .ret(1),
+    ]);
+    let mut data = Vec::new();
+    let size = cat::Arg {
+        files: &["a"],
+        rev: &["1"],
+        ..Default::default()
+    }
+    .run_to(&mut r, &mut data)
+    .unwrap();
+    assert_eq!(size, 8);
+    assert_eq!(data, b"foo\nbar\n");
+
+    let mut reader = cat::Arg {
+        files: &["a"],
+        ..Default::default()
+    }
+    .reader(&mut r)
+    .unwrap();
+    let mut buf = [0; 3];
+    reader.read_exact(&mut buf).unwrap();
+    assert_eq!(&buf, b"foo");
+    let mut data = String::new();
+    reader.read_to_string(&mut data).unwrap();
+    assert_eq!(data, "\nbar\n");
+    drop(reader);
+
+    let mut reader = cat::Arg {
+        files: &["b"],
+        ..Default::default()
+    }
+    .reader(&mut r)
+    .unwrap();
+    let mut data = Vec::new();
+    let err = reader.read_to_end(&mut data).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::Other);
+    assert_eq!(data, b"foo");
+    drop(reader);
+    assert!(r.is_done());
+
+    match (cat::Arg {
+        files: &["a"],
+        output: "%s.out",
+        ..Default::default()
+    })
+    .run_to(&mut r, &mut Vec::new())
+    {
+        Err(HglibError::InvalidArg(_)) => {}
+        x => panic!("Expected an invalid argument: {:?}", x),
+    }
+}
+
+#[test]
+fn test_diff_export() {
+    let mut r = Replay::new(vec![
+        Session::new(&["diff", "-c", "1", "-g"])
+            .out(b"diff --git a/a b/a\n")
+            .out(b"--- a/a\n+++ b/a\n")
+            .ret(0),
+        Session::new(&["export", "--", "0", "1"])
+            .out(b"# HG changeset patch\n")
+            .out(b"# HG changeset patch\n")
+            .ret(0),
+    ]);
+    let mut data = Vec::new();
+    diff::Arg {
+        change: "1",
+        git: true,
+        ..Default::default()
+    }
+    .run_to(&mut r, &mut data)
+    .unwrap();
+    assert_eq!(data, b"diff --git a/a b/a\n--- a/a\n+++ b/a\n");
+
+    let mut data = Vec::new();
+    export::Arg {
+        revs: &["0", "1"],
+        ..Default::default()
+    }
+    .run_to(&mut r, &mut data)
+    .unwrap();
+    assert_eq!(data, b"# HG changeset patch\n# HG changeset patch\n");
+    assert!(r.is_done());
+
+    assert!(diff::Arg {
+        change: "1",
+        revs: &["0"],
+        ..Default::default()
+    }
+    .run_to(&mut r, &mut Vec::new())
+    .is_err());
+}
+
+#[test]
+fn test_archive() {
+    let mut r = Replay::new(vec![Session::new(&["archive", "-t", "tgz", "--", "-"])
+        .out(b"\x1f\x8b\x08")
+        .out(b"\x00")
+        .ret(0)]);
+    let mut data = Vec::new();
+    let size = archive::Arg {
+        dest: "ignored.tgz",
+        typ: "tgz",
+        ..Default::default()
+    }
+    .run_to(&mut r, &mut data)
+    .unwrap();
+    assert_eq!(size, 4);
+    assert_eq!(data, b"\x1f\x8b\x08\x00");
+    assert!(r.is_done());
+
+    assert!(archive::Arg::default()
+        .run_to(&mut r, &mut Vec::new())
+        .is_err());
+}
+
+#[test]
+fn test_bundle() {
+    let mut c = common::TestClient::new("stream_bundle", &[]);
+    c.append("a", &["a"]);
+    hg!(c.client, commit, message = "first", addremove = true).unwrap();
+
+    let mut data = Vec::new();
+    assert!(c
+        .client
+        .bundle_to(
+            bundle::Arg {
+                all: true,
+                ..Default::default()
+            },
+            &mut data
+        )
+        .unwrap());
+    assert!(data.starts_with(b"HG"));
+
+    let mut data = Vec::new();
+    c.client
+        .cat_to(
+            cat::Arg {
+                files: &["a"],
+                ..Default::default()
+            },
+            &mut data,
+        )
+        .unwrap();
+    assert_eq!(data, b"a");
+}
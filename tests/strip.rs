@@ -0,0 +1,163 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this file,
+// You can obtain one at http://mozilla.org/MPL/2.0/.
+
+extern crate hglib;
+
+use std::path::PathBuf;
+
+use crate::hglib::{
+    commit, hg, log,
+    prune::{self, Prune},
+    replay::{Replay, Session},
+    strip,
+};
+
+mod common;
+
+#[test]
+fn test_replay() {
+    let mut r = Replay::new(vec![
+        Session::new(&["strip", "-k", "--config", "extensions.strip=", "--", "1"])
+            .out(b"saved backup bundle to /repo/.hg/strip-backup/abcdef-backup.hg\n")
+            .ret(0),
+        Session::new(&[
+            "strip",
+            "--no-backup",
+            "--config",
+            "extensions.strip=",
+            "--",
+            "2",
+        ])
+        .ret(0),
+        Session::new(&["showconfig", "--", "extensions"])
+            .out(b"extensions.evolve=\n")
+            .ret(0),
+        Session::new(&["prune", "-r", "3", "-s", "4", "--"])
+            .out(b"1 changesets pruned\n")
+            .ret(0),
+        Session::new(&["showconfig", "--", "extensions"]).ret(1),
+        Session::new(&["showconfig", "--", "extensions"]).ret(1),
+        Session::new(&["showconfig", "--", "extensions"])
+            .out(b"extensions.evolve=!\n")
+            .ret(0),
+        Session::new(&["strip", "--config", "extensions.strip=", "--", "3"])
+            .out(b"saved backup bundle to /repo/.hg/strip-backup/012345-backup.hg\n")
+            .ret(0),
+    ]);
+
+    assert_eq!(
+        strip::Arg {
+            revs: &["1"],
+            keep: true,
+            ..Default::default()
+        }
+        .run(&mut r)
+        .unwrap(),
+        Some(PathBuf::from("/repo/.hg/strip-backup/abcdef-backup.hg"))
+    );
+    assert_eq!(
+        strip::Arg {
+            revs: &["2"],
+            nobackup: true,
+            ..Default::default()
+        }
+        .run(&mut r)
+        .unwrap(),
+        None
+    );
+
+    assert_eq!(
+        prune::Arg {
+            revs: &["3"],
+            successors: &["4"],
+            ..Default::default()
+        }
+        .run(&mut r)
+        .unwrap(),
+        Prune::Pruned
+    );
+    assert!(prune::Arg {
+        revs: &["3"],
+        successors: &["4"],
+        ..Default::default()
+    }
+    .run(&mut r)
+    .is_err());
+    // The changesets aren't stripped without evolve unless it's explicitly allowed
+    assert!(prune::Arg {
+        revs: &["3"],
+        ..Default::default()
+    }
+    .run(&mut r)
+    .is_err());
+    assert_eq!(
+        prune::Arg {
+            revs: &["3"],
+            fallback_to_strip: true,
+            ..Default::default()
+        }
+        .run(&mut r)
+        .unwrap(),
+        Prune::Stripped(Some(PathBuf::from(
+            "/repo/.hg/strip-backup/012345-backup.hg"
+        )))
+    );
+    assert!(r.is_done());
+}
+
+#[cfg(unix)]
+#[test]
+fn test_replay_non_utf8_backup() {
+    use std::os::unix::ffi::OsStrExt;
+
+    let mut r = Replay::new(vec![Session::new(&[
+        "strip",
+        "--config",
+        "extensions.strip=",
+        "--",
+        "1",
+    ])
+    .out(b"saved backup bundle to /caf\xe9/.hg/strip-backup/abcdef-backup.hg\n")
+    .ret(0)]);
+    let backup = strip::Arg {
+        revs: &["1"],
+        ..Default::default()
+    }
+    .run(&mut r)
+    .unwrap()
+    .unwrap();
+    assert_eq!(
+        backup.as_os_str().as_bytes(),
+        b"/caf\xe9/.hg/strip-backup/abcdef-backup.hg"
+    );
+    assert!(r.is_done());
+}
+
+#[test]
+fn test_strip() {
+    let mut c = common::TestClient::new("strip", &[]);
+    c.append("a", &["a\n"]);
+    hg!(c.client, commit, message = "first", addremove = true).unwrap();
+    c.append("a", &["b\n"]);
+    hg!(c.client, commit, message = "second").unwrap();
+
+    let backup = hg!(c.client, strip, revs = &["1"]).unwrap().unwrap();
+    assert!(backup.exists());
+    assert_eq!(hg!(c.client, log).unwrap().len(), 1);
+
+    c.append("a", &["c\n"]);
+    hg!(c.client, commit, message = "third").unwrap();
+    assert_eq!(
+        hg!(c.client, strip, revs = &["1"], nobackup = true).unwrap(),
+        None
+    );
+
+    c.append("a", &["d\n"]);
+    hg!(c.client, commit, message = "fourth").unwrap();
+    match hg!(c.client, prune, revs = &["1"], fallback_to_strip = true).unwrap() {
+        Prune::Pruned => {}
+        Prune::Stripped(backup) => assert!(backup.unwrap().exists()),
+    }
+    assert_eq!(hg!(c.client, log).unwrap().len(), 1);
+}
@@ -7,9 +7,10 @@ extern crate hglib;
 use crate::hglib::{
     commit::{self, Commit},
     hg, parents,
+    replay::{Replay, Session},
     status::{self, Code, Status},
     update::{self, Update},
-    Runner,
+    HglibError, Runner,
 };
 
 mod common;
@@ -35,6 +36,7 @@ fn test_basic() {
             merged: 0,
             removed: 0,
             unresolved: 0,
+            ..Default::default()
         }
     );
 }
@@ -54,12 +56,15 @@ fn test_unresolved() {
             merged: 0,
             removed: 0,
             unresolved: 1,
+            conflicts: vec!["a".into()],
+            merging: true,
+            ..Default::default()
         }
     );
 
     assert!(hg![c.client, status].unwrap().contains(&Status {
         code: Code::Modified,
-        filename: "a".to_string(),
+        filename: "a".into(),
     }));
 }
 
@@ -84,6 +89,7 @@ fn test_merge() {
             merged: 1,
             removed: 0,
             unresolved: 0,
+            ..Default::default()
         }
     );
 
@@ -91,7 +97,7 @@ fn test_merge() {
         hg![c.client, status].unwrap(),
         vec![Status {
             code: Code::Modified,
-            filename: "a".to_string(),
+            filename: "a".into(),
         }]
     );
 }
@@ -153,6 +159,7 @@ fn test_basic_plain() {
             merged: 0,
             removed: 0,
             unresolved: 0,
+            ..Default::default()
         }
     );
 }
@@ -180,6 +187,57 @@ fn test_disabled_largefiles() {
             merged: 0,
             removed: 0,
             unresolved: 0,
+            ..Default::default()
         }
     );
 }
+
+#[test]
+fn test_replay() {
+    let mut r = Replay::new(vec![
+        Session::new(&["update", "-r", "foo", "--"])
+            .out(b"1 files updated, 0 files merged, 0 files removed, 0 files unresolved\n")
+            .out(b"(activating bookmark foo)\n")
+            .ret(0),
+        Session::new(&["update", "--"])
+            .out(b"merging a\nmerging b\n")
+            .err(b"warning: conflicts while merging a! (edit, then use 'hg resolve --mark')\n")
+            .err(b"merging b failed!\n")
+            .out(b"0 files updated, 0 files merged, 0 files removed, 2 files unresolved\n")
+            .out(b"(leaving bookmark foo)\n")
+            .out(b"use 'hg resolve' to retry unresolved file merges\n")
+            .ret(1),
+        Session::new(&["update", "--"])
+            .out(b"something new\n")
+            .ret(0),
+    ]);
+
+    assert_eq!(
+        update::Arg {
+            rev: "foo",
+            ..Default::default()
+        }
+        .run(&mut r)
+        .unwrap(),
+        Update {
+            updated: 1,
+            activated: Some("foo".to_string()),
+            ..Default::default()
+        }
+    );
+    assert_eq!(
+        update::Arg::default().run(&mut r).unwrap(),
+        Update {
+            unresolved: 2,
+            conflicts: vec!["a".into(), "b".into()],
+            merging: true,
+            deactivated: Some("foo".to_string()),
+            ..Default::default()
+        }
+    );
+    match update::Arg::default().run(&mut r) {
+        Err(HglibError::Parse { .. }) => {}
+        x => panic!("Expected a parse error: {:?}", x),
+    }
+    assert!(r.is_done());
+}